@@ -1,70 +1,418 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
-use bevy::prelude::default;
+use bevy::asset::{AssetLoader, AssetPath, Handle, LoadContext, LoadedAsset};
+use bevy::prelude::{default, warn, Image};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::text::Font;
+use bevy::ui::Val;
 use bevy::utils::BoxedFuture;
 use quick_xml::events::attributes::Attribute;
-use quick_xml::events::{BytesStart, Event};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::name::QName;
 use quick_xml::reader::Reader;
+use quick_xml::Writer;
+
+use crate::guise::template::{ParamType, TemplateParam};
+
+use super::style::{FontRef, Keyframes, PartialStyle, Selector, StyleAttr};
+use super::template::{
+    ElementNode, IncludeNode, Template, TemplateNode, TemplateNodeList, TextNode,
+};
+use super::view::{accessibility_role, relative_asset_path};
+use super::{GuiseError, GuiseFlags};
+
+/// The default asset of a guise file, mapping the ids of its templates and named styles to
+/// the labeled assets which hold them.
+#[derive(Debug, TypeUuid, TypePath, Default)]
+#[uuid = "9942b73a-20ad-4b0d-a0a1-95ac53019b3f"]
+pub struct GuiseDocument {
+    pub templates: HashMap<String, Handle<Template>>,
+    pub styles: HashMap<String, Handle<PartialStyle>>,
+
+    /// The fonts declared with `<font>` elements, by id. Holding these keeps the fonts loaded
+    /// for as long as the document is.
+    pub fonts: HashMap<String, Handle<Font>>,
+
+    /// The images named by `background-image` attributes, which likewise stay loaded for as
+    /// long as the document is.
+    pub images: Vec<Handle<Image>>,
+}
+
+impl GuiseDocument {
+    /// The handle of the top-level template with the given id.
+    pub fn template_handle(&self, id: &str) -> Option<Handle<Template>> {
+        self.templates.get(id).cloned()
+    }
+
+    /// The handle of the top-level `<style>` with the given id.
+    pub fn style_handle(&self, id: &str) -> Option<Handle<PartialStyle>> {
+        self.styles.get(id).cloned()
+    }
+}
+
+/// The contents of a guise document, parsed but not yet added to the asset server. This is what
+/// the loader produces from a file, and can also be built from a string, e.g. for generated UI.
+#[derive(Debug, Default)]
+pub struct ParsedDocument {
+    /// Top-level styles, by id, in the order they were declared.
+    pub styles: Vec<(String, PartialStyle)>,
+
+    /// Templates, by id, in the order they were declared.
+    pub templates: Vec<(String, Template)>,
+
+    /// Paths of the font files declared with `<font>` elements, by id.
+    pub fonts: Vec<(String, AssetPath<'static>)>,
+
+    /// Paths of the images named by `background-image` attributes, resolved against the
+    /// document in the same way as fonts.
+    pub images: Vec<AssetPath<'static>>,
+
+    /// Problems which were skipped over rather than failing the parse.
+    pub warnings: Vec<String>,
+
+    /// Other files included by each template, by template id, which the template depends on.
+    dependencies: HashMap<String, Vec<AssetPath<'static>>>,
+}
+
+impl ParsedDocument {
+    /// Parse a guise document from a string. `path` is the asset path the document is treated
+    /// as having been loaded from, which includes are resolved against.
+    pub fn parse(source: &str, path: &Path) -> Result<ParsedDocument, GuiseError> {
+        let mut visitor = GuiseXmlVisitor::new(source.as_bytes());
+        visitor.path = path.to_path_buf();
+        visitor.visit()?;
+        Ok(visitor.parsed)
+    }
+
+    /// Write the document back out as XML, which `parse` reads as an equivalent document.
+    /// This is not the original source: comments and formatting are gone, parts which `when`
+    /// disabled are left out, mixins are written out in full, and `<media>` styles refer to
+    /// generated breakpoints, one for each distinct range of widths.
+    pub fn to_xml(&self) -> String {
+        // Keyframes and breakpoints are only reachable through the styles which refer to them,
+        // and must be declared before those styles.
+        let mut keyframes: Vec<(String, Arc<Keyframes>)> = Vec::new();
+        let mut breakpoints: Vec<(f32, f32)> = Vec::new();
+        let inline_styles = self.templates.iter().flat_map(|(_, template)| {
+            template.iter_nodes().filter_map(|node| match node {
+                TemplateNode::Element(elt) | TemplateNode::Fragment(elt) => {
+                    elt.inline_styles.as_deref()
+                }
+                TemplateNode::Text(text) => text.inline_styles.as_deref(),
+                TemplateNode::Include(_) => None,
+            })
+        });
+        for style in self
+            .styles
+            .iter()
+            .map(|(_, style)| style)
+            .chain(inline_styles)
+        {
+            collect_references(style, &mut keyframes, &mut breakpoints);
+        }
+
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        let root = if self.templates.is_empty() {
+            "styles"
+        } else {
+            "templates"
+        };
+        write_event(&mut writer, Event::Start(BytesStart::new(root)));
+
+        for (id, path) in self.fonts.iter() {
+            let mut elem = BytesStart::new("font");
+            elem.push_attribute(("id", id.as_str()));
+            elem.push_attribute(("src", path.path().to_str().unwrap()));
+            write_event(&mut writer, Event::Empty(elem));
+        }
+
+        for (i, (min, max)) in breakpoints.iter().enumerate() {
+            let mut elem = BytesStart::new("breakpoint");
+            elem.push_attribute(("id", format!("breakpoint-{}", i + 1).as_str()));
+            if *min > 0. {
+                elem.push_attribute(("min-width", format!("{}px", min).as_str()));
+            }
+            if max.is_finite() {
+                elem.push_attribute(("max-width", format!("{}px", max).as_str()));
+            }
+            write_event(&mut writer, Event::Empty(elem));
+        }
+
+        for (name, frames) in keyframes.iter() {
+            let mut elem = BytesStart::new("keyframes");
+            elem.push_attribute(("id", name.as_str()));
+            write_event(&mut writer, Event::Start(elem));
+            for frame in frames.frames() {
+                let mut elem = BytesStart::new("keyframe");
+                elem.push_attribute(("offset", format!("{}%", frame.offset * 100.).as_str()));
+                frame
+                    .style
+                    .attrs()
+                    .iter()
+                    .for_each(|attr| attr.write_xml(&mut elem));
+                write_event(&mut writer, Event::Empty(elem));
+            }
+            write_event(&mut writer, Event::End(BytesEnd::new("keyframes")));
+        }
+
+        for (id, style) in self.styles.iter() {
+            let mut elem = BytesStart::new("style");
+            elem.push_attribute(("id", id.as_str()));
+            write_style(&mut writer, elem, style, &breakpoints);
+        }
 
-use crate::guise::template::TemplateParam;
+        for (id, template) in self.templates.iter() {
+            let mut elem = BytesStart::new("template");
+            elem.push_attribute(("id", id.as_str()));
+            write_event(&mut writer, Event::Start(elem));
+            let mut params: Vec<_> = template.params.iter().collect();
+            params.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, param) in params {
+                let mut elem = BytesStart::new("param");
+                elem.push_attribute(("name", name.as_str()));
+                elem.push_attribute(("type", param.r#type.as_str()));
+                write_event(&mut writer, Event::Empty(elem));
+            }
+            write_event(&mut writer, Event::Start(BytesStart::new("content")));
+            write_nodes(&mut writer, &template.children);
+            write_event(&mut writer, Event::End(BytesEnd::new("content")));
+            write_event(&mut writer, Event::End(BytesEnd::new("template")));
+        }
 
-use super::style::{PartialStyle, Selector, StyleAttr};
-use super::template::{ElementNode, Template, TemplateNode, TemplateNodeList, TextNode};
-use super::GuiseError;
+        write_event(&mut writer, Event::End(BytesEnd::new(root)));
+        String::from_utf8(writer.into_inner().into_inner()).unwrap()
+    }
 
-#[derive(Default)]
-pub struct GuiseLoader;
+    /// Add the styles and templates to the asset being loaded as labeled assets, and set the
+    /// default asset to a `GuiseDocument` which refers to them.
+    fn add_to(mut self, load_context: &mut LoadContext) {
+        let mut document = GuiseDocument::default();
+        for (id, style) in self.styles {
+            let handle = load_context.set_labeled_asset(&id, LoadedAsset::new(style));
+            document.styles.insert(id, handle);
+        }
+        for (id, template) in self.templates {
+            let includes = self.dependencies.remove(&id).unwrap_or_default();
+            let handle = load_context
+                .set_labeled_asset(&id, LoadedAsset::new(template).with_dependencies(includes));
+            document.templates.insert(id, handle);
+        }
+        let mut dependencies = Vec::with_capacity(self.fonts.len() + self.images.len());
+        for (id, path) in self.fonts {
+            document
+                .fonts
+                .insert(id, load_context.get_handle(path.get_id()));
+            dependencies.push(path);
+        }
+        for path in self.images {
+            document.images.push(load_context.get_handle(path.get_id()));
+            dependencies.push(path);
+        }
+        load_context.set_default_asset(LoadedAsset::new(document).with_dependencies(dependencies));
+    }
+}
+
+pub struct GuiseLoader {
+    /// If true, invalid color values are an error. Otherwise they are skipped with a warning.
+    pub strict: bool,
+
+    /// Flags which enable the parts of a document marked with `when`. `GuisePlugin` shares
+    /// these with the `GuiseFlags` resource.
+    pub flags: GuiseFlags,
+
+    /// File extensions handled by this loader, without the leading dot.
+    extensions: Vec<&'static str>,
+}
+
+/// The extension of guise files, unless configured otherwise.
+pub(crate) const DEFAULT_EXTENSION: &str = "guise.xml";
+
+/// The extension of style libraries: guise files with a `<styles>` root, which hold styles but
+/// no templates.
+pub(crate) const STYLE_EXTENSION: &str = "style.xml";
+
+impl GuiseLoader {
+    /// Create a loader which handles files with the given extensions instead of the default.
+    /// Bevy asks loaders for their extensions as `&str`s borrowed from the loader, so the names
+    /// are leaked; this is fine since a loader is only registered once per app.
+    pub fn with_extensions(extensions: &[String]) -> Self {
+        Self {
+            strict: false,
+            flags: GuiseFlags::default(),
+            extensions: extensions
+                .iter()
+                .map(|ext| &*Box::leak(ext.clone().into_boxed_str()))
+                .collect(),
+        }
+    }
+}
+
+impl Default for GuiseLoader {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            flags: GuiseFlags::default(),
+            extensions: vec![DEFAULT_EXTENSION, STYLE_EXTENSION],
+        }
+    }
+}
 
 const ATTR_ID: QName = QName(b"id");
 const ATTR_NAME: QName = QName(b"name");
 const ATTR_TYPE: QName = QName(b"type");
 const ATTR_SELECTOR: QName = QName(b"selector");
 const ATTR_CONTROLLER: QName = QName(b"controller");
+const ATTR_OFFSET: QName = QName(b"offset");
+const ATTR_APPLY: QName = QName(b"apply");
+const ATTR_SRC: QName = QName(b"src");
+const ATTR_TAB_INDEX: QName = QName(b"tab-index");
+const ATTR_BREAKPOINT: QName = QName(b"breakpoint");
+const ATTR_MIN_WIDTH: QName = QName(b"min-width");
+const ATTR_MAX_WIDTH: QName = QName(b"max-width");
+const ATTR_WHEN: QName = QName(b"when");
 
 impl AssetLoader for GuiseLoader {
+    /// Parses directly out of the byte slice handed over by the asset server: events borrow
+    /// from `bytes`, so no intermediate buffer is allocated for the document.
     fn load<'a>(
         &'a self,
         bytes: &'a [u8],
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
         Box::pin(async move {
-            let mut visitor = GuiseXmlVisitor::<'a> {
-                reader: Reader::from_reader(bytes),
-            };
-            match visitor.visit(load_context) {
-                Ok(()) => Ok(()),
-                Err(e) => {
-                    panic!("Parsing error: {:?}", e);
+            let mut visitor = GuiseXmlVisitor::new(bytes);
+            visitor.strict = self.strict;
+            visitor.flags = self.flags.active();
+            visitor.path = load_context.path().to_path_buf();
+            match visitor.visit() {
+                Ok(()) => {
+                    visitor.parsed.add_to(load_context);
+                    Ok(())
                 }
+                Err(e) => Err(e.into()),
             }
         })
     }
 
     fn extensions(&self) -> &[&str] {
-        &["guise.xml"]
+        &self.extensions
     }
 }
 
 struct GuiseXmlVisitor<'a> {
+    source: &'a [u8],
     reader: Reader<&'a [u8]>,
+
+    /// Keyframe sets declared so far, by id. Animations can only refer to keyframes which
+    /// were declared earlier in the file.
+    keyframes: HashMap<String, Arc<Keyframes>>,
+
+    /// Top-level styles declared so far, by id, for use as mixins.
+    styles: HashMap<String, PartialStyle>,
+
+    /// Window width breakpoints declared so far, by id, as `(min, max)` ranges in logical
+    /// pixels. Like keyframes, a breakpoint must be declared before the styles that use it.
+    breakpoints: HashMap<String, (f32, f32)>,
+
+    /// Fonts declared so far, by id. Like keyframes, a font must be declared before the styles
+    /// that use it.
+    fonts: HashMap<String, AssetPath<'static>>,
+
+    /// See `GuiseLoader::strict`.
+    strict: bool,
+
+    /// Active flags. Styles, templates and nodes whose `when` flags aren't all active are
+    /// skipped.
+    flags: HashSet<String>,
+
+    /// The styles and templates parsed so far, and any warnings.
+    parsed: ParsedDocument,
+
+    /// Path of the file being loaded, which includes are resolved against.
+    path: PathBuf,
+
+    /// Other files included by the template being visited, which it depends on.
+    includes: Vec<AssetPath<'static>>,
+
+    /// Labels of templates in this file included by the template being visited.
+    local_includes: Vec<String>,
+
+    /// Labels of the templates in this file included by each template, by template id.
+    template_includes: HashMap<String, Vec<String>>,
 }
 
 impl<'a> GuiseXmlVisitor<'a> {
-    fn visit(&mut self, load_context: &'a mut LoadContext) -> Result<(), GuiseError> {
+    fn new(source: &'a [u8]) -> Self {
+        Self {
+            source,
+            reader: Reader::from_reader(source),
+            keyframes: HashMap::new(),
+            styles: HashMap::new(),
+            breakpoints: HashMap::new(),
+            fonts: HashMap::new(),
+            strict: false,
+            flags: HashSet::new(),
+            parsed: ParsedDocument::default(),
+            path: PathBuf::new(),
+            includes: Vec::new(),
+            local_includes: Vec::new(),
+            template_includes: HashMap::new(),
+        }
+    }
+
+    /// Convert an XML reader error into a `GuiseError` which records where it happened.
+    fn syntax_error(&self, err: quick_xml::Error) -> GuiseError {
+        let (line, column) = line_and_column(self.source, self.reader.buffer_position());
+        GuiseError::SyntaxError {
+            line,
+            column,
+            message: err.to_string(),
+        }
+    }
+
+    /// Unescape the value of an attribute, converting a malformed entity into a `GuiseError`.
+    fn unescape<'b>(&self, attr: &Attribute<'b>) -> Result<Cow<'b, str>, GuiseError> {
+        attr.unescape_value().map_err(|err| self.syntax_error(err))
+    }
+
+    /// True if all of the flags listed in the element's `when` attribute are active. Elements
+    /// without a `when` attribute are always enabled.
+    fn is_enabled(&self, e: &BytesStart) -> bool {
+        match require_attr(e, ATTR_WHEN) {
+            Ok(when) => when
+                .unescape_value()
+                .unwrap()
+                .split_whitespace()
+                .all(|flag| self.flags.contains(flag)),
+            Err(_) => true,
+        }
+    }
+
+    /// Skip over the contents of a disabled element.
+    fn skip<'b>(&mut self, e: &'b BytesStart) -> Result<(), GuiseError> {
+        match self.reader.read_to_end(e.name()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(self.syntax_error(err)),
+        }
+    }
+
+    fn visit(&mut self) -> Result<(), GuiseError> {
         loop {
             match self.reader.read_event() {
-                Err(e) => panic!(
-                    "Error at position {}: {:?}",
-                    self.reader.buffer_position(),
-                    e
-                ),
+                Err(e) => return Err(self.syntax_error(e)),
                 Ok(Event::Eof) => break,
 
                 Ok(Event::Start(e)) => match e.name().as_ref() {
                     b"templates" => {
-                        self.visit_templates(load_context)?;
+                        self.visit_templates(false)?;
+                    }
+
+                    b"styles" => {
+                        self.visit_templates(true)?;
                     }
 
                     _ => {
@@ -79,6 +427,8 @@ impl<'a> GuiseXmlVisitor<'a> {
                         self.reader.read_to_end(e.name()).unwrap();
                     }
 
+                    b"styles" => (),
+
                     _ => {
                         return Err(GuiseError::InvalidElement(
                             std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
@@ -87,41 +437,46 @@ impl<'a> GuiseXmlVisitor<'a> {
                 },
 
                 Ok(Event::End(e)) => match e.name().as_ref() {
-                    b"templates" => break,
+                    b"templates" | b"styles" => break,
 
                     _ => {
-                        panic!(
-                            "Unrecognized end tag: </{}>",
-                            std::str::from_utf8(e.name().as_ref()).unwrap()
-                        );
+                        return Err(GuiseError::MismatchedEnd(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
                     }
                 },
 
                 _ => (),
             }
         }
+
         Ok(())
     }
 
-    fn visit_templates<'b>(&mut self, load_context: &'b mut LoadContext) -> Result<(), GuiseError> {
+    /// Visit the children of the root element, which is `<styles>` rather than `<templates>`
+    /// if `styles_only` is true. Style libraries can't contain templates.
+    fn visit_templates(&mut self, styles_only: bool) -> Result<(), GuiseError> {
+        let root: &[u8] = if styles_only { b"styles" } else { b"templates" };
         loop {
             match self.reader.read_event() {
-                Err(e) => panic!(
-                    "Error at position {}: {:?}",
-                    self.reader.buffer_position(),
-                    e
-                ),
+                Err(e) => return Err(self.syntax_error(e)),
                 Ok(Event::Eof) => return Err(GuiseError::PrematureEof),
 
+                Ok(Event::Start(e)) if !self.is_enabled(&e) => self.skip(&e)?,
+                Ok(Event::Empty(e)) if !self.is_enabled(&e) => (),
+
                 Ok(Event::Start(e)) => match e.name().as_ref() {
-                    b"template" => {
-                        self.visit_template(&e, load_context)?;
+                    b"template" if !styles_only => {
+                        self.visit_template(&e)?;
                     }
 
                     b"style" => {
-                        let id = require_attr(&e, ATTR_ID)?.unescape_value().unwrap();
-                        let style = self.visit_style(&e, false)?;
-                        load_context.set_labeled_asset(&id, LoadedAsset::new(style));
+                        let named_style = self.visit_named_style(&e, false)?;
+                        self.parsed.styles.push(named_style);
+                    }
+
+                    b"keyframes" => {
+                        self.visit_keyframes(&e, false)?;
                     }
 
                     _ => {
@@ -133,9 +488,20 @@ impl<'a> GuiseXmlVisitor<'a> {
 
                 Ok(Event::Empty(e)) => match e.name().as_ref() {
                     b"style" => {
-                        let id = require_attr(&e, ATTR_ID)?.unescape_value().unwrap();
-                        let style = self.visit_style(&e, true)?;
-                        load_context.set_labeled_asset(&id, LoadedAsset::new(style));
+                        let named_style = self.visit_named_style(&e, true)?;
+                        self.parsed.styles.push(named_style);
+                    }
+
+                    b"keyframes" => {
+                        self.visit_keyframes(&e, true)?;
+                    }
+
+                    b"breakpoint" => {
+                        self.visit_breakpoint(&e)?;
+                    }
+
+                    b"font" => {
+                        self.visit_font(&e)?;
                     }
 
                     _ => {
@@ -146,38 +512,35 @@ impl<'a> GuiseXmlVisitor<'a> {
                 },
 
                 Ok(Event::End(e)) => match e.name().as_ref() {
-                    b"templates" => break,
+                    name if name == root => break,
 
                     _ => {
-                        panic!(
-                            "Unrecognized end tag: </{}>",
-                            std::str::from_utf8(e.name().as_ref()).unwrap()
-                        );
+                        return Err(GuiseError::MismatchedEnd(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
                     }
                 },
 
                 _ => (),
             }
         }
-        Ok(())
+
+        // Includes of other files can only be checked once they're loaded, which happens when
+        // the view is built.
+        match find_include_cycle(&self.template_includes) {
+            Some(chain) => Err(GuiseError::CircularReference(chain)),
+            None => Ok(()),
+        }
     }
 
-    fn visit_template<'b>(
-        &mut self,
-        e: &'b BytesStart,
-        load_context: &'b mut LoadContext,
-    ) -> Result<(), GuiseError> {
+    fn visit_template<'b>(&mut self, e: &'b BytesStart) -> Result<(), GuiseError> {
         let id = require_attr(e, ATTR_ID)?.unescape_value().unwrap();
 
         let mut result = Template::new();
 
         loop {
             match self.reader.read_event() {
-                Err(e) => panic!(
-                    "Error at position {}: {:?}",
-                    self.reader.buffer_position(),
-                    e
-                ),
+                Err(e) => return Err(self.syntax_error(e)),
                 Ok(Event::Eof) => return Err(GuiseError::PrematureEof),
 
                 Ok(Event::Start(e)) => match e.name().as_ref() {
@@ -204,9 +567,10 @@ impl<'a> GuiseXmlVisitor<'a> {
                         self.visit_param(&e, &mut result, true)?;
                     }
 
+                    // The style would share the template's label, which the template replaces.
+                    // TODO: Give templates their own style.
                     b"style" => {
-                        let style = self.visit_style(&e, true)?;
-                        load_context.set_labeled_asset(&id, LoadedAsset::new(style));
+                        self.visit_style(&e, true)?;
                     }
 
                     _ => {
@@ -220,10 +584,9 @@ impl<'a> GuiseXmlVisitor<'a> {
                     b"template" => break,
 
                     _ => {
-                        panic!(
-                            "Unrecognized end tag: </{}>",
-                            std::str::from_utf8(e.name().as_ref()).unwrap()
-                        );
+                        return Err(GuiseError::MismatchedEnd(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
                     }
                 },
 
@@ -231,11 +594,44 @@ impl<'a> GuiseXmlVisitor<'a> {
             }
         }
 
+        result.validate_references()?;
+
         // println!("Template element loaded: {}", id);
-        load_context.set_labeled_asset(&id, LoadedAsset::new(result));
+        self.parsed
+            .dependencies
+            .insert(id.to_string(), std::mem::take(&mut self.includes));
+        self.template_includes
+            .insert(id.to_string(), std::mem::take(&mut self.local_includes));
+        self.parsed.templates.push((id.to_string(), result));
         Ok(())
     }
 
+    /// Visit a top-level `<style>` element, and remember it so that later styles can apply it
+    /// as a mixin.
+    fn visit_named_style<'b>(
+        &mut self,
+        e: &'b BytesStart,
+        empty: bool,
+    ) -> Result<(String, PartialStyle), GuiseError> {
+        let id = require_attr(e, ATTR_ID)?
+            .unescape_value()
+            .unwrap()
+            .to_string();
+        if let Ok(apply) = require_attr(e, ATTR_APPLY) {
+            if apply
+                .unescape_value()
+                .unwrap()
+                .split_whitespace()
+                .any(|name| name == id)
+            {
+                return Err(GuiseError::CircularReference(format!("{} -> {}", id, id)));
+            }
+        }
+        let style = self.visit_style(e, empty)?;
+        self.styles.insert(id.clone(), style.clone());
+        Ok((id, style))
+    }
+
     fn visit_style<'b>(
         &mut self,
         e: &'b BytesStart,
@@ -257,18 +653,38 @@ impl<'a> GuiseXmlVisitor<'a> {
     ) -> Result<(), GuiseError> {
         for a in e.attributes() {
             if let Ok(attr) = a {
-                if attr.key != ATTR_ID && attr.key != ATTR_SELECTOR && attr.key.prefix().is_none() {
+                if attr.key != ATTR_ID
+                    && attr.key != ATTR_SELECTOR
+                    && attr.key != ATTR_OFFSET
+                    && attr.key != ATTR_BREAKPOINT
+                    && attr.key != ATTR_WHEN
+                    && attr.key.prefix().is_none()
+                {
                     let attr_name: &[u8] = attr.key.local_name().into_inner();
                     let attr_value: &str = &attr.unescape_value().unwrap();
+                    if attr.key == ATTR_APPLY {
+                        self.apply_mixins(attr_value, attrs)?;
+                        continue;
+                    }
+                    let key = attr.key.into_inner();
                     match StyleAttr::parse(attr_name, attr_value.trim()) {
-                        Ok(Some(attr)) => attrs.push(attr),
+                        Ok(Some(attr)) => {
+                            let attr = self
+                                .resolve_keyframes(attr)
+                                .and_then(|attr| self.resolve_font(attr))
+                                .map_err(|err| self.locate(key, err))?;
+                            attrs.push(self.resolve_image(attr));
+                        }
                         Ok(None) => {
                             // We didn't recognize the style attribute. That's an error
                             // for <style> element but not an error for inline styles, since
                             // nodes can have other attributes.
                             return Err(GuiseError::UnknownAttribute(attr_name.to_vec()));
                         }
-                        Err(err) => return Err(err),
+                        Err(err) => {
+                            let err = self.locate(key, err);
+                            self.recover(attr_name, attr_value, err)?
+                        }
                     }
                 }
             }
@@ -277,18 +693,60 @@ impl<'a> GuiseXmlVisitor<'a> {
         Ok(())
     }
 
+    /// Decide whether an attribute which failed to parse can be skipped. In non-strict mode,
+    /// invalid colors are logged and ignored, leaving the property unset.
+    fn recover(&mut self, name: &[u8], value: &str, err: GuiseError) -> Result<(), GuiseError> {
+        if self.strict || !StyleAttr::is_color(name) {
+            return Err(err);
+        }
+        let message = format!(
+            "Invalid color for '{}': '{}'",
+            std::str::from_utf8(name).unwrap(),
+            value
+        );
+        warn!("{}", message);
+        self.parsed.warnings.push(message);
+        Ok(())
+    }
+
+    /// Wrap an error in the value of the attribute named `key` in `GuiseError::InvalidAttribute`,
+    /// and fill in the line and column of the attribute. Events borrow from the source, so the
+    /// name points into it.
+    fn locate(&self, key: &[u8], err: GuiseError) -> GuiseError {
+        let location = (key.as_ptr() as usize)
+            .checked_sub(self.source.as_ptr() as usize)
+            .filter(|offset| *offset < self.source.len())
+            .map(|offset| line_and_column(self.source, offset));
+        match err {
+            GuiseError::InvalidAttribute {
+                name,
+                location: None,
+                error,
+            } => GuiseError::InvalidAttribute {
+                name,
+                location,
+                error,
+            },
+            err @ GuiseError::InvalidAttribute { .. } => err,
+            error => GuiseError::InvalidAttribute {
+                name: String::from_utf8_lossy(key).to_string(),
+                location,
+                error: Box::new(error),
+            },
+        }
+    }
+
     fn visit_style_children<'b>(&mut self, parent: &mut PartialStyle) -> Result<(), GuiseError> {
         let mut result = Template::new();
 
         loop {
             match self.reader.read_event() {
-                Err(e) => panic!(
-                    "Error at position {}: {:?}",
-                    self.reader.buffer_position(),
-                    e
-                ),
+                Err(e) => return Err(self.syntax_error(e)),
                 Ok(Event::Eof) => return Err(GuiseError::PrematureEof),
 
+                Ok(Event::Start(e)) if !self.is_enabled(&e) => self.skip(&e)?,
+                Ok(Event::Empty(e)) if !self.is_enabled(&e) => (),
+
                 Ok(Event::Start(e)) => match e.name().as_ref() {
                     b"style" => {
                         let selector = require_attr(&e, ATTR_SELECTOR)?.unescape_value().unwrap();
@@ -297,6 +755,12 @@ impl<'a> GuiseXmlVisitor<'a> {
                         parent.add_selector(selector, style);
                     }
 
+                    b"media" => {
+                        let selector = self.media_selector(&e)?;
+                        let style = self.visit_style(&e, false)?;
+                        parent.add_selector(selector, style);
+                    }
+
                     b"content" => {
                         self.visit_node_list(&e, &mut result.children)?;
                     }
@@ -316,6 +780,12 @@ impl<'a> GuiseXmlVisitor<'a> {
                         parent.add_selector(selector, style);
                     }
 
+                    b"media" => {
+                        let selector = self.media_selector(&e)?;
+                        let style = self.visit_style(&e, true)?;
+                        parent.add_selector(selector, style);
+                    }
+
                     _ => {
                         return Err(GuiseError::InvalidElement(
                             std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
@@ -324,13 +794,12 @@ impl<'a> GuiseXmlVisitor<'a> {
                 },
 
                 Ok(Event::End(e)) => match e.name().as_ref() {
-                    b"style" => break,
+                    b"style" | b"media" => break,
 
                     _ => {
-                        panic!(
-                            "Unrecognized end tag: </{}>",
-                            std::str::from_utf8(e.name().as_ref()).unwrap()
-                        );
+                        return Err(GuiseError::MismatchedEnd(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
                     }
                 },
 
@@ -343,6 +812,171 @@ impl<'a> GuiseXmlVisitor<'a> {
         Ok(())
     }
 
+    /// Visit a top-level `<breakpoint>`, a named range of window widths for `<media>` styles.
+    /// Either bound may be omitted.
+    fn visit_breakpoint<'b>(&mut self, e: &'b BytesStart) -> Result<(), GuiseError> {
+        let id = require_attr(e, ATTR_ID)?.unescape_value().unwrap();
+        let width = |name: QName, default: f32| match require_attr(e, name) {
+            Ok(attr) => match StyleAttr::parse_val(attr.unescape_value().unwrap().trim())? {
+                Val::Px(width) => Ok(width),
+                _ => Err(GuiseError::InvalidAttributeValue(
+                    attr.unescape_value().unwrap().to_string(),
+                )),
+            },
+            Err(_) => Ok(default),
+        };
+        let range = (
+            width(ATTR_MIN_WIDTH, 0.)?,
+            width(ATTR_MAX_WIDTH, f32::INFINITY)?,
+        );
+        self.breakpoints.insert(id.to_string(), range);
+        Ok(())
+    }
+
+    /// Visit a top-level `<font>`, which names a font file so that styles can refer to it by
+    /// id. Paths starting with `./` or `../` are relative to this file.
+    fn visit_font<'b>(&mut self, e: &'b BytesStart) -> Result<(), GuiseError> {
+        let id = require_attr(e, ATTR_ID)?.unescape_value().unwrap();
+        let src = require_attr(e, ATTR_SRC)?.unescape_value().unwrap();
+        let base = AssetPath::new_ref(&self.path, None);
+        let path = relative_asset_path(&base, None, &src).to_owned();
+        self.fonts.insert(id.to_string(), path.clone());
+        self.parsed.fonts.push((id.to_string(), path));
+        Ok(())
+    }
+
+    /// The selector for a `<media>` style, which matches while the window width is within the
+    /// breakpoint it names.
+    fn media_selector<'b>(&self, e: &'b BytesStart) -> Result<Selector, GuiseError> {
+        let name = require_attr(e, ATTR_BREAKPOINT)?.unescape_value().unwrap();
+        match self.breakpoints.get(name.as_ref()) {
+            Some(&(min, max)) => Ok(Selector::WindowWidth(min, max)),
+            None => Err(GuiseError::InvalidAttributeValue(name.to_string())),
+        }
+    }
+
+    fn visit_keyframes<'b>(&mut self, e: &'b BytesStart, empty: bool) -> Result<(), GuiseError> {
+        let id = self.unescape(&require_attr(e, ATTR_ID)?)?;
+        let mut keyframes = Keyframes::new();
+
+        while !empty {
+            match self.reader.read_event() {
+                Err(e) => return Err(self.syntax_error(e)),
+                Ok(Event::Eof) => return Err(GuiseError::PrematureEof),
+
+                Ok(Event::Start(e)) => match e.name().as_ref() {
+                    b"keyframe" => {
+                        self.visit_keyframe(&e, &mut keyframes)?;
+                        self.skip(&e)?;
+                    }
+
+                    _ => {
+                        return Err(GuiseError::InvalidElement(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
+                    }
+                },
+
+                Ok(Event::Empty(e)) => match e.name().as_ref() {
+                    b"keyframe" => {
+                        self.visit_keyframe(&e, &mut keyframes)?;
+                    }
+
+                    _ => {
+                        return Err(GuiseError::InvalidElement(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
+                    }
+                },
+
+                Ok(Event::End(e)) => match e.name().as_ref() {
+                    b"keyframes" => break,
+
+                    _ => {
+                        return Err(GuiseError::MismatchedEnd(
+                            std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                        ))
+                    }
+                },
+
+                _ => (),
+            }
+        }
+
+        self.keyframes.insert(id.to_string(), Arc::new(keyframes));
+        Ok(())
+    }
+
+    fn visit_keyframe<'b>(
+        &mut self,
+        e: &'b BytesStart,
+        keyframes: &mut Keyframes,
+    ) -> Result<(), GuiseError> {
+        let offset = self.unescape(&require_attr(e, ATTR_OFFSET)?)?;
+        let offset = Keyframes::parse_offset(offset.trim())?;
+        let mut attrs: Vec<StyleAttr> = Vec::with_capacity(10);
+        self.visit_style_attrs(e, &mut attrs)?;
+        keyframes.add(offset, PartialStyle::from_attrs(&attrs));
+        Ok(())
+    }
+
+    /// Insert the attributes of each of the whitespace-separated style ids in `names`, so that
+    /// attributes which come later can override them.
+    fn apply_mixins(&self, names: &str, attrs: &mut Vec<StyleAttr>) -> Result<(), GuiseError> {
+        for name in names.split_whitespace() {
+            match self.styles.get(name) {
+                Some(mixin) => attrs.extend_from_slice(mixin.attrs()),
+                None => return Err(GuiseError::InvalidAttributeValue(name.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Link an `animation` attribute to the keyframes it names.
+    fn resolve_keyframes(&self, attr: StyleAttr) -> Result<StyleAttr, GuiseError> {
+        match attr {
+            StyleAttr::Animation(Some(mut spec)) => match self.keyframes.get(&spec.name) {
+                Some(keyframes) => {
+                    spec.keyframes = Some(keyframes.clone());
+                    Ok(StyleAttr::Animation(Some(spec)))
+                }
+                None => Err(GuiseError::InvalidAttributeValue(spec.name)),
+            },
+            _ => Ok(attr),
+        }
+    }
+
+    /// Link a `font` attribute to the font file it names.
+    fn resolve_font(&self, attr: StyleAttr) -> Result<StyleAttr, GuiseError> {
+        match attr {
+            StyleAttr::Font(font) => match self.fonts.get(&font.name) {
+                Some(path) => Ok(StyleAttr::Font(FontRef {
+                    handle: Some(Handle::weak(path.get_id().into())),
+                    ..font
+                })),
+                None => Err(GuiseError::InvalidAttributeValue(font.name)),
+            },
+            _ => Ok(attr),
+        }
+    }
+
+    /// Resolve the path of a `background-image` against this file, in the same way as a
+    /// `<font>`, and record the image as a dependency of the document.
+    fn resolve_image(&mut self, attr: StyleAttr) -> StyleAttr {
+        match attr {
+            StyleAttr::BackgroundImage(Some(src)) => {
+                let base = AssetPath::new_ref(&self.path, None);
+                let path = relative_asset_path(&base, None, &src).to_owned();
+                let resolved = path.path().to_string_lossy().to_string();
+                if !self.parsed.images.contains(&path) {
+                    self.parsed.images.push(path);
+                }
+                StyleAttr::BackgroundImage(Some(resolved))
+            }
+            _ => attr,
+        }
+    }
+
     fn visit_param<'b>(
         &mut self,
         e: &'b BytesStart,
@@ -354,7 +988,7 @@ impl<'a> GuiseXmlVisitor<'a> {
         // println!("Template param: {}: {}", name, typ);
         template
             .params
-            .insert(name.to_string(), TemplateParam::new(typ));
+            .insert(name.to_string(), TemplateParam::new(ParamType::parse(typ)?));
 
         if !empty {
             self.reader.read_to_end(e.name()).unwrap();
@@ -370,37 +1004,38 @@ impl<'a> GuiseXmlVisitor<'a> {
         let name = e.name();
         loop {
             match self.reader.read_event() {
-                Err(e) => panic!(
-                    "Error at position {}: {:?}",
-                    self.reader.buffer_position(),
-                    e
-                ),
+                Err(e) => return Err(self.syntax_error(e)),
                 Ok(Event::Eof) => return Err(GuiseError::PrematureEof),
+                Ok(Event::Start(e)) if !self.is_enabled(&e) => self.skip(&e)?,
+                Ok(Event::Empty(e)) if !self.is_enabled(&e) => (),
                 Ok(Event::Start(e)) => match e.name().as_ref() {
                     b"node" => self.visit_element_node(&e, nodes, false)?,
+                    b"include" => self.visit_include(&e, nodes, false)?,
                     _ => {
                         return Err(GuiseError::InvalidElement(
                             std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
                         ))
                     }
                 },
-                Ok(Event::Empty(e)) => self.visit_element_node(&e, nodes, true)?,
+                Ok(Event::Empty(e)) => match e.name().as_ref() {
+                    b"include" => self.visit_include(&e, nodes, true)?,
+                    _ => self.visit_element_node(&e, nodes, true)?,
+                },
                 Ok(Event::End(e)) => {
                     if e.name() == name {
                         break;
                     }
-                    panic!(
-                        "Unrecognized end tag: </{}>",
-                        std::str::from_utf8(e.name().as_ref()).unwrap()
-                    );
+                    return Err(GuiseError::MismatchedEnd(
+                        std::str::from_utf8(e.name().as_ref()).unwrap().to_string(),
+                    ));
                 }
 
-                // TODO: Stateful trimming of whitespace.
+                // Text is kept as written; whitespace is processed according to the style of
+                // the parent node.
                 Ok(Event::Text(e)) => {
                     let mut node = TextNode { ..default() };
                     let content = e.unescape().expect("string expected");
-                    let content = content.trim();
-                    if content.len() > 0 {
+                    if content.trim().len() > 0 {
                         node.content = content.to_string();
                         nodes.push(Box::new(TemplateNode::Text(node)));
                     }
@@ -433,23 +1068,53 @@ impl<'a> GuiseXmlVisitor<'a> {
                 } else if attr.key == ATTR_CONTROLLER {
                     // Controller type name
                     node.controller = Some(attr_value.to_string());
+                } else if attr.key == ATTR_TAB_INDEX {
+                    node.tab_index = Some(attr_value.trim().parse().map_err(|_| {
+                        let err = GuiseError::ExpectedInteger {
+                            name: "tab-index".to_string(),
+                            value: attr_value.to_string(),
+                        };
+                        self.locate(attr.key.into_inner(), err)
+                    })?);
+                } else if attr.key == ATTR_WHEN {
+                    // Already checked by the caller.
+                } else if attr.key == ATTR_APPLY {
+                    // Mixins
+                    self.apply_mixins(attr_value, &mut style_attrs)?;
                 } else {
+                    let key = attr.key.into_inner();
                     match StyleAttr::parse(attr_name, attr_value) {
                         // If we recognized the attribute as a style, then add it to the style list.
-                        Ok(Some(attr)) => style_attrs.push(attr),
+                        Ok(Some(attr)) => {
+                            let attr = self
+                                .resolve_keyframes(attr)
+                                .and_then(|attr| self.resolve_font(attr))
+                                .map_err(|err| self.locate(key, err))?;
+                            style_attrs.push(self.resolve_image(attr));
+                        }
 
                         // Otherwise, if we didn't recognize it, that's OK - treat it as a generic
                         // atribute for this template node.
                         // TODO: Use 'ui' or 'ctrl' namespace here.
                         Ok(None) => {
+                            if attr_name == b"role" && accessibility_role(attr_value).is_none() {
+                                let message =
+                                    format!("Unknown accessibility role '{}'", attr_value.trim());
+                                warn!("{}", message);
+                                self.parsed.warnings.push(message);
+                            }
                             node.attrs.insert(
                                 std::str::from_utf8(attr_name).unwrap().to_string(),
                                 attr_value.to_string(),
                             );
                         }
 
-                        // If the parser returned an error, then propagate it.
-                        Err(err) => return Err(err),
+                        // If the parser returned an error, then propagate it, unless it's
+                        // recoverable.
+                        Err(err) => {
+                            let err = self.locate(key, err);
+                            self.recover(attr_name, attr_value, err)?
+                        }
                     }
                 }
             }
@@ -466,6 +1131,44 @@ impl<'a> GuiseXmlVisitor<'a> {
         parent.push(Box::new(TemplateNode::Element(node)));
         Ok(())
     }
+
+    /// Visit an `<include>` element, which refers to a template in this or another file. The
+    /// reference is resolved relative to this file, and other files become dependencies of the
+    /// template being visited.
+    fn visit_include<'b>(
+        &mut self,
+        e: &'b BytesStart,
+        parent: &mut TemplateNodeList,
+        empty: bool,
+    ) -> Result<(), GuiseError> {
+        let src = self.unescape(&require_attr(e, ATTR_SRC)?)?.to_string();
+        let base = AssetPath::new_ref(&self.path, None);
+        let path = relative_asset_path(&base, None, &src).to_owned();
+        if path.path() != self.path {
+            self.includes.push(path.clone());
+        } else if let Some(label) = path.label() {
+            self.local_includes.push(label.to_string());
+        }
+        let mut params = bevy::utils::HashMap::new();
+        for attr in e.attributes().flatten() {
+            if attr.key != ATTR_SRC && attr.key != ATTR_WHEN && attr.key.prefix().is_none() {
+                params.insert(
+                    String::from_utf8_lossy(attr.key.local_name().into_inner()).to_string(),
+                    self.unescape(&attr)?.to_string(),
+                );
+            }
+        }
+        parent.push(Box::new(TemplateNode::Include(IncludeNode {
+            src,
+            template: Handle::weak(path.get_id().into()),
+            params,
+        })));
+
+        if !empty {
+            self.skip(e)?;
+        }
+        Ok(())
+    }
 }
 
 pub fn require_attr<'a>(e: &'a BytesStart, name: QName) -> Result<Attribute<'a>, GuiseError> {
@@ -482,3 +1185,959 @@ pub fn require_attr<'a>(e: &'a BytesStart, name: QName) -> Result<Attribute<'a>,
         std::str::from_utf8(name.into_inner()).unwrap().to_string(),
     ))
 }
+
+fn write_event(writer: &mut Writer<Cursor<Vec<u8>>>, event: Event) {
+    writer
+        .write_event(event)
+        .expect("writing to an in-memory buffer can't fail");
+}
+
+/// Add the keyframes and window width ranges which `style` and its selectors refer to, if they
+/// aren't there already.
+fn collect_references(
+    style: &PartialStyle,
+    keyframes: &mut Vec<(String, Arc<Keyframes>)>,
+    breakpoints: &mut Vec<(f32, f32)>,
+) {
+    for attr in style.attrs() {
+        if let StyleAttr::Animation(Some(spec)) = attr {
+            if let Some(frames) = &spec.keyframes {
+                if !keyframes.iter().any(|(name, _)| *name == spec.name) {
+                    keyframes.push((spec.name.clone(), frames.clone()));
+                }
+            }
+        }
+    }
+    for (selector, style) in style.selectors() {
+        if let Selector::WindowWidth(min, max) = selector {
+            if !breakpoints.contains(&(*min, *max)) {
+                breakpoints.push((*min, *max));
+            }
+        }
+        collect_references(style, keyframes, breakpoints);
+    }
+}
+
+/// Write `elem` with the style's attributes, and its selectors as nested `<style>` and
+/// `<media>` elements.
+fn write_style(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    mut elem: BytesStart,
+    style: &PartialStyle,
+    breakpoints: &[(f32, f32)],
+) {
+    style
+        .attrs()
+        .iter()
+        .for_each(|attr| attr.write_xml(&mut elem));
+    if style.selectors().is_empty() {
+        write_event(writer, Event::Empty(elem));
+        return;
+    }
+    let end = elem.to_end().into_owned();
+    write_event(writer, Event::Start(elem));
+    for (selector, style) in style.selectors() {
+        let elem = match selector {
+            Selector::WindowWidth(min, max) => {
+                let index = breakpoints
+                    .iter()
+                    .position(|range| *range == (*min, *max))
+                    .unwrap();
+                let mut elem = BytesStart::new("media");
+                elem.push_attribute(("breakpoint", format!("breakpoint-{}", index + 1).as_str()));
+                elem
+            }
+            _ => {
+                let mut elem = BytesStart::new("style");
+                elem.push_attribute(("selector", selector.to_string().as_str()));
+                elem
+            }
+        };
+        write_style(writer, elem, style, breakpoints);
+    }
+    write_event(writer, Event::End(end));
+}
+
+/// Write template nodes as `<node>`, `<include>` and text. Attributes are sorted by name, so
+/// that the output doesn't depend on the order of a hash map.
+fn write_nodes(writer: &mut Writer<Cursor<Vec<u8>>>, nodes: &TemplateNodeList) {
+    for node in nodes.iter() {
+        match node.as_ref() {
+            TemplateNode::Element(elt) | TemplateNode::Fragment(elt) => {
+                let mut elem = BytesStart::new("node");
+                if let Some(id) = &elt.id {
+                    elem.push_attribute(("id", id.as_str()));
+                }
+                if let Some(controller) = &elt.controller {
+                    elem.push_attribute(("controller", controller.as_str()));
+                }
+                if let Some(tab_index) = elt.tab_index {
+                    elem.push_attribute(("tab-index", tab_index.to_string().as_str()));
+                }
+                let mut attrs: Vec<_> = elt.attrs.iter().collect();
+                attrs.sort();
+                for (name, value) in attrs {
+                    elem.push_attribute((name.as_str(), value.as_str()));
+                }
+                if let Some(style) = &elt.inline_styles {
+                    style
+                        .attrs()
+                        .iter()
+                        .for_each(|attr| attr.write_xml(&mut elem));
+                }
+                if elt.children.is_empty() {
+                    write_event(writer, Event::Empty(elem));
+                } else {
+                    write_event(writer, Event::Start(elem));
+                    write_nodes(writer, &elt.children);
+                    write_event(writer, Event::End(BytesEnd::new("node")));
+                }
+            }
+            TemplateNode::Text(text) => {
+                write_event(writer, Event::Text(BytesText::new(&text.content)));
+            }
+            TemplateNode::Include(inc) => {
+                let mut elem = BytesStart::new("include");
+                elem.push_attribute(("src", inc.src.as_str()));
+                let mut params: Vec<_> = inc.params.iter().collect();
+                params.sort();
+                for (name, value) in params {
+                    elem.push_attribute((name.as_str(), value.as_str()));
+                }
+                write_event(writer, Event::Empty(elem));
+            }
+        }
+    }
+}
+
+/// Look for a cycle among the templates of a file which include each other, given the labels
+/// which each template includes. Returns the chain of template ids forming the cycle, if any.
+fn find_include_cycle(includes: &HashMap<String, Vec<String>>) -> Option<String> {
+    fn visit<'a>(
+        id: &'a str,
+        includes: &'a HashMap<String, Vec<String>>,
+        stack: &mut Vec<&'a str>,
+        done: &mut Vec<&'a str>,
+    ) -> Option<String> {
+        if let Some(start) = stack.iter().position(|s| *s == id) {
+            let mut chain = stack[start..].to_vec();
+            chain.push(id);
+            return Some(chain.join(" -> "));
+        }
+        if done.contains(&id) {
+            return None;
+        }
+        stack.push(id);
+        for label in includes.get(id).into_iter().flatten() {
+            if let Some(chain) = visit(label, includes, stack, done) {
+                return Some(chain);
+            }
+        }
+        stack.pop();
+        done.push(id);
+        None
+    }
+
+    let mut ids: Vec<&String> = includes.keys().collect();
+    ids.sort();
+    let mut done: Vec<&str> = Vec::new();
+    ids.into_iter()
+        .find_map(|id| visit(id, includes, &mut Vec::new(), &mut done))
+}
+
+/// Convert a byte offset within `source` into a 1-based (line, column) pair.
+fn line_and_column(source: &[u8], offset: usize) -> (usize, usize) {
+    let prefix = &source[..offset.min(source.len())];
+    let line = prefix.iter().filter(|&&b| b == b'\n').count() + 1;
+    let column = prefix.iter().rev().take_while(|&&b| b != b'\n').count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guise::style::{ComputedStyle, CornerRadii};
+
+    /// Read the opening tag of `src` and visit the node list inside it.
+    fn parse_node_list(src: &str) -> Result<TemplateNodeList, GuiseError> {
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        let e = match visitor.reader.read_event() {
+            Ok(Event::Start(e)) => e,
+            _ => panic!("expected start tag"),
+        };
+        let mut nodes = TemplateNodeList::new();
+        visitor.visit_node_list(&e, &mut nodes)?;
+        Ok(nodes)
+    }
+
+    /// Start loading the document at `path`, and update `app` until it has loaded or failed.
+    fn load_document(app: &mut bevy::prelude::App, path: &str) -> Handle<GuiseDocument> {
+        use bevy::asset::{AssetServer, LoadState};
+
+        let handle = app.world.resource::<AssetServer>().load(path);
+        for _ in 0..200 {
+            app.update();
+            match app.world.resource::<AssetServer>().get_load_state(&handle) {
+                LoadState::Loaded | LoadState::Failed => break,
+                _ => std::thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        }
+        handle
+    }
+
+    #[test]
+    fn test_document() {
+        use bevy::asset::{AssetPath, AssetPlugin, AssetServer, Assets};
+        use bevy::prelude::{App, MinimalPlugins};
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Template>()
+            .add_asset::<PartialStyle>()
+            .add_asset::<GuiseDocument>()
+            .add_asset_loader(GuiseLoader::default());
+
+        let handle = load_document(&mut app, "editor/ui/test.guise.xml");
+
+        let documents = app.world.resource::<Assets<GuiseDocument>>();
+        let document = documents.get(&handle).expect("document should be loaded");
+        let style = document.style_handle("panel").unwrap();
+        assert_eq!(
+            app.world.resource::<AssetServer>().get_handle_path(&style),
+            Some(AssetPath::from("editor/ui/test.guise.xml#panel"))
+        );
+        let style = app
+            .world
+            .resource::<Assets<PartialStyle>>()
+            .get(&style)
+            .unwrap();
+        assert!(style.property_names().contains(&"display"));
+        assert!(document.template_handle("main").is_some());
+        assert!(document.style_handle("missing").is_none());
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let parsed = ParsedDocument::parse(
+            r##"<templates>
+                <style id="panel" width="10px" background-color="#12345z" />
+                <template id="main">
+                    <content>
+                        <include src="shared.guise.xml#header" />
+                        <node style="#panel" />
+                    </content>
+                </template>
+            </templates>"##,
+            Path::new("ui/dialog.guise.xml"),
+        )
+        .unwrap();
+
+        assert_eq!(parsed.styles.len(), 1);
+        assert_eq!(parsed.styles[0].0, "panel");
+        assert_eq!(parsed.styles[0].1.property_names(), vec!["width"]);
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.templates.len(), 1);
+        assert_eq!(parsed.templates[0].0, "main");
+        assert_eq!(parsed.templates[0].1.children.len(), 2);
+        // Includes are resolved against the path the document was given.
+        assert_eq!(
+            parsed.dependencies["main"],
+            vec![AssetPath::from("ui/shared.guise.xml#header")]
+        );
+
+        assert!(ParsedDocument::parse("<templates>", Path::new("ui/dialog.guise.xml")).is_err());
+    }
+
+    #[test]
+    fn test_media() {
+        use crate::guise::style::SelectorTarget;
+
+        let parse = |source| ParsedDocument::parse(source, Path::new("ui/dialog.guise.xml"));
+        let parsed = parse(
+            r#"<templates>
+                <breakpoint id="narrow" max-width="599px" />
+                <breakpoint id="wide" min-width="600" />
+                <style id="panel" width="100px">
+                    <media breakpoint="narrow" width="50%" />
+                    <media breakpoint="wide" width="200px"></media>
+                </style>
+            </templates>"#,
+        )
+        .unwrap();
+        let panel = &parsed.styles[0].1;
+        let width = |window_width| {
+            let mut computed = ComputedStyle::default();
+            panel.apply_to(&mut computed);
+            panel.apply_selected_to(
+                &mut computed,
+                &SelectorTarget {
+                    window_width,
+                    ..default()
+                },
+            );
+            computed.style.width
+        };
+        assert_eq!(width(None), bevy::ui::Val::Px(100.));
+        assert_eq!(width(Some(400.)), bevy::ui::Val::Percent(50.));
+        assert_eq!(width(Some(800.)), bevy::ui::Val::Px(200.));
+        assert!(panel.window_width_changes(Some(400.), Some(800.)));
+
+        // Breakpoints must be declared first, and measured in pixels.
+        assert!(parse(
+            r#"<templates>
+                <style id="panel"><media breakpoint="wide" width="50%" /></style>
+            </templates>"#,
+        )
+        .is_err());
+        assert!(
+            parse(r#"<templates><breakpoint id="wide" min-width="50%" /></templates>"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_when_flags() {
+        let src = r#"<templates>
+            <style id="compact" when="mobile" width="10px" />
+            <template id="tools" when="desktop"><content></content></template>
+            <template id="main">
+                <content>
+                    <node id="always" />
+                    <node id="menu-bar" when="desktop"><node id="file" /></node>
+                    <node id="hybrid" when="desktop touch" />
+                </content>
+            </template>
+        </templates>"#;
+        let parse = |flags: &[&str]| {
+            let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+            visitor.flags = flags.iter().map(|flag| flag.to_string()).collect();
+            visitor.visit().unwrap();
+            visitor.parsed
+        };
+        let ids = |parsed: &ParsedDocument| -> Vec<String> {
+            let (_, main) = parsed.templates.last().unwrap();
+            main.iter_nodes()
+                .filter_map(|node| match node {
+                    TemplateNode::Element(elt) => elt.id.clone(),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let parsed = parse(&[]);
+        assert!(parsed.styles.is_empty());
+        assert_eq!(parsed.templates.len(), 1);
+        assert_eq!(ids(&parsed), ["always"]);
+
+        let parsed = parse(&["desktop"]);
+        assert_eq!(parsed.templates.len(), 2);
+        assert_eq!(ids(&parsed), ["always", "menu-bar", "file"]);
+
+        // Every listed flag must be active.
+        let parsed = parse(&["desktop", "touch", "mobile"]);
+        assert_eq!(parsed.styles.len(), 1);
+        assert_eq!(ids(&parsed), ["always", "menu-bar", "file", "hybrid"]);
+
+        // Nested styles can be disabled too.
+        let src = r#"<templates>
+            <breakpoint id="narrow" max-width="599px" />
+            <style id="panel" width="100px">
+                <style selector="self.hover" when="desktop" width="120px" />
+                <media breakpoint="narrow" when="mobile" width="50%">
+                    <style selector="self.hover" width="60%" />
+                </media>
+            </style>
+        </templates>"#;
+        let selectors = |flags: &[&str]| {
+            let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+            visitor.flags = flags.iter().map(|flag| flag.to_string()).collect();
+            visitor.visit().unwrap();
+            visitor.parsed.styles[0].1.selectors().len()
+        };
+        assert_eq!(selectors(&[]), 0);
+        assert_eq!(selectors(&["desktop"]), 1);
+        assert_eq!(selectors(&["desktop", "mobile"]), 2);
+    }
+
+    #[test]
+    fn test_undeclared_params() {
+        let parse = |source| ParsedDocument::parse(source, Path::new("ui/dialog.guise.xml"));
+        let result = parse(
+            r#"<templates>
+                <template id="main">
+                    <param name="count" type="i32" />
+                    <content>
+                        <node title="{{ title }}">Count {{count}} of {{total}}</node>
+                    </content>
+                </template>
+            </templates>"#,
+        );
+        match result {
+            Err(err @ GuiseError::UndeclaredParams(_)) => {
+                assert_eq!(err.to_string(), "undeclared template params: title, total");
+            }
+            result => panic!("expected an undeclared params error, got {:?}", result),
+        }
+
+        assert!(parse(
+            r#"<templates>
+                <template id="main">
+                    <param name="count" type="i32" />
+                    <content>Count {{count}}</content>
+                </template>
+            </templates>"#,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_custom_extension() {
+        use bevy::asset::{AssetPlugin, Assets};
+        use bevy::prelude::{App, MinimalPlugins};
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Template>()
+            .add_asset::<PartialStyle>()
+            .add_asset::<GuiseDocument>()
+            .add_asset_loader(GuiseLoader::with_extensions(&["ui.xml".to_string()]));
+
+        let handle = load_document(&mut app, "editor/ui/swatch.ui.xml");
+
+        let documents = app.world.resource::<Assets<GuiseDocument>>();
+        let document = documents.get(&handle).expect("document should be loaded");
+        assert!(document.style_handle("swatch").is_some());
+    }
+
+    #[test]
+    fn test_style_library() {
+        use bevy::asset::{AssetPlugin, Assets};
+        use bevy::prelude::{App, MinimalPlugins};
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Template>()
+            .add_asset::<PartialStyle>()
+            .add_asset::<GuiseDocument>()
+            .add_asset_loader(GuiseLoader::default());
+
+        let handle = load_document(&mut app, "editor/ui/buttons.style.xml");
+
+        let documents = app.world.resource::<Assets<GuiseDocument>>();
+        let document = documents.get(&handle).expect("document should be loaded");
+        let style = document.style_handle("button").unwrap();
+        let style = app
+            .world
+            .resource::<Assets<PartialStyle>>()
+            .get(&style)
+            .unwrap();
+        assert!(style.property_names().contains(&"background-color"));
+        assert!(document.template_handle("button").is_none());
+
+        // Style libraries can't contain templates.
+        assert!(ParsedDocument::parse(
+            r#"<styles><template id="main"><content></content></template></styles>"#,
+            Path::new("ui/buttons.style.xml"),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_line_and_column() {
+        let src = b"<a>\n  <b>\n</a>";
+        assert_eq!(line_and_column(src, 0), (1, 1));
+        assert_eq!(line_and_column(src, 3), (1, 4));
+        assert_eq!(line_and_column(src, 6), (2, 3));
+        assert_eq!(line_and_column(src, 100), (3, 5));
+    }
+
+    #[test]
+    fn test_comments() {
+        let nodes = parse_node_list(
+            r#"<content>
+                <!-- A comment -->
+                <node width="10" />
+                <!-- Another
+                     comment -->
+            </content>"#,
+        )
+        .unwrap();
+        assert_eq!(nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_large_document() {
+        const COUNT: usize = 100_000;
+        let mut src = String::from("<content>\n");
+        for i in 0..COUNT {
+            src.push_str(&format!("  <node width=\"{}px\" />\n", i % 100));
+        }
+        src.push_str("</content>");
+        assert!(src.len() > 2_000_000);
+
+        let nodes = parse_node_list(&src).unwrap();
+        assert_eq!(nodes.len(), COUNT);
+    }
+
+    #[test]
+    fn test_syntax_error_line() {
+        let err = parse_node_list("<content>\n<node>\n</content>").unwrap_err();
+        match err {
+            GuiseError::SyntaxError { line, .. } => assert_eq!(line, 3),
+            _ => panic!("expected syntax error, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_keyframes() {
+        fn next_style(visitor: &mut GuiseXmlVisitor) -> Result<PartialStyle, GuiseError> {
+            loop {
+                match visitor.reader.read_event() {
+                    Ok(Event::Empty(e)) => return visitor.visit_style(&e, true),
+                    Ok(Event::Text(_)) => (),
+                    _ => panic!("expected empty tag"),
+                }
+            }
+        }
+
+        let src = r#"<keyframes id="pulse">
+                <keyframe offset="0%" width="0" />
+                <keyframe offset="to" width="10px" />
+            </keyframes>
+            <style id="pulsing" animation="pulse 1s infinite" />
+            <style id="unknown" animation="glow 1s" />"#;
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        match visitor.reader.read_event() {
+            Ok(Event::Start(e)) => visitor.visit_keyframes(&e, false).unwrap(),
+            _ => panic!("expected start tag"),
+        };
+
+        let keyframes = visitor.keyframes.get("pulse").unwrap().clone();
+        assert_eq!(
+            keyframes.sample(&ComputedStyle::default(), 0.5).style.width,
+            bevy::ui::Val::Px(5.)
+        );
+
+        let mut computed = ComputedStyle::default();
+        next_style(&mut visitor).unwrap().apply_to(&mut computed);
+        let spec = computed.animation.unwrap();
+        assert_eq!(spec.keyframes, Some(keyframes));
+
+        // Animations must refer to keyframes that have already been declared.
+        assert!(next_style(&mut visitor).is_err());
+
+        // Malformed XML is an error rather than a panic.
+        let parse_keyframes = |src: &str| {
+            let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+            match visitor.reader.read_event() {
+                Ok(Event::Start(e)) => visitor.visit_keyframes(&e, false),
+                _ => panic!("expected start tag"),
+            }
+        };
+        assert!(parse_keyframes(r#"<keyframes id="a&bogus;"></keyframes>"#).is_err());
+        assert!(
+            parse_keyframes(r#"<keyframes id="a"><keyframe offset="&bogus;" /></keyframes>"#)
+                .is_err()
+        );
+        assert!(parse_keyframes(r#"<keyframes id="a"><keyframe offset="to">"#).is_err());
+    }
+
+    #[test]
+    fn test_fonts() {
+        let src = r#"<templates>
+                <font id="body" src="fonts/FiraSans-Regular.ttf" />
+                <font id="heading" src="./Heading.ttf" />
+                <style id="title" font="heading" font-size="24px" />
+                <template id="main"><content><node font="body">Text</node></content></template>
+            </templates>"#;
+        let parsed = ParsedDocument::parse(src, Path::new("ui/main.guise.xml")).unwrap();
+        assert_eq!(
+            parsed.fonts,
+            vec![
+                (
+                    "body".to_string(),
+                    AssetPath::from("fonts/FiraSans-Regular.ttf")
+                ),
+                ("heading".to_string(), AssetPath::from("ui/Heading.ttf")),
+            ]
+        );
+
+        let mut computed = ComputedStyle::default();
+        parsed.styles[0].1.apply_to(&mut computed);
+        let font = computed.font.unwrap();
+        assert_eq!(font.name, "heading");
+        assert_eq!(
+            font.handle.unwrap().id(),
+            bevy::asset::HandleId::from(AssetPath::from("ui/Heading.ttf"))
+        );
+
+        // Fonts must be declared before the styles that use them.
+        let src = r#"<templates>
+                <style id="title" font="heading" />
+                <font id="heading" src="Heading.ttf" />
+            </templates>"#;
+        assert!(ParsedDocument::parse(src, Path::new("main.guise.xml")).is_err());
+    }
+
+    #[test]
+    fn test_background_image_path() {
+        let src = r#"<templates>
+                <style id="panel" background-image="url(./panel.png)" />
+                <style id="frame" background-image="url(../frame.png)" />
+                <style id="logo" background-image="url(images/logo.png)" />
+                <template id="main">
+                    <content><node background-image="url(./panel.png)" /></content>
+                </template>
+            </templates>"#;
+        let parsed = ParsedDocument::parse(src, Path::new("ui/panels/main.guise.xml")).unwrap();
+        let image = |index: usize| {
+            let mut computed = ComputedStyle::default();
+            parsed.styles[index].1.apply_to(&mut computed);
+            computed.background_image.unwrap()
+        };
+        assert_eq!(image(0), "ui/panels/panel.png");
+        assert_eq!(image(1), "ui/frame.png");
+        assert_eq!(image(2), "images/logo.png");
+        let TemplateNode::Element(node) = parsed.templates[0].1.children[0].as_ref() else {
+            panic!("expected an element");
+        };
+        let mut computed = ComputedStyle::default();
+        node.inline_styles.as_ref().unwrap().apply_to(&mut computed);
+        assert_eq!(computed.background_image.unwrap(), "ui/panels/panel.png");
+
+        // Each image is a dependency of the document once.
+        assert_eq!(
+            parsed.images,
+            vec![
+                AssetPath::from("ui/panels/panel.png"),
+                AssetPath::from("ui/frame.png"),
+                AssetPath::from("images/logo.png"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include() {
+        use bevy::asset::HandleId;
+
+        let src = r##"<content>
+            <include src="#header" title="Settings"/>
+            <node/>
+            <include src="./shared.guise.xml#footer"></include>
+        </content>"##;
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        visitor.path = PathBuf::from("ui/panel.guise.xml");
+        let e = match visitor.reader.read_event() {
+            Ok(Event::Start(e)) => e,
+            _ => panic!("expected start tag"),
+        };
+        let mut nodes = TemplateNodeList::new();
+        visitor.visit_node_list(&e, &mut nodes).unwrap();
+
+        assert_eq!(nodes.len(), 3);
+        match nodes[0].as_ref() {
+            TemplateNode::Include(inc) => {
+                assert_eq!(inc.src, "#header");
+                assert_eq!(
+                    inc.template.id(),
+                    HandleId::from(AssetPath::from("ui/panel.guise.xml#header").get_id())
+                );
+                assert_eq!(inc.params.len(), 1);
+                assert_eq!(inc.params["title"], "Settings");
+            }
+            node => panic!("expected an include, got {:?}", node),
+        }
+        assert!(matches!(nodes[1].as_ref(), TemplateNode::Element(_)));
+        match nodes[2].as_ref() {
+            TemplateNode::Include(inc) => assert_eq!(
+                inc.template.id(),
+                HandleId::from(AssetPath::from("ui/shared.guise.xml#footer").get_id())
+            ),
+            node => panic!("expected an include, got {:?}", node),
+        }
+
+        // Only the other file is a dependency.
+        assert_eq!(
+            visitor.includes,
+            vec![AssetPath::from("ui/shared.guise.xml#footer")]
+        );
+
+        // Malformed entities and unclosed includes are errors rather than panics.
+        assert!(parse_node_list(r##"<content><include src="#a&bogus;"/></content>"##).is_err());
+        assert!(
+            parse_node_list(r##"<content><include src="#a" title="&bogus;"/></content>"##).is_err()
+        );
+        assert!(parse_node_list(r##"<content><include src="#a">"##).is_err());
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let includes = |pairs: &[(&str, &[&str])]| -> HashMap<String, Vec<String>> {
+            pairs
+                .iter()
+                .map(|(id, labels)| {
+                    (
+                        id.to_string(),
+                        labels.iter().map(|label| label.to_string()).collect(),
+                    )
+                })
+                .collect()
+        };
+
+        // A template which includes itself.
+        assert_eq!(
+            find_include_cycle(&includes(&[("a", &["a"])])),
+            Some("a -> a".to_string())
+        );
+        assert_eq!(
+            find_include_cycle(&includes(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])])),
+            Some("a -> b -> c -> a".to_string())
+        );
+        assert_eq!(
+            find_include_cycle(&includes(&[("a", &["b", "c"]), ("b", &["c"]), ("c", &[])])),
+            None
+        );
+
+        // Labels which aren't templates in this file are ignored.
+        assert_eq!(find_include_cycle(&includes(&[("a", &["x"])])), None);
+    }
+
+    #[test]
+    fn test_self_mixin() {
+        let mut visitor = GuiseXmlVisitor::new(br#"<style id="loop" apply="base loop" />"#);
+        let e = match visitor.reader.read_event() {
+            Ok(Event::Empty(e)) => e,
+            _ => panic!("expected empty tag"),
+        };
+        match visitor.visit_named_style(&e, true) {
+            Err(GuiseError::CircularReference(chain)) => assert_eq!(chain, "loop -> loop"),
+            result => panic!("expected a circular reference, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_mixins() {
+        let src = r##"<style id="rounded" border-radius="4px" border="1px" />
+            <style id="card" apply="rounded" background-color="#fff" />
+            <style id="pill" apply="rounded" border-radius="12px" />
+            <style id="broken" apply="missing" />"##;
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        let mut next_style = || loop {
+            match visitor.reader.read_event() {
+                Ok(Event::Empty(e)) => break visitor.visit_named_style(&e, true),
+                Ok(Event::Text(_)) => (),
+                _ => panic!("expected empty tag"),
+            }
+        };
+
+        let (_, rounded) = next_style().unwrap();
+        let (_, card) = next_style().unwrap();
+        let (_, pill) = next_style().unwrap();
+        assert!(next_style().is_err());
+
+        // Both styles get the mixin's attributes, in place of the `apply` attribute.
+        assert_eq!(&card.attrs()[..2], rounded.attrs());
+        assert_eq!(&pill.attrs()[..2], rounded.attrs());
+
+        // Attributes after the mixin override it.
+        let mut computed = ComputedStyle::default();
+        pill.apply_to(&mut computed);
+        assert_eq!(
+            computed.border_radius,
+            Some(CornerRadii::all(bevy::ui::Val::Px(12.)))
+        );
+        assert_eq!(
+            computed.style.border,
+            bevy::ui::UiRect::all(bevy::ui::Val::Px(1.))
+        );
+    }
+
+    #[test]
+    fn test_source_order() {
+        use bevy::ui::{UiRect, Val};
+
+        let src = r#"<style id="a" margin="4px" margin-left="8px" />
+            <style id="b" margin-left="8px" margin="4px" />"#;
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        let mut next_margin = || loop {
+            match visitor.reader.read_event() {
+                Ok(Event::Empty(e)) => {
+                    let (_, style) = visitor.visit_named_style(&e, true).unwrap();
+                    let mut computed = ComputedStyle::default();
+                    style.apply_to(&mut computed);
+                    break computed.style.margin;
+                }
+                Ok(Event::Text(_)) => (),
+                _ => panic!("expected empty tag"),
+            }
+        };
+
+        // As in CSS, whichever attribute comes last wins.
+        let mut margin = UiRect::all(Val::Px(4.));
+        margin.left = Val::Px(8.);
+        assert_eq!(next_margin(), margin);
+        assert_eq!(next_margin(), UiRect::all(Val::Px(4.)));
+    }
+
+    #[test]
+    fn test_attribute_error_location() {
+        let src = "<templates>\n    <style id=\"panel\" width=\"wide\" />\n</templates>";
+        let err = ParsedDocument::parse(src, Path::new("main.guise.xml")).unwrap_err();
+        assert!(err.to_string().ends_with("at line 2, column 23"));
+        match err {
+            GuiseError::InvalidAttribute {
+                name,
+                location: Some(location),
+                ..
+            } => {
+                assert_eq!(name, "width");
+                assert_eq!(location, (2, 23));
+            }
+            other => panic!("expected an invalid attribute, got {:?}", other),
+        }
+
+        // Inline styles on nodes are located too.
+        let src = r#"<content><node id="a" height="tall" /></content>"#;
+        match parse_node_list(src).unwrap_err() {
+            GuiseError::InvalidAttribute {
+                location: Some(location),
+                ..
+            } => assert_eq!(location, (1, 23)),
+            other => panic!("expected an invalid attribute, got {:?}", other),
+        }
+
+        // So are errors which already name the attribute, without naming it twice.
+        let src = r#"<content><node grid-row="1.5 / 2" /></content>"#;
+        let err = parse_node_list(src).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'grid-row' expects an integer, got '1.5' at line 1, column 16"
+        );
+        match err {
+            GuiseError::InvalidAttribute {
+                name,
+                location: Some(location),
+                error,
+            } => {
+                assert_eq!(name, "grid-row");
+                assert_eq!(location, (1, 16));
+                assert!(matches!(*error, GuiseError::ExpectedInteger { .. }));
+            }
+            other => panic!("expected an invalid attribute, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_color() {
+        let src = r##"<style id="typo" background-color="#12345z" width="10px" />"##;
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        let e = match visitor.reader.read_event() {
+            Ok(Event::Empty(e)) => e,
+            _ => panic!("expected empty tag"),
+        };
+
+        let style = visitor.visit_style(&e, true).unwrap();
+        assert_eq!(style.property_names(), vec!["width"]);
+        assert_eq!(
+            visitor.parsed.warnings,
+            vec!["Invalid color for 'background-color': '#12345z'".to_string()]
+        );
+
+        // Strict mode fails instead.
+        visitor.strict = true;
+        assert!(visitor.visit_style(&e, true).is_err());
+
+        // Other invalid values are always errors.
+        assert!(parse_node_list(r#"<content><node width="wide" /></content>"#).is_err());
+    }
+
+    #[test]
+    fn test_unknown_role() {
+        let src = r#"<content><node role="bogus" /><node role="button" /></content>"#;
+        let mut visitor = GuiseXmlVisitor::new(src.as_bytes());
+        let e = match visitor.reader.read_event() {
+            Ok(Event::Start(e)) => e,
+            _ => panic!("expected start tag"),
+        };
+        let mut nodes = TemplateNodeList::new();
+        visitor.visit_node_list(&e, &mut nodes).unwrap();
+
+        // Unknown roles are reported once, when the document is loaded.
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(
+            visitor.parsed.warnings,
+            vec!["Unknown accessibility role 'bogus'".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_to_xml() {
+        let src = r##"<templates>
+                <font id="body" src="./Body.ttf" />
+                <breakpoint id="narrow" max-width="600px" />
+                <keyframes id="pulse">
+                    <keyframe offset="from" opacity="1" />
+                    <keyframe offset="50%" opacity="0.5" />
+                </keyframes>
+                <style id="base" font="body" width="10px" />
+                <style id="panel" apply="base" animation="pulse 2s infinite">
+                    <style selector="self.hover:not(:nth-child(2n+1))" color="red" />
+                    <media breakpoint="narrow" width="100%" />
+                </style>
+                <template id="main">
+                    <param name="title" type="string" />
+                    <content>
+                        <node id="root" tab-index="0" style="#panel" height="5px">
+                            {{title}} &amp; more
+                            <include src="#footer" />
+                        </node>
+                    </content>
+                </template>
+                <template id="footer"><content><node /></content></template>
+            </templates>"##;
+        let path = Path::new("ui/main.guise.xml");
+        let parsed = ParsedDocument::parse(src, path).unwrap();
+        let xml = parsed.to_xml();
+        let reparsed = ParsedDocument::parse(&xml, path).unwrap();
+
+        assert_eq!(reparsed.styles, parsed.styles);
+        assert_eq!(reparsed.fonts, parsed.fonts);
+        let selectors = |style: &PartialStyle| -> Vec<String> {
+            style
+                .selectors()
+                .iter()
+                .map(|(selector, style)| format!("{:?} {:?}", selector, style.attrs()))
+                .collect()
+        };
+        assert_eq!(
+            selectors(&reparsed.styles[1].1),
+            selectors(&parsed.styles[1].1)
+        );
+
+        let ids = |doc: &ParsedDocument| -> Vec<String> {
+            doc.templates.iter().map(|(id, _)| id.clone()).collect()
+        };
+        assert_eq!(ids(&reparsed), ids(&parsed));
+        let (_, main) = &reparsed.templates[0];
+        assert_eq!(main.params["title"].r#type, ParamType::String);
+        assert_eq!(main.iter_nodes().count(), 3);
+        match main.children[0].as_ref() {
+            TemplateNode::Element(elt) => {
+                assert_eq!(elt.id.as_deref(), Some("root"));
+                assert_eq!(elt.tab_index, Some(0));
+                assert_eq!(elt.attrs["style"], "#panel");
+                assert_eq!(
+                    elt.inline_styles.as_deref().unwrap().property_names(),
+                    vec!["height"]
+                );
+            }
+            _ => panic!("expected element"),
+        }
+        assert!(main.iter_nodes().any(|node| matches!(
+            node,
+            TemplateNode::Text(text) if text.content.trim() == "{{title}} & more"
+        )));
+
+        // Writing the reparsed document gives the same output.
+        assert_eq!(reparsed.to_xml(), xml);
+    }
+}
@@ -1,10 +1,35 @@
 use bevy::prelude::*;
 
 use super::{
-    style::{ComputedStyle, PartialStyle, UpdateComputedStyle},
-    ViewElement,
+    style::{ComputedStyle, PartialStyle, StyleFields, UpdateComputedStyle},
+    GuiseTheme, ViewElement,
 };
 
+/// Shared state needed by controllers to compute the style of a view element.
+pub struct StyleContext<'a> {
+    /// Loaded stylesheets.
+    pub assets: &'a Assets<PartialStyle>,
+
+    /// Style applied to every element before its own styles, if loaded.
+    pub default_style: Option<&'a PartialStyle>,
+
+    /// Replacement stylesheets for the current theme, if any.
+    pub theme: Option<&'a GuiseTheme>,
+
+    /// Width of the primary window in logical pixels, for `<media>` styles.
+    pub window_width: Option<f32>,
+}
+
+impl<'a> StyleContext<'a> {
+    /// Look up a loaded stylesheet, using the theme's replacement for it if there is one.
+    pub fn style(&self, handle: &Handle<PartialStyle>) -> Option<&'a PartialStyle> {
+        match self.theme {
+            Some(theme) => self.assets.get(theme.resolve(handle)),
+            None => self.assets.get(handle),
+        }
+    }
+}
+
 /// A controller is an object which attaches to a UiComponent and handles events.
 #[bevy_trait_query::queryable]
 pub trait Controller {
@@ -16,13 +41,23 @@ pub trait Controller {
         commands: &mut Commands,
         entity: Entity,
         view: &ViewElement,
-        assets: &Assets<PartialStyle>,
+        ctx: &StyleContext,
     ) {
         let mut computed = ComputedStyle::default();
-        view.apply_base_styles(&mut computed, assets);
-        view.apply_inline_styles(&mut computed);
+        view.compute_style(&mut computed, ctx);
         commands.add(UpdateComputedStyle { entity, computed });
     }
+
+    /// Called after applying the element's computed style changed fields of its `Style`, e.g.
+    /// to lay out a canvas again when the width changes. `changed` is never empty.
+    fn style_changed(
+        &self,
+        _commands: &mut Commands,
+        _entity: Entity,
+        _view: &ViewElement,
+        _changed: StyleFields,
+    ) {
+    }
 }
 
 // pub enum UiEvent {
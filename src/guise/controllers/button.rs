@@ -2,8 +2,8 @@ use bevy::{prelude::*, ui::FocusPolicy};
 use bevy_mod_picking::prelude::*;
 
 use crate::guise::{
-    controller::Controller,
-    style::{ComputedStyle, PartialStyle, UpdateComputedStyle},
+    controller::{Controller, StyleContext},
+    style::{ComputedStyle, UpdateComputedStyle},
     view::StyleHandlesChanged,
     ViewElement,
 };
@@ -48,10 +48,10 @@ impl Controller for ButtonController {
         commands: &mut Commands,
         entity: Entity,
         view: &ViewElement,
-        assets: &Assets<PartialStyle>,
+        ctx: &StyleContext,
     ) {
         let mut computed = ComputedStyle::default();
-        view.apply_base_styles(&mut computed, assets);
+        view.apply_base_styles(&mut computed, ctx);
 
         if self.pressed {
             computed.background_color = Some(PRESSED);
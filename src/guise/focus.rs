@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use super::view::{SetClass, ViewElement};
+
+/// Class name given to the element which has keyboard focus, so that styles can use
+/// `self.focus` or `self:focus` selectors.
+pub const FOCUS_CLASS: &str = "focus";
+
+/// The view element which has keyboard focus, if any.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub struct FocusedElement(pub Option<Entity>);
+
+/// Sent when keyboard focus moves between view elements.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum FocusEvent {
+    /// The element gained focus.
+    Focus(Entity),
+
+    /// The element lost focus.
+    Blur(Entity),
+}
+
+/// Moves keyboard focus to the next element in tab order when Tab is pressed, or to the
+/// previous one with Shift+Tab, wrapping around at either end.
+///
+/// As in HTML, elements with a positive `tab-index` come first, in increasing order, followed by
+/// those with a `tab-index` of zero. Elements with a negative `tab-index` or none at all are
+/// skipped. Ties are broken by entity, which follows document order for the elements of a newly
+/// built view.
+///
+/// If the focused element is despawned, nothing has focus, and no `Blur` event is sent for it.
+pub fn update_focus(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    query: Query<(Entity, &ViewElement)>,
+    mut focused: ResMut<FocusedElement>,
+    mut ev_focus: EventWriter<FocusEvent>,
+) {
+    if matches!(focused.0, Some(entity) if !query.contains(entity)) {
+        focused.0 = None;
+    }
+
+    if !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    let backward = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let mut order: Vec<(bool, i32, Entity)> = query
+        .iter()
+        .filter_map(|(entity, view)| match view.tab_index {
+            Some(index) if index >= 0 => Some((index == 0, index, entity)),
+            _ => None,
+        })
+        .collect();
+    if order.is_empty() {
+        return;
+    }
+    order.sort();
+
+    let current = focused
+        .0
+        .and_then(|entity| order.iter().position(|(_, _, e)| *e == entity));
+    let next = match (current, backward) {
+        (Some(pos), false) => (pos + 1) % order.len(),
+        (Some(pos), true) => (pos + order.len() - 1) % order.len(),
+        (None, false) => 0,
+        (None, true) => order.len() - 1,
+    };
+    let next = order[next].2;
+    if focused.0 == Some(next) {
+        return;
+    }
+
+    if let Some(previous) = focused.0 {
+        commands.add(SetClass {
+            entity: previous,
+            class: FOCUS_CLASS.to_string(),
+            enabled: false,
+        });
+        ev_focus.send(FocusEvent::Blur(previous));
+    }
+    commands.add(SetClass {
+        entity: next,
+        class: FOCUS_CLASS.to_string(),
+        enabled: true,
+    });
+    ev_focus.send(FocusEvent::Focus(next));
+    focused.0 = Some(next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_order() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<FocusedElement>()
+            .add_event::<FocusEvent>()
+            .add_systems(Update, update_focus);
+        let mut spawn = |tab_index| {
+            app.world
+                .spawn(ViewElement {
+                    tab_index,
+                    ..default()
+                })
+                .id()
+        };
+        let first = spawn(Some(0));
+        let skipped = spawn(Some(-1));
+        let second = spawn(Some(0));
+        let positive = spawn(Some(2));
+        spawn(None);
+
+        let tab = |app: &mut App, shift: bool| {
+            let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+            keys.release_all();
+            keys.clear();
+            if shift {
+                keys.press(KeyCode::ShiftLeft);
+            }
+            keys.press(KeyCode::Tab);
+            app.update();
+            app.world.resource::<FocusedElement>().0
+        };
+        let has_focus = |app: &App, entity| {
+            app.world
+                .get::<ViewElement>(entity)
+                .unwrap()
+                .has_class(FOCUS_CLASS)
+        };
+
+        // Positive indices come first, then zero in creation order.
+        assert_eq!(tab(&mut app, false), Some(positive));
+        assert!(has_focus(&app, positive));
+        assert_eq!(tab(&mut app, false), Some(first));
+        assert!(!has_focus(&app, positive));
+        assert!(has_focus(&app, first));
+        assert_eq!(tab(&mut app, false), Some(second));
+        assert!(!has_focus(&app, skipped));
+
+        // Focus wraps around, in both directions.
+        assert_eq!(tab(&mut app, false), Some(positive));
+        assert_eq!(tab(&mut app, true), Some(second));
+
+        let events: Vec<FocusEvent> = app
+            .world
+            .resource_mut::<Events<FocusEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(
+            &events[events.len() - 2..],
+            &[FocusEvent::Blur(positive), FocusEvent::Focus(second)]
+        );
+
+        // Despawning the focused element clears focus without blurring it.
+        app.world.despawn(second);
+        let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+        keys.release_all();
+        keys.clear();
+        app.update();
+        assert_eq!(app.world.resource::<FocusedElement>().0, None);
+        assert_eq!(tab(&mut app, false), Some(positive));
+        let events: Vec<FocusEvent> = app
+            .world
+            .resource_mut::<Events<FocusEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(events, vec![FocusEvent::Focus(positive)]);
+    }
+}
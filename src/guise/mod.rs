@@ -1,23 +1,135 @@
 mod asset;
 mod controller;
 mod controllers;
+mod focus;
 mod plugin;
 mod style;
 mod template;
 mod view;
 
-pub use controller::Controller;
+pub use asset::{GuiseDocument, ParsedDocument};
+pub use controller::{Controller, StyleContext};
+pub use focus::{FocusEvent, FocusedElement, FOCUS_CLASS};
 pub use plugin::*;
+pub use style::{
+    register_style_property, ComputedStyle, CustomProperty, CustomValue, StyleChanged, StyleField,
+    StyleFields,
+};
+pub use view::SetClass;
+pub use view::ToggleClass;
 pub use view::ViewElement;
 pub use view::ViewRoot;
+pub use view::ViewSource;
+
+use std::fmt;
 
 #[derive(Debug)]
 pub enum GuiseError {
     PrematureEof,
+    SyntaxError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
     InvalidElement(String),
     MismatchedEnd(String),
     UnknownAttribute(Vec<u8>),
     UnknownAttributeValue(String),
     InvalidAttributeValue(String),
     MissingRequiredAttribute(String),
+    UnknownParamType(String),
+    InvalidParamValue {
+        name: String,
+        expected: String,
+        value: String,
+    },
+    ExpectedInteger {
+        name: String,
+        value: String,
+    },
+
+    /// A style or template refers back to itself, directly or indirectly. Holds the chain of
+    /// references, e.g. `a -> b -> a`.
+    CircularReference(String),
+
+    /// A template refers to params which it doesn't declare.
+    UndeclaredParams(Vec<String>),
+
+    /// An attribute value which is valid CSS, but which Bevy has no equivalent for.
+    UnsupportedAttributeValue {
+        name: String,
+        value: String,
+        reason: String,
+    },
+
+    /// An attribute whose value failed to parse, wrapping the error from the value.
+    /// `location` is the 1-based line and column of the attribute within the file, when it was
+    /// read by the loader.
+    InvalidAttribute {
+        name: String,
+        location: Option<(usize, usize)>,
+        error: Box<GuiseError>,
+    },
 }
+
+impl GuiseError {
+    /// True if the error's message already names the attribute it came from, so that wrapping
+    /// it in `InvalidAttribute` shouldn't name it again.
+    pub(crate) fn names_attribute(&self) -> bool {
+        matches!(
+            self,
+            GuiseError::ExpectedInteger { .. } | GuiseError::UnsupportedAttributeValue { .. }
+        )
+    }
+}
+
+impl fmt::Display for GuiseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuiseError::SyntaxError {
+                line,
+                column,
+                message,
+            } => write!(f, "{} at line {}, column {}", message, line, column),
+            GuiseError::InvalidParamValue {
+                name,
+                expected,
+                value,
+            } => write!(
+                f,
+                "template param '{}' expects a {}, got '{}'",
+                name, expected, value
+            ),
+            GuiseError::ExpectedInteger { name, value } => {
+                write!(f, "'{}' expects an integer, got '{}'", name, value)
+            }
+            GuiseError::CircularReference(chain) => write!(f, "circular reference: {}", chain),
+            GuiseError::UndeclaredParams(names) => {
+                write!(f, "undeclared template params: {}", names.join(", "))
+            }
+            GuiseError::UnsupportedAttributeValue {
+                name,
+                value,
+                reason,
+            } => write!(f, "'{}: {}' is not supported: {}", name, value, reason),
+            GuiseError::InvalidAttribute {
+                name,
+                location,
+                error,
+            } => {
+                if error.names_attribute() {
+                    write!(f, "{}", error)?;
+                } else {
+                    write!(f, "invalid '{}': {}", name, error)?;
+                }
+                match location {
+                    Some((line, column)) => write!(f, " at line {}, column {}", line, column),
+                    None => Ok(()),
+                }
+            }
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl std::error::Error for GuiseError {}
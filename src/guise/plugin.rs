@@ -1,33 +1,133 @@
-use bevy::{prelude::*, ui::FocusPolicy};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use bevy::{
+    prelude::*,
+    transform::TransformSystem,
+    ui::{FocusPolicy, UiSystem},
+    utils::HashMap,
+};
 
 use crate::guise::view::attach_view_controllers;
 
 use super::{
-    asset::GuiseLoader,
+    asset::{GuiseDocument, GuiseLoader, DEFAULT_EXTENSION, STYLE_EXTENSION},
     controller::Controller,
     controllers::{ButtonController, DefaultController},
-    style::PartialStyle,
+    focus::{update_focus, FocusEvent, FocusedElement},
+    style::{
+        animate_styles, apply_style_transforms, update_max_content, update_text_shadows,
+        PartialStyle, RootFontSize, StyleChanged,
+    },
     template::Template,
-    view::{create_views, update_view_styles, update_view_styles_poll, ViewRoot},
+    view::{
+        create_views, notify_style_changes, update_interaction_classes, update_media_styles,
+        update_view_styles, update_view_styles_poll, ViewRoot, ViewSource,
+    },
 };
 
-pub struct GuisePlugin;
+pub struct GuisePlugin {
+    /// File extensions loaded as guise documents, without the leading dot. Defaults to
+    /// `guise.xml`, and `style.xml` for style libraries.
+    pub extensions: Vec<String>,
+}
+
+impl Default for GuisePlugin {
+    fn default() -> Self {
+        Self {
+            extensions: vec![DEFAULT_EXTENSION.to_string(), STYLE_EXTENSION.to_string()],
+        }
+    }
+}
+
+/// Global configuration for guise views.
+#[derive(Resource, Default)]
+pub struct GuiseSettings {
+    /// Style applied to every view element before its own styles, similar to a browser's
+    /// user-agent stylesheet.
+    pub default_style: Option<Handle<PartialStyle>>,
+
+    /// Directory against which `./` and `../` references are resolved, e.g. `ui`. If not set,
+    /// references are resolved relative to the directory of the file containing them.
+    pub base_path: Option<PathBuf>,
+}
+
+/// Replacement stylesheets, used to switch the whole UI between themes at runtime without
+/// reloading any assets. Wherever one of the keys would be used, including as the default style,
+/// the corresponding value is used instead. Changing this resource recomputes the styles of
+/// every view element.
+#[derive(Resource, Default)]
+pub struct GuiseTheme {
+    pub styles: HashMap<Handle<PartialStyle>, Handle<PartialStyle>>,
+}
+
+/// Named flags, such as `desktop` or `mobile`, which enable the parts of a guise document marked
+/// with a `when` attribute. An item is loaded only if all of the flags it lists are active;
+/// flags which were never set are inactive. Flags are read when a document is loaded, so
+/// changing them doesn't affect documents which have already been loaded.
+///
+/// Insert this resource before adding `GuisePlugin` to load the first documents with flags.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct GuiseFlags(Arc<RwLock<HashSet<String>>>);
+
+impl GuiseFlags {
+    /// Activate or deactivate `flag`.
+    pub fn set(&self, flag: &str, active: bool) {
+        let mut flags = self.0.write().unwrap();
+        if active {
+            flags.insert(flag.to_string());
+        } else {
+            flags.remove(flag);
+        }
+    }
+
+    /// True if `flag` is active.
+    pub fn is_active(&self, flag: &str) -> bool {
+        self.0.read().unwrap().contains(flag)
+    }
+
+    /// A copy of the active flags.
+    pub(crate) fn active(&self) -> HashSet<String> {
+        self.0.read().unwrap().clone()
+    }
+}
+
+impl GuiseTheme {
+    /// The stylesheet to use in place of `style`, which is `style` itself if it isn't replaced.
+    pub fn resolve<'a>(&'a self, style: &'a Handle<PartialStyle>) -> &'a Handle<PartialStyle> {
+        self.styles.get(style).unwrap_or(style)
+    }
+}
 
 impl Plugin for GuisePlugin {
     fn build(&self, app: &mut App) {
         use bevy_trait_query::RegisterExt;
+        let mut loader = GuiseLoader::with_extensions(&self.extensions);
+        loader.flags = app
+            .world
+            .get_resource_or_insert_with(GuiseFlags::default)
+            .clone();
         app.add_plugins((
             bevy_mod_picking::picking_core::CorePlugin,
             bevy_mod_picking::picking_core::InteractionPlugin,
             bevy_mod_picking::input::InputPlugin,
             bevy_mod_picking::backends::bevy_ui::BevyUiBackend,
         ))
-        .add_asset_loader(GuiseLoader)
+        .add_asset_loader(loader)
         .add_asset::<Template>()
         .add_asset::<PartialStyle>()
+        .add_asset::<GuiseDocument>()
+        .init_resource::<GuiseSettings>()
+        .init_resource::<GuiseTheme>()
+        .init_resource::<RootFontSize>()
+        .init_resource::<FocusedElement>()
+        .add_event::<FocusEvent>()
+        .add_event::<StyleChanged>()
         .register_component_as::<dyn Controller, DefaultController>()
         .register_component_as::<dyn Controller, ButtonController>()
         .register_type::<ButtonController>()
+        .register_type::<ViewSource>()
         .add_systems(Startup, create_test_ui)
         .add_systems(
             Update,
@@ -36,10 +136,27 @@ impl Plugin for GuisePlugin {
                 attach_view_controllers,
                 // apply_deferred,
                 update_view_styles,
+                update_interaction_classes,
+                update_media_styles,
+                update_focus,
                 // apply_deferred,
                 update_view_styles_poll,
+                notify_style_changes,
+                animate_styles,
             )
                 .chain(),),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                apply_style_transforms
+                    .after(UiSystem::Layout)
+                    .before(TransformSystem::TransformPropagate),
+                update_text_shadows
+                    .after(UiSystem::Layout)
+                    .before(TransformSystem::TransformPropagate),
+                update_max_content.after(UiSystem::Layout),
+            ),
         );
     }
 }
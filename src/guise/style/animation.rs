@@ -0,0 +1,458 @@
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::guise::GuiseError;
+
+use super::val_math::{self, ValContext};
+use super::{copy_style_fields, ComputedStyle, PartialStyle};
+
+/// A single step of a keyframe animation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    /// Position of this keyframe within the animation, from 0.0 to 1.0.
+    pub offset: f32,
+
+    /// Style properties at this point of the animation.
+    pub style: PartialStyle,
+}
+
+/// A named sequence of keyframes, similar to CSS `@keyframes`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Keyframes {
+    frames: Vec<Keyframe>,
+}
+
+impl Keyframes {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+
+    /// The keyframes, sorted by offset.
+    pub fn frames(&self) -> &[Keyframe] {
+        &self.frames
+    }
+
+    /// Add a keyframe, keeping the frames sorted by offset.
+    pub fn add(&mut self, offset: f32, style: PartialStyle) {
+        let index = self.frames.partition_point(|f| f.offset <= offset);
+        self.frames.insert(index, Keyframe { offset, style });
+    }
+
+    /// Compute the style at position `t` (from 0.0 to 1.0) of the animation. Keyframes are
+    /// applied on top of `base`, which also stands in for missing 0% and 100% frames.
+    pub fn sample(&self, base: &ComputedStyle, t: f32) -> ComputedStyle {
+        let resolve = |frame: &Keyframe| {
+            let mut computed = base.clone();
+            frame.style.apply_to(&mut computed);
+            computed
+        };
+
+        let (start_offset, start) = match self.frames.iter().rev().find(|f| f.offset <= t) {
+            Some(frame) => (frame.offset, resolve(frame)),
+            None => (0., base.clone()),
+        };
+        let (end_offset, end) = match self.frames.iter().find(|f| f.offset > t) {
+            Some(frame) => (frame.offset, resolve(frame)),
+            None => (1., base.clone()),
+        };
+
+        if end_offset <= start_offset {
+            return start;
+        }
+        lerp_computed(
+            &start,
+            &end,
+            (t - start_offset) / (end_offset - start_offset),
+        )
+    }
+
+    /// Parse a keyframe offset, either a percentage or one of the keywords `from` and `to`.
+    pub(crate) fn parse_offset(str: &str) -> Result<f32, GuiseError> {
+        match str {
+            "from" => Ok(0.),
+            "to" => Ok(1.),
+            _ => str
+                .strip_suffix('%')
+                .and_then(|pct| pct.parse::<f32>().ok())
+                .filter(|pct| (0. ..=100.).contains(pct))
+                .map(|pct| pct / 100.)
+                .ok_or(GuiseError::InvalidAttributeValue(str.to_string())),
+        }
+    }
+}
+
+/// Timing function used to ease an animation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2. - t),
+            Easing::EaseInOut => t * t * (3. - 2. * t),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease-in",
+            Easing::EaseOut => "ease-out",
+            Easing::EaseInOut => "ease-in-out",
+        }
+    }
+}
+
+/// The value of an `animation` style attribute, e.g. `pulse 2s infinite ease-in-out`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationSpec {
+    /// Id of the `<keyframes>` element.
+    pub name: String,
+
+    /// Duration of a single iteration, in seconds.
+    pub duration: f32,
+
+    /// Number of iterations, or `None` to repeat forever.
+    pub iterations: Option<u32>,
+
+    pub easing: Easing,
+
+    /// The keyframes referred to by `name`, filled in by the loader.
+    pub keyframes: Option<Arc<Keyframes>>,
+}
+
+impl AnimationSpec {
+    pub(crate) fn parse(str: &str) -> Result<Self, GuiseError> {
+        let mut name: Option<&str> = None;
+        let mut duration = 0.;
+        let mut iterations = Some(1);
+        let mut easing = Easing::Linear;
+        for token in str.split_whitespace() {
+            match token {
+                "infinite" => iterations = None,
+                "linear" => easing = Easing::Linear,
+                "ease-in" => easing = Easing::EaseIn,
+                "ease-out" => easing = Easing::EaseOut,
+                "ease" | "ease-in-out" => easing = Easing::EaseInOut,
+                _ => {
+                    if let Some(ms) = token.strip_suffix("ms").and_then(|t| t.parse::<f32>().ok()) {
+                        duration = ms / 1000.;
+                    } else if let Some(s) =
+                        token.strip_suffix('s').and_then(|t| t.parse::<f32>().ok())
+                    {
+                        duration = s;
+                    } else if let Ok(count) = token.parse::<u32>() {
+                        iterations = Some(count);
+                    } else if name.is_none() {
+                        name = Some(token);
+                    } else {
+                        return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+                    }
+                }
+            }
+        }
+
+        match name {
+            Some(name) => Ok(Self {
+                name: name.to_string(),
+                duration,
+                iterations,
+                easing,
+                keyframes: None,
+            }),
+            None => Err(GuiseError::InvalidAttributeValue(str.to_string())),
+        }
+    }
+
+    /// Returns the eased position within the current iteration after `elapsed` seconds, or
+    /// `None` if the animation has finished.
+    pub fn progress(&self, elapsed: f32) -> Option<f32> {
+        if self.duration <= 0. {
+            return None;
+        }
+        let cycles = elapsed / self.duration;
+        match self.iterations {
+            Some(count) if cycles >= count as f32 => None,
+            _ => Some(self.easing.apply(cycles.fract())),
+        }
+    }
+}
+
+impl fmt::Display for AnimationSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}s ", self.name, self.duration)?;
+        match self.iterations {
+            Some(count) => write!(f, "{} ", count)?,
+            None => write!(f, "infinite ")?,
+        }
+        write!(f, "{}", self.easing.as_str())
+    }
+}
+
+/// Component that tracks an animation on a ui node. A finished animation stays on the node,
+/// so that restyling it with the same animation doesn't play it again.
+#[derive(Component, Debug, Clone)]
+pub struct StyleAnimation {
+    pub spec: AnimationSpec,
+
+    /// The computed style without the animation applied.
+    pub base: ComputedStyle,
+
+    /// Time since the animation started, in seconds.
+    pub elapsed: f32,
+}
+
+impl StyleAnimation {
+    pub fn new(spec: AnimationSpec, base: ComputedStyle) -> Self {
+        Self {
+            spec,
+            base,
+            elapsed: 0.,
+        }
+    }
+
+    /// Compute the animated style at the current elapsed time.
+    pub fn sample(&self) -> ComputedStyle {
+        match (self.spec.progress(self.elapsed), &self.spec.keyframes) {
+            (Some(t), Some(keyframes)) => keyframes.sample(&self.base, t),
+            _ => self.base.clone(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.spec.progress(self.elapsed).is_none()
+    }
+}
+
+/// Advances style animations and writes the interpolated values to the ui node. The last frame
+/// of an animation writes the un-animated style, after which the animation is left alone.
+pub fn animate_styles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut StyleAnimation,
+        &mut Style,
+        Option<&mut BackgroundColor>,
+        Option<&mut BorderColor>,
+    )>,
+) {
+    for (entity, mut anim, mut style, bg, bc) in query.iter_mut() {
+        if anim.is_finished() {
+            continue;
+        }
+        anim.elapsed += time.delta_seconds();
+        let computed = anim.sample();
+        let mut merged = style.clone();
+        copy_style_fields(&computed.style, &mut merged, computed.style_fields);
+        if !style.eq(&merged) {
+            *style = merged;
+        }
+
+        match (bg, computed.rendered_background_color()) {
+            (Some(mut bg), Some(color)) => {
+                if bg.0 != color {
+                    bg.0 = color;
+                }
+            }
+            (None, Some(color)) => {
+                commands.entity(entity).insert(BackgroundColor(color));
+            }
+            (Some(_), None) => {
+                commands.entity(entity).remove::<BackgroundColor>();
+            }
+            (None, None) => {}
+        }
+
+        match (bc, computed.rendered_border_color()) {
+            (Some(mut bc), Some(color)) => {
+                if bc.0 != color {
+                    bc.0 = color;
+                }
+            }
+            (None, Some(color)) => {
+                commands.entity(entity).insert(BorderColor(color));
+            }
+            (Some(_), None) => {
+                commands.entity(entity).remove::<BorderColor>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Interpolate between two computed styles. Colors and lengths are interpolated; everything
+/// else switches from `a` to `b` halfway through.
+fn lerp_computed(a: &ComputedStyle, b: &ComputedStyle, t: f32) -> ComputedStyle {
+    let mut result = if t < 0.5 { a.clone() } else { b.clone() };
+    result.style_fields = a.style_fields.union(b.style_fields);
+    result.background_color = lerp_opt_color(a.background_color, b.background_color, t);
+    result.border_color = lerp_opt_color(a.border_color, b.border_color, t);
+    result.color = lerp_opt_color(a.color, b.color, t);
+
+    let (sa, sb, style) = (&a.style, &b.style, &mut result.style);
+    style.left = lerp_val(sa.left, sb.left, t);
+    style.right = lerp_val(sa.right, sb.right, t);
+    style.top = lerp_val(sa.top, sb.top, t);
+    style.bottom = lerp_val(sa.bottom, sb.bottom, t);
+    style.width = lerp_val(sa.width, sb.width, t);
+    style.height = lerp_val(sa.height, sb.height, t);
+    style.min_width = lerp_val(sa.min_width, sb.min_width, t);
+    style.min_height = lerp_val(sa.min_height, sb.min_height, t);
+    style.max_width = lerp_val(sa.max_width, sb.max_width, t);
+    style.max_height = lerp_val(sa.max_height, sb.max_height, t);
+    style.flex_basis = lerp_val(sa.flex_basis, sb.flex_basis, t);
+    style.row_gap = lerp_val(sa.row_gap, sb.row_gap, t);
+    style.column_gap = lerp_val(sa.column_gap, sb.column_gap, t);
+    style.margin = lerp_uirect(sa.margin, sb.margin, t);
+    style.padding = lerp_uirect(sa.padding, sb.padding, t);
+    style.border = lerp_uirect(sa.border, sb.border, t);
+    result
+}
+
+fn lerp_opt_color(a: Option<Color>, b: Option<Color>, t: f32) -> Option<Color> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let (a, b) = (a.as_rgba_f32(), b.as_rgba_f32());
+            Some(Color::rgba(
+                a[0] + (b[0] - a[0]) * t,
+                a[1] + (b[1] - a[1]) * t,
+                a[2] + (b[2] - a[2]) * t,
+                a[3] + (b[3] - a[3]) * t,
+            ))
+        }
+        _ => {
+            if t < 0.5 {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+/// Interpolate two lengths. Animations don't know the sizes of the layout, so lengths with
+/// different units can't be mixed, and switch halfway through.
+fn lerp_val(a: Val, b: Val, t: f32) -> Val {
+    val_math::lerp(a, b, t, &ValContext::default())
+}
+
+fn lerp_uirect(a: UiRect, b: UiRect, t: f32) -> UiRect {
+    UiRect {
+        left: lerp_val(a.left, b.left, t),
+        right: lerp_val(a.right, b.right, t),
+        top: lerp_val(a.top, b.top, t),
+        bottom: lerp_val(a.bottom, b.bottom, t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::Command;
+
+    use super::*;
+    use crate::guise::style::{StyleAttr, UpdateComputedStyle};
+
+    fn pulse() -> Keyframes {
+        let mut keyframes = Keyframes::new();
+        keyframes.add(
+            1.,
+            PartialStyle::from_attrs(&[
+                StyleAttr::BackgroundColor(Some(Color::rgba(0., 0., 1., 1.))),
+                StyleAttr::Width(Val::Px(100.)),
+            ]),
+        );
+        keyframes.add(
+            0.,
+            PartialStyle::from_attrs(&[
+                StyleAttr::BackgroundColor(Some(Color::rgba(1., 0., 0., 1.))),
+                StyleAttr::Width(Val::Px(0.)),
+            ]),
+        );
+        keyframes
+    }
+
+    #[test]
+    fn test_parse_animation() {
+        let spec = AnimationSpec::parse("pulse 500ms infinite ease-in-out").unwrap();
+        assert_eq!(spec.name, "pulse");
+        assert_eq!(spec.duration, 0.5);
+        assert_eq!(spec.iterations, None);
+        assert_eq!(spec.easing, Easing::EaseInOut);
+        assert_eq!(AnimationSpec::parse(&spec.to_string()).unwrap(), spec);
+
+        let spec = AnimationSpec::parse("2s pulse 3").unwrap();
+        assert_eq!(spec.duration, 2.);
+        assert_eq!(spec.iterations, Some(3));
+        assert!(AnimationSpec::parse("2s").is_err());
+        assert!(AnimationSpec::parse("pulse glow").is_err());
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(Keyframes::parse_offset("from").unwrap(), 0.);
+        assert_eq!(Keyframes::parse_offset("50%").unwrap(), 0.5);
+        assert_eq!(Keyframes::parse_offset("to").unwrap(), 1.);
+        assert!(Keyframes::parse_offset("50").is_err());
+        assert!(Keyframes::parse_offset("150%").is_err());
+    }
+
+    #[test]
+    fn test_sample_midpoint() {
+        let mut spec = AnimationSpec::parse("pulse 2s").unwrap();
+        spec.keyframes = Some(Arc::new(pulse()));
+        let mut anim = StyleAnimation::new(spec, ComputedStyle::default());
+
+        anim.elapsed += 1.;
+        let computed = anim.sample();
+        assert_eq!(
+            computed.background_color,
+            Some(Color::rgba(0.5, 0., 0.5, 1.))
+        );
+        assert_eq!(computed.style.width, Val::Px(50.));
+        assert!(!anim.is_finished());
+
+        anim.elapsed += 1.;
+        assert!(anim.is_finished());
+        assert_eq!(anim.sample(), ComputedStyle::default());
+    }
+
+    #[test]
+    fn test_finished_animation_not_restarted() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let entity = world.spawn(NodeBundle::default()).id();
+        let mut spec = AnimationSpec::parse("pulse 1s").unwrap();
+        spec.keyframes = Some(Arc::new(pulse()));
+        let computed = ComputedStyle {
+            animation: Some(spec),
+            ..default()
+        };
+        UpdateComputedStyle {
+            entity,
+            computed: computed.clone(),
+        }
+        .apply(&mut world);
+
+        world.get_mut::<StyleAnimation>(entity).unwrap().elapsed = 2.;
+        let mut schedule = Schedule::default();
+        schedule.add_systems(animate_styles);
+        schedule.run(&mut world);
+        let anim = world.get::<StyleAnimation>(entity).unwrap();
+        assert!(anim.is_finished());
+
+        // Restyling with the same animation leaves it finished.
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        assert_eq!(world.get::<StyleAnimation>(entity).unwrap().elapsed, 2.);
+    }
+}
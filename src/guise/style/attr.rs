@@ -1,4 +1,9 @@
-use bevy::{prelude::Color, ui::*};
+use bevy::{
+    asset::Handle,
+    prelude::{warn, Color, Vec2},
+    text::{BreakLineOn, Font, TextAlignment, TextStyle},
+    ui::*,
+};
 use lazy_static::lazy_static;
 use quick_xml::events::BytesStart;
 use regex::Regex;
@@ -6,20 +11,62 @@ use std::str::FromStr;
 
 use crate::guise::GuiseError;
 
-use super::ComputedStyle;
+use super::{custom_property, AnimationSpec, ComputedStyle, CustomAttr, RawStyle, StyleField};
 
-/** Set of style attributes that can be applied to construct a style. */
+/** Set of style attributes that can be applied to construct a style.
+
+Each variant holds an already-parsed value, so styles can be built in code by passing a list
+such as `[StyleAttr::Width(Val::Px(10.)), StyleAttr::Color(Color::RED)]` to
+`PartialStyle::from_attrs`. `StyleAttr::parse` builds one from an attribute name and value, as
+written in a `<style>` element. */
 #[derive(Debug, Clone, PartialEq)]
 pub enum StyleAttr {
     BackgroundColor(Option<Color>),
+
+    /// Asset path of an image drawn over the background color, as `url(path)`.
+    BackgroundImage(Option<String>),
     BorderColor(Option<Color>),
-    ZIndex(i32),
+
+    /// Color of the node's text. Inherited by descendants which don't set their own. `initial`
+    /// sets it to Bevy's default text color, which stops the parent's color being inherited.
+    Color(Color),
+    ZIndex(Option<i32>),
+    Opacity(f32),
+    Content(Option<String>),
+    Animation(Option<AnimationSpec>),
+    WhiteSpace(WhiteSpace),
+    /// Maximum number of lines of text, or `None` for no limit.
+    LineClamp(Option<u16>),
+    TextAlign(TextAlign),
+
+    /// Font of the node's text, by the id of a `<font>` declared in the document.
+    Font(FontRef),
+    FontSize(FontSize),
+
+    /// Indent of the first line of text, in px, em or rem. Ems are relative to the node's own
+    /// font size.
+    TextIndent(FontSize),
+    TextShadow(Option<TextShadow>),
+
+    /// Color of an outline drawn around the node in debug builds, for inspecting layout.
+    DebugOutline(Option<Color>),
+
+    /// Clockwise rotation in radians.
+    Rotate(f32),
+    /// Horizontal and vertical scale.
+    Scale(f32, f32),
+    /// Horizontal and vertical offset.
+    Translate(bevy::ui::Val, bevy::ui::Val),
 
     Display(bevy::ui::Display),
     Position(bevy::ui::PositionType),
     Overflow(bevy::ui::OverflowAxis),
     OverflowX(bevy::ui::OverflowAxis),
     OverflowY(bevy::ui::OverflowAxis),
+
+    /// How far outside its bounds a clipping node's content may paint. Bevy 0.11 always clips
+    /// at the node's edge, so this is stored on the computed style but not applied.
+    OverflowClipMargin(bevy::ui::Val),
     Direction(bevy::ui::Direction),
 
     Left(bevy::ui::Val),
@@ -29,12 +76,19 @@ pub enum StyleAttr {
 
     Width(bevy::ui::Val),
     Height(bevy::ui::Val),
+
+    /// `width="max-content"`: the width is measured from the node's text after layout. See
+    /// `MaxContent`.
+    MaxContentWidth,
+    /// `height="max-content"`.
+    MaxContentHeight,
     MinWidth(bevy::ui::Val),
     MinHeight(bevy::ui::Val),
     MaxWidth(bevy::ui::Val),
     MaxHeight(bevy::ui::Val),
 
-    // pub aspect_ratio: StyleProp<f32>,
+    /// Ratio of width to height, or `None` for `auto`.
+    AspectRatio(Option<f32>),
     AlignItems(bevy::ui::AlignItems),
     JustifyItems(bevy::ui::JustifyItems),
     AlignSelf(bevy::ui::AlignSelf),
@@ -60,16 +114,21 @@ pub enum StyleAttr {
     BorderRight(bevy::ui::Val),
     BorderTop(bevy::ui::Val),
     BorderBottom(bevy::ui::Val),
+    BorderRadius(CornerRadii),
+    BorderStyle(BorderStyle),
+    BoxSizing(BoxSizing),
 
     FlexDirection(bevy::ui::FlexDirection),
     FlexWrap(bevy::ui::FlexWrap),
+    Flex(f32, f32, bevy::ui::Val),
     FlexGrow(f32),
     FlexShrink(f32),
     FlexBasis(bevy::ui::Val),
 
     RowGap(bevy::ui::Val),
     ColumnGap(bevy::ui::Val),
-    Gap(bevy::ui::Val),
+    /// Row and column gap.
+    Gap(bevy::ui::Val, bevy::ui::Val),
 
     // TODO:
     GridAutoFlow(bevy::ui::GridAutoFlow),
@@ -85,20 +144,371 @@ pub enum StyleAttr {
     GridColumnStart(i16),
     GridColumnSpan(u16),
     GridColumnEnd(i16),
+
+    /// A property registered with `register_style_property`.
+    Custom(CustomAttr),
+
+    /// Fields of Bevy's `Style` set by reflection, for properties which aren't modeled yet.
+    /// Applied before the other attributes of the same style, so that they take precedence.
+    RawStyle(RawStyle),
+}
+
+/// Radii for the four corners of an element, in CSS order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CornerRadii {
+    pub top_left: Val,
+    pub top_right: Val,
+    pub bottom_right: Val,
+    pub bottom_left: Val,
+}
+
+impl CornerRadii {
+    /// Construct a `CornerRadii` with the same radius for every corner.
+    pub const fn all(val: Val) -> Self {
+        Self {
+            top_left: val,
+            top_right: val,
+            bottom_right: val,
+            bottom_left: val,
+        }
+    }
+}
+
+/// A drop shadow behind text: horizontal and vertical offset, blur radius and color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextShadow {
+    pub offset_x: Val,
+    pub offset_y: Val,
+    pub blur: Val,
+    pub color: Color,
+}
+
+/// The CSS `border-style` property. Bevy 0.11 only draws solid borders, so other styles such
+/// as `dashed` are parsed as `solid`, with a warning.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BorderStyle {
+    #[default]
+    Solid,
+
+    /// No border is drawn, and the border takes up no space, whatever the border widths are.
+    None,
+}
+
+/// What the width and height of a node measure, like the CSS `box-sizing` property. Bevy's
+/// layout always uses border-box sizing; content-box sizes are converted when the style is
+/// resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoxSizing {
+    /// Sizes include the padding and border. This is Bevy's native behavior.
+    #[default]
+    BorderBox,
+
+    /// Sizes exclude the padding and border, which are added on. This only works when the size
+    /// and the padding and border on that axis are all in pixels; other sizes can't be added
+    /// without layout, and are left as they are.
+    ContentBox,
+}
+
+/// How whitespace within text is handled, like the CSS `white-space` property.
+///
+/// | value      | collapses whitespace | `BreakLineOn`  |
+/// |------------|----------------------|----------------|
+/// | `normal`   | yes                  | `WordBoundary` |
+/// | `nowrap`   | yes                  | `WordBoundary` |
+/// | `pre`      | no                   | `WordBoundary` |
+/// | `pre-wrap` | no                   | `WordBoundary` |
+///
+/// Bevy 0.11 has no way to turn line wrapping off, so `nowrap` and `pre` still wrap.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WhiteSpace {
+    #[default]
+    Normal,
+    NoWrap,
+    Pre,
+    PreWrap,
+}
+
+impl WhiteSpace {
+    /// Whether runs of whitespace, including newlines, collapse into a single space.
+    pub fn collapses(&self) -> bool {
+        matches!(self, WhiteSpace::Normal | WhiteSpace::NoWrap)
+    }
+
+    pub fn line_break(&self) -> BreakLineOn {
+        BreakLineOn::WordBoundary
+    }
+
+    /// Convert template text into the text to display. When whitespace is preserved, a single
+    /// newline directly after the opening tag is dropped, as in HTML.
+    ///
+    /// The escape `\n` is a line break even where whitespace collapses, and `\\` is a single
+    /// backslash. Other backslashes are left as they are. Bevy breaks lines at these whatever
+    /// the `BreakLineOn` setting.
+    pub fn process(&self, text: &str) -> String {
+        let text = if self.collapses() {
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            text.strip_prefix("\r\n")
+                .or_else(|| text.strip_prefix('\n'))
+                .unwrap_or(text)
+                .to_string()
+        };
+        if !text.contains('\\') {
+            return text;
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.clone().next() {
+                Some('n') => {
+                    chars.next();
+                    result.push('\n');
+                }
+                Some('\\') => {
+                    chars.next();
+                    result.push('\\');
+                }
+                _ => result.push('\\'),
+            }
+        }
+        if self.collapses() {
+            // Drop the collapsed spaces on either side of a line break.
+            result = result
+                .split('\n')
+                .map(|line| line.trim_matches(' '))
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        result
+    }
+}
+
+/// Horizontal alignment of text, like the CSS `text-align` property. `start` and `end` depend
+/// on the node's `direction`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextAlign {
+    #[default]
+    Start,
+    End,
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    /// Convert to the physical alignment used by Bevy, given the node's direction. An
+    /// inherited direction is treated as left-to-right, since it isn't known here.
+    pub fn resolve(&self, direction: bevy::ui::Direction) -> TextAlignment {
+        let rtl = direction == bevy::ui::Direction::RightToLeft;
+        match self {
+            TextAlign::Left => TextAlignment::Left,
+            TextAlign::Center => TextAlignment::Center,
+            TextAlign::Right => TextAlignment::Right,
+            TextAlign::Start if rtl => TextAlignment::Right,
+            TextAlign::Start => TextAlignment::Left,
+            TextAlign::End if rtl => TextAlignment::Left,
+            TextAlign::End => TextAlignment::Right,
+        }
+    }
+}
+
+/// The value of a `font` style attribute: the id of a `<font>` element, which maps it to a font
+/// file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontRef {
+    /// Id of the `<font>` element.
+    pub name: String,
+
+    /// The font referred to by `name`, filled in by the loader.
+    pub handle: Option<Handle<Font>>,
+}
+
+/// The size of a node's text, like the CSS `font-size` property.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontSize {
+    /// Size in pixels.
+    Px(f32),
+
+    /// Percentage of the inherited font size.
+    Percent(f32),
+
+    /// Multiple of the inherited font size.
+    Em(f32),
+
+    /// Multiple of the root font size.
+    Rem(f32),
+}
+
+impl FontSize {
+    /// Convert to pixels, given the font size inherited from the parent and the root font
+    /// size.
+    pub fn resolve(&self, inherited: f32, root: f32) -> f32 {
+        match self {
+            FontSize::Px(px) => *px,
+            FontSize::Percent(pct) => inherited * pct / 100.,
+            FontSize::Em(em) => inherited * em,
+            FontSize::Rem(rem) => root * rem,
+        }
+    }
 }
 
 impl StyleAttr {
+    /// Whether the attribute named `name` takes a color value.
+    pub(crate) fn is_color(name: &[u8]) -> bool {
+        matches!(name, b"background-color" | b"border-color" | b"color")
+    }
+
+    /// The name of this attribute, as used in a `<style>` element.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StyleAttr::BackgroundColor(..) => "background-color",
+            StyleAttr::BackgroundImage(..) => "background-image",
+            StyleAttr::BorderColor(..) => "border-color",
+            StyleAttr::Color(..) => "color",
+            StyleAttr::ZIndex(..) => "z-index",
+            StyleAttr::Opacity(..) => "opacity",
+            StyleAttr::Content(..) => "content",
+            StyleAttr::Animation(..) => "animation",
+            StyleAttr::WhiteSpace(..) => "white-space",
+            StyleAttr::LineClamp(..) => "line-clamp",
+            StyleAttr::TextAlign(..) => "text-align",
+            StyleAttr::Font(..) => "font",
+            StyleAttr::FontSize(..) => "font-size",
+            StyleAttr::TextIndent(..) => "text-indent",
+            StyleAttr::TextShadow(..) => "text-shadow",
+            StyleAttr::DebugOutline(..) => "debug-outline",
+            StyleAttr::Rotate(..) => "rotate",
+            StyleAttr::Scale(..) => "scale",
+            StyleAttr::Translate(..) => "translate",
+            StyleAttr::Display(..) => "display",
+            StyleAttr::Position(..) => "position",
+            StyleAttr::Overflow(..) => "overflow",
+            StyleAttr::OverflowX(..) => "overflow-x",
+            StyleAttr::OverflowY(..) => "overflow-y",
+            StyleAttr::OverflowClipMargin(..) => "overflow-clip-margin",
+            StyleAttr::Direction(..) => "direction",
+            StyleAttr::Left(..) => "left",
+            StyleAttr::Right(..) => "right",
+            StyleAttr::Top(..) => "top",
+            StyleAttr::Bottom(..) => "bottom",
+            StyleAttr::Width(..) | StyleAttr::MaxContentWidth => "width",
+            StyleAttr::Height(..) | StyleAttr::MaxContentHeight => "height",
+            StyleAttr::MinWidth(..) => "min-width",
+            StyleAttr::MinHeight(..) => "min-height",
+            StyleAttr::MaxWidth(..) => "max-width",
+            StyleAttr::MaxHeight(..) => "max-height",
+            StyleAttr::AspectRatio(..) => "aspect-ratio",
+            StyleAttr::AlignItems(..) => "align-items",
+            StyleAttr::JustifyItems(..) => "justify-items",
+            StyleAttr::AlignSelf(..) => "align-self",
+            StyleAttr::JustifySelf(..) => "justify-self",
+            StyleAttr::AlignContent(..) => "align-content",
+            StyleAttr::JustifyContent(..) => "justify-content",
+            StyleAttr::Margin(..) => "margin",
+            StyleAttr::MarginLeft(..) => "margin-left",
+            StyleAttr::MarginRight(..) => "margin-right",
+            StyleAttr::MarginTop(..) => "margin-top",
+            StyleAttr::MarginBottom(..) => "margin-bottom",
+            StyleAttr::Padding(..) => "padding",
+            StyleAttr::PaddingLeft(..) => "padding-left",
+            StyleAttr::PaddingRight(..) => "padding-right",
+            StyleAttr::PaddingTop(..) => "padding-top",
+            StyleAttr::PaddingBottom(..) => "padding-bottom",
+            StyleAttr::Border(..) => "border",
+            StyleAttr::BorderLeft(..) => "border-left",
+            StyleAttr::BorderRight(..) => "border-right",
+            StyleAttr::BorderTop(..) => "border-top",
+            StyleAttr::BorderBottom(..) => "border-bottom",
+            StyleAttr::BorderRadius(..) => "border-radius",
+            StyleAttr::BorderStyle(..) => "border-style",
+            StyleAttr::BoxSizing(..) => "box-sizing",
+            StyleAttr::FlexDirection(..) => "flex-direction",
+            StyleAttr::FlexWrap(..) => "flex-wrap",
+            StyleAttr::Flex(..) => "flex",
+            StyleAttr::FlexGrow(..) => "flex-grow",
+            StyleAttr::FlexShrink(..) => "flex-shrink",
+            StyleAttr::FlexBasis(..) => "flex-basis",
+            StyleAttr::RowGap(..) => "row-gap",
+            StyleAttr::ColumnGap(..) => "column-gap",
+            StyleAttr::Gap(..) => "gap",
+            StyleAttr::GridAutoFlow(..) => "grid-auto-flow",
+            StyleAttr::GridRow(..) => "grid-row",
+            StyleAttr::GridRowStart(..) => "grid-row-start",
+            StyleAttr::GridRowSpan(..) => "grid-row-span",
+            StyleAttr::GridRowEnd(..) => "grid-row-end",
+            StyleAttr::GridColumn(..) => "grid-column",
+            StyleAttr::GridColumnStart(..) => "grid-column-start",
+            StyleAttr::GridColumnSpan(..) => "grid-column-span",
+            StyleAttr::GridColumnEnd(..) => "grid-column-end",
+            StyleAttr::Custom(attr) => attr.property.name,
+            StyleAttr::RawStyle(_) => "bevy-style",
+        }
+    }
+
     /// Apply this style attribute to a computed style.
     pub fn apply(&self, computed: &mut ComputedStyle) {
         match self {
             StyleAttr::BackgroundColor(val) => {
                 computed.background_color = *val;
             }
+            StyleAttr::BackgroundImage(val) => {
+                computed.background_image = val.clone();
+            }
             StyleAttr::BorderColor(val) => {
                 computed.border_color = *val;
             }
+            StyleAttr::Color(val) => {
+                computed.color = Some(*val);
+            }
             StyleAttr::ZIndex(val) => {
-                computed.z_index = Some(*val);
+                computed.z_index = *val;
+            }
+            StyleAttr::Opacity(val) => {
+                computed.opacity = Some(*val);
+            }
+            StyleAttr::Content(val) => {
+                computed.content = val.clone();
+            }
+            StyleAttr::Animation(val) => {
+                computed.animation = val.clone();
+            }
+            StyleAttr::WhiteSpace(val) => {
+                computed.white_space = *val;
+            }
+            StyleAttr::LineClamp(val) => {
+                computed.line_clamp = *val;
+            }
+            StyleAttr::TextAlign(val) => {
+                computed.text_align = *val;
+            }
+            StyleAttr::Font(val) => {
+                computed.font = Some(val.clone());
+            }
+            StyleAttr::FontSize(val) => {
+                computed.font_size = Some(*val);
+            }
+            StyleAttr::TextIndent(val) => {
+                computed.text_indent = Some(*val);
+            }
+            StyleAttr::TextShadow(val) => {
+                computed.text_shadow = *val;
+            }
+            StyleAttr::DebugOutline(val) => {
+                computed.debug_outline = *val;
+            }
+            StyleAttr::Rotate(val) => {
+                computed.transform.rotate = *val;
+            }
+            StyleAttr::Scale(x, y) => {
+                computed.transform.scale = Vec2::new(*x, *y);
+            }
+            StyleAttr::Translate(x, y) => {
+                computed.transform.translate = (*x, *y);
             }
 
             StyleAttr::Display(val) => {
@@ -117,6 +527,9 @@ impl StyleAttr {
             StyleAttr::OverflowY(val) => {
                 computed.style.overflow.y = *val;
             }
+            StyleAttr::OverflowClipMargin(val) => {
+                computed.overflow_clip_margin = Some(*val);
+            }
             StyleAttr::Direction(val) => {
                 computed.style.direction = *val;
             }
@@ -136,9 +549,17 @@ impl StyleAttr {
 
             StyleAttr::Width(val) => {
                 computed.style.width = *val;
+                computed.max_content.x = false;
             }
             StyleAttr::Height(val) => {
                 computed.style.height = *val;
+                computed.max_content.y = false;
+            }
+            StyleAttr::MaxContentWidth => {
+                computed.max_content.x = true;
+            }
+            StyleAttr::MaxContentHeight => {
+                computed.max_content.y = true;
             }
             StyleAttr::MinWidth(val) => {
                 computed.style.min_width = *val;
@@ -152,6 +573,11 @@ impl StyleAttr {
             StyleAttr::MaxHeight(val) => {
                 computed.style.max_height = *val;
             }
+            StyleAttr::AspectRatio(val) => {
+                // TODO: Fit the background image within the box given by the aspect ratio,
+                // according to an `object-fit` attribute.
+                computed.style.aspect_ratio = *val;
+            }
 
             StyleAttr::AlignItems(val) => {
                 computed.style.align_items = *val;
@@ -219,6 +645,15 @@ impl StyleAttr {
             StyleAttr::BorderBottom(val) => {
                 computed.style.border.bottom = *val;
             }
+            StyleAttr::BorderRadius(val) => {
+                computed.border_radius = Some(*val);
+            }
+            StyleAttr::BorderStyle(val) => {
+                computed.border_style = *val;
+            }
+            StyleAttr::BoxSizing(val) => {
+                computed.box_sizing = *val;
+            }
 
             StyleAttr::FlexDirection(val) => {
                 computed.style.flex_direction = *val;
@@ -226,6 +661,11 @@ impl StyleAttr {
             StyleAttr::FlexWrap(val) => {
                 computed.style.flex_wrap = *val;
             }
+            StyleAttr::Flex(grow, shrink, basis) => {
+                computed.style.flex_grow = *grow;
+                computed.style.flex_shrink = *shrink;
+                computed.style.flex_basis = *basis;
+            }
             StyleAttr::FlexGrow(val) => {
                 computed.style.flex_grow = *val;
             }
@@ -242,9 +682,9 @@ impl StyleAttr {
             StyleAttr::ColumnGap(val) => {
                 computed.style.column_gap = *val;
             }
-            StyleAttr::Gap(val) => {
-                computed.style.row_gap = *val;
-                computed.style.column_gap = *val;
+            StyleAttr::Gap(row, column) => {
+                computed.style.row_gap = *row;
+                computed.style.column_gap = *column;
             }
 
             StyleAttr::GridAutoFlow(val) => {
@@ -275,11 +715,138 @@ impl StyleAttr {
             StyleAttr::GridColumnEnd(val) => {
                 computed.style.grid_column.set_end(*val);
             }
+            StyleAttr::Custom(attr) => (attr.property.apply)(&attr.value, computed),
+            StyleAttr::RawStyle(raw) => raw.apply(computed),
+        }
+
+        for field in self.style_fields() {
+            computed.style_fields.insert(*field);
+        }
+    }
+
+    /// The fields of Bevy's `Style` which this attribute sets.
+    pub fn style_fields(&self) -> &'static [StyleField] {
+        match self {
+            StyleAttr::BackgroundColor(..)
+            | StyleAttr::BackgroundImage(..)
+            | StyleAttr::BorderColor(..)
+            | StyleAttr::Color(..)
+            | StyleAttr::ZIndex(..)
+            | StyleAttr::Opacity(..)
+            | StyleAttr::Content(..)
+            | StyleAttr::Animation(..)
+            | StyleAttr::WhiteSpace(..)
+            | StyleAttr::LineClamp(..)
+            | StyleAttr::TextAlign(..)
+            | StyleAttr::Font(..)
+            | StyleAttr::FontSize(..)
+            | StyleAttr::TextIndent(..)
+            | StyleAttr::TextShadow(..)
+            | StyleAttr::OverflowClipMargin(..)
+            | StyleAttr::DebugOutline(..)
+            | StyleAttr::Rotate(..)
+            | StyleAttr::Scale(..)
+            | StyleAttr::Translate(..)
+            | StyleAttr::BorderRadius(..) => &[],
+
+            // Set after layout by `update_max_content`, rather than by the style.
+            StyleAttr::MaxContentWidth | StyleAttr::MaxContentHeight => &[],
+
+            // `none` hides the border by overriding its widths.
+            StyleAttr::BorderStyle(BorderStyle::None) => &[StyleField::Border],
+            StyleAttr::BorderStyle(BorderStyle::Solid) => &[],
+            StyleAttr::BoxSizing(..) => &[],
+
+            StyleAttr::Display(..) => &[StyleField::Display],
+            StyleAttr::Position(..) => &[StyleField::PositionType],
+            StyleAttr::Overflow(..) | StyleAttr::OverflowX(..) | StyleAttr::OverflowY(..) => {
+                &[StyleField::Overflow]
+            }
+            StyleAttr::Direction(..) => &[StyleField::Direction],
+
+            StyleAttr::Left(..) => &[StyleField::Left],
+            StyleAttr::Right(..) => &[StyleField::Right],
+            StyleAttr::Top(..) => &[StyleField::Top],
+            StyleAttr::Bottom(..) => &[StyleField::Bottom],
+
+            StyleAttr::Width(..) => &[StyleField::Width],
+            StyleAttr::Height(..) => &[StyleField::Height],
+            StyleAttr::MinWidth(..) => &[StyleField::MinWidth],
+            StyleAttr::MinHeight(..) => &[StyleField::MinHeight],
+            StyleAttr::MaxWidth(..) => &[StyleField::MaxWidth],
+            StyleAttr::MaxHeight(..) => &[StyleField::MaxHeight],
+            StyleAttr::AspectRatio(..) => &[StyleField::AspectRatio],
+
+            StyleAttr::AlignItems(..) => &[StyleField::AlignItems],
+            StyleAttr::JustifyItems(..) => &[StyleField::JustifyItems],
+            StyleAttr::AlignSelf(..) => &[StyleField::AlignSelf],
+            StyleAttr::JustifySelf(..) => &[StyleField::JustifySelf],
+            StyleAttr::AlignContent(..) => &[StyleField::AlignContent],
+            StyleAttr::JustifyContent(..) => &[StyleField::JustifyContent],
+
+            StyleAttr::Margin(..)
+            | StyleAttr::MarginLeft(..)
+            | StyleAttr::MarginRight(..)
+            | StyleAttr::MarginTop(..)
+            | StyleAttr::MarginBottom(..) => &[StyleField::Margin],
+
+            StyleAttr::Padding(..)
+            | StyleAttr::PaddingLeft(..)
+            | StyleAttr::PaddingRight(..)
+            | StyleAttr::PaddingTop(..)
+            | StyleAttr::PaddingBottom(..) => &[StyleField::Padding],
+
+            StyleAttr::Border(..)
+            | StyleAttr::BorderLeft(..)
+            | StyleAttr::BorderRight(..)
+            | StyleAttr::BorderTop(..)
+            | StyleAttr::BorderBottom(..) => &[StyleField::Border],
+
+            StyleAttr::FlexDirection(..) => &[StyleField::FlexDirection],
+            StyleAttr::FlexWrap(..) => &[StyleField::FlexWrap],
+            StyleAttr::Flex(..) => &[
+                StyleField::FlexGrow,
+                StyleField::FlexShrink,
+                StyleField::FlexBasis,
+            ],
+            StyleAttr::FlexGrow(..) => &[StyleField::FlexGrow],
+            StyleAttr::FlexShrink(..) => &[StyleField::FlexShrink],
+            StyleAttr::FlexBasis(..) => &[StyleField::FlexBasis],
+
+            StyleAttr::RowGap(..) => &[StyleField::RowGap],
+            StyleAttr::ColumnGap(..) => &[StyleField::ColumnGap],
+            StyleAttr::Gap(..) => &[StyleField::RowGap, StyleField::ColumnGap],
+
+            StyleAttr::GridAutoFlow(..) => &[StyleField::GridAutoFlow],
+            StyleAttr::GridRow(..)
+            | StyleAttr::GridRowStart(..)
+            | StyleAttr::GridRowSpan(..)
+            | StyleAttr::GridRowEnd(..) => &[StyleField::GridRow],
+            StyleAttr::GridColumn(..)
+            | StyleAttr::GridColumnStart(..)
+            | StyleAttr::GridColumnSpan(..)
+            | StyleAttr::GridColumnEnd(..) => &[StyleField::GridColumn],
+            // Raw styles record their own fields when applied.
+            StyleAttr::Custom(_) | StyleAttr::RawStyle(_) => &[],
         }
     }
 
-    /// Parse a `StyleAttr` from an XML attribute name/value pair.
+    /// Parse a `StyleAttr` from an XML attribute name/value pair. Errors which don't already
+    /// name the attribute are wrapped in `GuiseError::InvalidAttribute`.
     pub fn parse<'a>(name: &'a [u8], value: &str) -> Result<Option<Self>, GuiseError> {
+        StyleAttr::parse_value(name, value).map_err(|error| {
+            if error.names_attribute() {
+                return error;
+            }
+            GuiseError::InvalidAttribute {
+                name: String::from_utf8_lossy(name).to_string(),
+                location: None,
+                error: Box::new(error),
+            }
+        })
+    }
+
+    fn parse_value<'a>(name: &'a [u8], value: &str) -> Result<Option<Self>, GuiseError> {
         Ok(Some(match name {
             b"background-color" => StyleAttr::BackgroundColor(if value == "transparent" {
                 None
@@ -287,55 +854,149 @@ impl StyleAttr {
                 Some(StyleAttr::parse_color(value)?)
             }),
 
+            b"background-image" => StyleAttr::BackgroundImage(if value == "none" {
+                None
+            } else {
+                Some(StyleAttr::parse_url(value)?)
+            }),
+
             b"border-color" => StyleAttr::BorderColor(if value == "transparent" {
                 None
             } else {
                 Some(StyleAttr::parse_color(value)?)
             }),
 
-            b"z-index" => StyleAttr::ZIndex(StyleAttr::parse_i32(value)?),
+            b"color" => StyleAttr::Color(if value == "initial" {
+                TextStyle::default().color
+            } else {
+                StyleAttr::parse_color(value)?
+            }),
 
-            b"display" => StyleAttr::Display(match value {
-                "none" => Display::None,
-                "grid" => Display::Grid,
-                "flex" => Display::Flex,
-                _ => {
-                    return Err(GuiseError::UnknownAttributeValue(value.to_string()));
-                }
+            b"z-index" => StyleAttr::ZIndex(if value == "auto" {
+                None
+            } else {
+                Some(StyleAttr::parse_integer("z-index", value)?)
             }),
 
-            b"position" => StyleAttr::Position(match value {
-                "absolute" => PositionType::Absolute,
-                "relative" => PositionType::Relative,
+            b"opacity" => StyleAttr::Opacity(StyleAttr::parse_opacity(value)?),
+            b"content" => StyleAttr::Content(StyleAttr::parse_content(value)),
+            b"animation" => StyleAttr::Animation(if value == "none" {
+                None
+            } else {
+                Some(AnimationSpec::parse(value)?)
+            }),
+            b"text-shadow" => StyleAttr::TextShadow(if value == "none" {
+                None
+            } else {
+                Some(StyleAttr::parse_text_shadow(value)?)
+            }),
+            b"debug-outline" => StyleAttr::DebugOutline(if value == "none" {
+                None
+            } else {
+                Some(StyleAttr::parse_color(value)?)
+            }),
+            b"rotate" => StyleAttr::Rotate(StyleAttr::parse_angle(value)?),
+            b"scale" => StyleAttr::parse_scale(value)?,
+            b"translate" => StyleAttr::parse_translate(value)?,
+            b"white-space" => StyleAttr::WhiteSpace(match value {
+                "normal" => WhiteSpace::Normal,
+                "nowrap" => WhiteSpace::NoWrap,
+                "pre" => WhiteSpace::Pre,
+                "pre-wrap" => WhiteSpace::PreWrap,
                 _ => {
                     return Err(GuiseError::UnknownAttributeValue(value.to_string()));
                 }
             }),
-
-            b"overflow" => StyleAttr::Overflow(match value {
-                "clip" => OverflowAxis::Clip,
-                "visible" => OverflowAxis::Visible,
+            b"line-clamp" => StyleAttr::LineClamp(match value {
+                "none" => None,
+                _ => Some(StyleAttr::parse_span("line-clamp", value)?),
+            }),
+            b"font" => {
+                if value.is_empty() || value.contains(char::is_whitespace) {
+                    return Err(GuiseError::InvalidAttributeValue(value.to_string()));
+                }
+                StyleAttr::Font(FontRef {
+                    name: value.to_string(),
+                    handle: None,
+                })
+            }
+            // Like the root, `initial` uses the root font size rather than the inherited one.
+            b"font-size" => StyleAttr::FontSize(if value == "initial" {
+                FontSize::Rem(1.)
+            } else {
+                StyleAttr::parse_font_size(value)?
+            }),
+            // A percentage would be of the containing block's width, which isn't known until
+            // after layout.
+            b"text-indent" => StyleAttr::TextIndent(match StyleAttr::parse_font_size(value)? {
+                FontSize::Percent(_) => {
+                    return Err(GuiseError::InvalidAttributeValue(value.to_string()));
+                }
+                length => length,
+            }),
+            b"text-align" => StyleAttr::TextAlign(match value {
+                "start" => TextAlign::Start,
+                "end" => TextAlign::End,
+                "left" => TextAlign::Left,
+                "center" => TextAlign::Center,
+                "right" => TextAlign::Right,
                 _ => {
                     return Err(GuiseError::UnknownAttributeValue(value.to_string()));
                 }
             }),
 
-            b"overflow-x" => StyleAttr::OverflowX(match value {
-                "clip" => OverflowAxis::Clip,
-                "visible" => OverflowAxis::Visible,
+            b"display" => StyleAttr::Display(match value {
+                "none" => Display::None,
+                "grid" => Display::Grid,
+                "flex" => Display::Flex,
+                // Bevy 0.11 only has flex and grid layout, so `block`, `inline` and the rest are
+                // rejected rather than quietly approximated.
                 _ => {
                     return Err(GuiseError::UnknownAttributeValue(value.to_string()));
                 }
             }),
 
-            b"overflow-y" => StyleAttr::OverflowY(match value {
-                "clip" => OverflowAxis::Clip,
-                "visible" => OverflowAxis::Visible,
+            b"position" => StyleAttr::Position(match value {
+                "absolute" => PositionType::Absolute,
+                "relative" => PositionType::Relative,
+                "static" | "fixed" | "sticky" => {
+                    return Err(GuiseError::UnsupportedAttributeValue {
+                        name: "position".to_string(),
+                        value: value.to_string(),
+                        reason: match value {
+                            "static" => "Bevy always applies offsets; use `relative` without them",
+                            "fixed" => "use `absolute` on a node at the root of the view instead",
+                            _ => "Bevy has no scroll-dependent positioning",
+                        }
+                        .to_string(),
+                    });
+                }
                 _ => {
                     return Err(GuiseError::UnknownAttributeValue(value.to_string()));
                 }
             }),
 
+            b"overflow" => StyleAttr::Overflow(StyleAttr::parse_overflow(value)?),
+
+            b"overflow-x" => StyleAttr::OverflowX(StyleAttr::parse_overflow(value)?),
+
+            b"overflow-y" => StyleAttr::OverflowY(StyleAttr::parse_overflow(value)?),
+
+            b"overflow-clip-margin" => {
+                // As in CSS, a non-negative length.
+                let margin = StyleAttr::parse_val(value)?;
+                if matches!(margin, Val::Percent(_) | Val::Auto) || value.starts_with('-') {
+                    return Err(GuiseError::InvalidAttributeValue(value.to_string()));
+                }
+                if margin != Val::Px(0.) {
+                    warn!(
+                        "Overflow clip margin '{}' is not supported, clipping at the node's edge",
+                        value
+                    );
+                }
+                StyleAttr::OverflowClipMargin(margin)
+            }
+
             b"direction" => StyleAttr::Direction(match value {
                 "inherit" => bevy::ui::Direction::Inherit,
                 "ltr" => bevy::ui::Direction::LeftToRight,
@@ -350,12 +1011,19 @@ impl StyleAttr {
             b"top" => StyleAttr::Top(StyleAttr::parse_val(value)?),
             b"bottom" => StyleAttr::Bottom(StyleAttr::parse_val(value)?),
 
-            b"width" => StyleAttr::Width(StyleAttr::parse_val(value)?),
-            b"height" => StyleAttr::Height(StyleAttr::parse_val(value)?),
+            b"width" => match value {
+                "max-content" => StyleAttr::MaxContentWidth,
+                _ => StyleAttr::Width(StyleAttr::parse_val(value)?),
+            },
+            b"height" => match value {
+                "max-content" => StyleAttr::MaxContentHeight,
+                _ => StyleAttr::Height(StyleAttr::parse_val(value)?),
+            },
             b"min-width" => StyleAttr::MinWidth(StyleAttr::parse_val(value)?),
             b"min-height" => StyleAttr::MinHeight(StyleAttr::parse_val(value)?),
             b"max-width" => StyleAttr::MaxWidth(StyleAttr::parse_val(value)?),
             b"max-height" => StyleAttr::MaxHeight(StyleAttr::parse_val(value)?),
+            b"aspect-ratio" => StyleAttr::AspectRatio(StyleAttr::parse_aspect_ratio(value)?),
 
             //     // pub aspect_ratio: StyleProp<f32>,
             b"align-items" => StyleAttr::AlignItems(match value {
@@ -458,6 +1126,28 @@ impl StyleAttr {
             b"border-right" => StyleAttr::BorderRight(StyleAttr::parse_val(value)?),
             b"border-top" => StyleAttr::BorderTop(StyleAttr::parse_val(value)?),
             b"border-bottom" => StyleAttr::BorderBottom(StyleAttr::parse_val(value)?),
+            b"border-radius" => StyleAttr::BorderRadius(StyleAttr::parse_corner_radii(value)?),
+            b"border-style" => StyleAttr::BorderStyle(match value {
+                "solid" => BorderStyle::Solid,
+                "none" | "hidden" => BorderStyle::None,
+                "dashed" | "dotted" | "double" | "groove" | "ridge" | "inset" | "outset" => {
+                    warn!(
+                        "Border style '{}' is not supported, drawing a solid border",
+                        value
+                    );
+                    BorderStyle::Solid
+                }
+                _ => {
+                    return Err(GuiseError::UnknownAttributeValue(value.to_string()));
+                }
+            }),
+            b"box-sizing" => StyleAttr::BoxSizing(match value {
+                "border-box" => BoxSizing::BorderBox,
+                "content-box" => BoxSizing::ContentBox,
+                _ => {
+                    return Err(GuiseError::UnknownAttributeValue(value.to_string()));
+                }
+            }),
 
             b"flex-direction" => StyleAttr::FlexDirection(match value {
                 "row" => FlexDirection::Row,
@@ -478,15 +1168,14 @@ impl StyleAttr {
                 }
             }),
 
-            // TODO: Allow shortcut forms for flex.
-            b"flex" => StyleAttr::FlexGrow(StyleAttr::parse_f32(value)?),
+            b"flex" => StyleAttr::parse_flex(value)?,
             b"flex-grow" => StyleAttr::FlexGrow(StyleAttr::parse_f32(value)?),
             b"flex-shrink" => StyleAttr::FlexShrink(StyleAttr::parse_f32(value)?),
-            b"flex-basis" => StyleAttr::FlexBasis(StyleAttr::parse_val(value)?),
+            b"flex-basis" => StyleAttr::FlexBasis(StyleAttr::parse_flex_basis(value)?),
 
             b"row-gap" => StyleAttr::RowGap(StyleAttr::parse_val(value)?),
             b"column-gap" => StyleAttr::ColumnGap(StyleAttr::parse_val(value)?),
-            b"gap" => StyleAttr::Gap(StyleAttr::parse_val(value)?),
+            b"gap" => StyleAttr::parse_gap(value)?,
 
             b"grid-auto-flow" => StyleAttr::GridAutoFlow(match value {
                 "row" => GridAutoFlow::Row,
@@ -502,374 +1191,366 @@ impl StyleAttr {
             //     // pub grid_template_columns: Option<Vec<RepeatedGridTrack>>,
             //     // pub grid_auto_rows: Option<Vec<GridTrack>>,
             //     // pub grid_auto_columns: Option<Vec<GridTrack>>,
-            b"grid-row" => StyleAttr::GridRow(StyleAttr::parse_grid_placement(value)?),
-            b"grid-row-start" => StyleAttr::GridRowStart(StyleAttr::parse_i16(value)?),
-            b"grid-row-span" => StyleAttr::GridRowSpan(StyleAttr::parse_u16(value)?),
-            b"grid-row-end" => StyleAttr::GridRowEnd(StyleAttr::parse_i16(value)?),
-            b"grid-column" => StyleAttr::GridColumn(StyleAttr::parse_grid_placement(value)?),
-            b"grid-column-start" => StyleAttr::GridColumnStart(StyleAttr::parse_i16(value)?),
-            b"grid-column-span" => StyleAttr::GridColumnSpan(StyleAttr::parse_u16(value)?),
-            b"grid-column-end" => StyleAttr::GridColumnEnd(StyleAttr::parse_i16(value)?),
-            _ => return Ok(None),
+            b"grid-row" => StyleAttr::GridRow(StyleAttr::parse_grid_placement("grid-row", value)?),
+            b"grid-row-start" => {
+                StyleAttr::GridRowStart(StyleAttr::parse_line("grid-row-start", value)?)
+            }
+            b"grid-row-span" => {
+                StyleAttr::GridRowSpan(StyleAttr::parse_span("grid-row-span", value)?)
+            }
+            b"grid-row-end" => StyleAttr::GridRowEnd(StyleAttr::parse_line("grid-row-end", value)?),
+            b"grid-column" => {
+                StyleAttr::GridColumn(StyleAttr::parse_grid_placement("grid-column", value)?)
+            }
+            b"grid-column-start" => {
+                StyleAttr::GridColumnStart(StyleAttr::parse_line("grid-column-start", value)?)
+            }
+            b"grid-column-span" => {
+                StyleAttr::GridColumnSpan(StyleAttr::parse_span("grid-column-span", value)?)
+            }
+            b"grid-column-end" => {
+                StyleAttr::GridColumnEnd(StyleAttr::parse_line("grid-column-end", value)?)
+            }
+            b"bevy-style" => StyleAttr::RawStyle(RawStyle::parse(value)?),
+            _ => match std::str::from_utf8(name).ok().and_then(custom_property) {
+                Some(property) => StyleAttr::Custom(CustomAttr::parse(property, value)?),
+                None => return Ok(None),
+            },
         }))
     }
 
+    /// Add this attribute to an XML element. Grid placements which can't be written in a form
+    /// that `parse` accepts are skipped with a warning.
     pub fn write_xml(&self, elem: &mut BytesStart) {
-        match self {
-            StyleAttr::BackgroundColor(Some(col)) => {
-                elem.push_attribute(("background-color", StyleAttr::color_to_str(*col).as_str()));
-            }
-            StyleAttr::BackgroundColor(None) => {
-                elem.push_attribute(("background-color", "transparent"));
+        if let StyleAttr::GridRow(placement) | StyleAttr::GridColumn(placement) = self {
+            if StyleAttr::grid_placement_to_str(*placement).is_none() {
+                warn!(
+                    "Can't write '{}' placement {:?}, skipping it",
+                    self.name(),
+                    placement
+                );
+                return;
             }
+        }
+        elem.push_attribute((self.name(), self.to_css_string().as_str()));
+    }
 
-            StyleAttr::BorderColor(Some(col)) => {
-                elem.push_attribute(("border-color", StyleAttr::color_to_str(*col).as_str()));
-            }
-            StyleAttr::BorderColor(None) => {
-                elem.push_attribute(("border-color", "transparent"));
-            }
+    /// Convert the attribute's value into the CSS-style string it would be parsed from. Grid
+    /// placements which can't be written that way come out as `auto`.
+    pub fn to_css_string(&self) -> String {
+        match self {
+            StyleAttr::BackgroundColor(Some(col)) => StyleAttr::color_to_str(*col),
+            StyleAttr::BackgroundColor(None) => "transparent".to_string(),
 
-            StyleAttr::ZIndex(val) => {
-                elem.push_attribute(("z-index", val.to_string().as_str()));
-            }
-
-            StyleAttr::Display(disp) => {
-                elem.push_attribute((
-                    "display",
-                    match disp {
-                        Display::None => "none",
-                        Display::Flex => "flex",
-                        Display::Grid => "grid",
-                    },
-                ));
-            }
-
-            StyleAttr::Position(pos) => {
-                elem.push_attribute((
-                    "position",
-                    match pos {
-                        PositionType::Absolute => "absolute",
-                        PositionType::Relative => "relative",
-                    },
-                ));
-            }
-
-            StyleAttr::Overflow(ov) => {
-                elem.push_attribute((
-                    "overflow",
-                    match ov {
-                        OverflowAxis::Clip => "clip",
-                        OverflowAxis::Visible => "visible",
-                    },
-                ));
-            }
-
-            StyleAttr::OverflowX(ov) => {
-                elem.push_attribute((
-                    "overflow-x",
-                    match ov {
-                        OverflowAxis::Clip => "clip",
-                        OverflowAxis::Visible => "visible",
-                    },
-                ));
-            }
-
-            StyleAttr::OverflowY(ov) => {
-                elem.push_attribute((
-                    "overflow-y",
-                    match ov {
-                        OverflowAxis::Clip => "clip",
-                        OverflowAxis::Visible => "visible",
-                    },
-                ));
-            }
-
-            StyleAttr::Direction(dir) => {
-                elem.push_attribute((
-                    "direction",
-                    match dir {
-                        bevy::ui::Direction::Inherit => "inherit",
-                        bevy::ui::Direction::LeftToRight => "ltr",
-                        bevy::ui::Direction::RightToLeft => "rtl",
-                    },
-                ));
-            }
+            StyleAttr::BackgroundImage(Some(path)) => format!("url({})", path),
+            StyleAttr::BackgroundImage(None) => "none".to_string(),
 
-            StyleAttr::Left(val) => {
-                elem.push_attribute(("left", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::Right(val) => {
-                elem.push_attribute(("right", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::Top(val) => {
-                elem.push_attribute(("top", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::Bottom(val) => {
-                elem.push_attribute(("bottom", StyleAttr::val_to_str(*val).as_str()));
-            }
+            StyleAttr::BorderColor(Some(col)) => StyleAttr::color_to_str(*col),
+            StyleAttr::BorderColor(None) => "transparent".to_string(),
 
-            StyleAttr::Width(val) => {
-                elem.push_attribute(("width", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::Height(val) => {
-                elem.push_attribute(("height", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MinWidth(val) => {
-                elem.push_attribute(("min-width", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MinHeight(val) => {
-                elem.push_attribute(("min-height", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MaxWidth(val) => {
-                elem.push_attribute(("max-width", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MaxHeight(val) => {
-                elem.push_attribute(("max-height", StyleAttr::val_to_str(*val).as_str()));
-            }
-
-            StyleAttr::AlignItems(align) => {
-                elem.push_attribute((
-                    "align-items",
-                    match align {
-                        AlignItems::Default => "default",
-                        AlignItems::Start => "start",
-                        AlignItems::End => "end",
-                        AlignItems::FlexStart => "flex-start",
-                        AlignItems::FlexEnd => "flex-end",
-                        AlignItems::Center => "center",
-                        AlignItems::Baseline => "baseline",
-                        AlignItems::Stretch => "stretch",
-                    },
-                ));
-            }
-
-            StyleAttr::JustifyItems(align) => {
-                elem.push_attribute((
-                    "justify-items",
-                    match align {
-                        JustifyItems::Default => "default",
-                        JustifyItems::Start => "start",
-                        JustifyItems::End => "end",
-                        JustifyItems::Center => "center",
-                        JustifyItems::Baseline => "baseline",
-                        JustifyItems::Stretch => "stretch",
-                    },
-                ));
-            }
-
-            StyleAttr::AlignSelf(align) => {
-                elem.push_attribute((
-                    "align-self",
-                    match align {
-                        AlignSelf::Auto => "auto",
-                        AlignSelf::Start => "start",
-                        AlignSelf::End => "end",
-                        AlignSelf::FlexStart => "flex-start",
-                        AlignSelf::FlexEnd => "flex-end",
-                        AlignSelf::Center => "center",
-                        AlignSelf::Baseline => "baseline",
-                        AlignSelf::Stretch => "stretch",
-                    },
-                ));
-            }
-
-            StyleAttr::JustifySelf(align) => {
-                elem.push_attribute((
-                    "justify-self",
-                    match align {
-                        JustifySelf::Auto => "auto",
-                        JustifySelf::Start => "start",
-                        JustifySelf::End => "end",
-                        JustifySelf::Center => "center",
-                        JustifySelf::Baseline => "baseline",
-                        JustifySelf::Stretch => "stretch",
-                    },
-                ));
-            }
-
-            StyleAttr::AlignContent(align) => {
-                elem.push_attribute((
-                    "align-content",
-                    match align {
-                        AlignContent::Default => "default",
-                        AlignContent::Start => "start",
-                        AlignContent::End => "end",
-                        AlignContent::FlexStart => "flex-start",
-                        AlignContent::FlexEnd => "flex-end",
-                        AlignContent::Center => "center",
-                        AlignContent::Stretch => "stretch",
-                        AlignContent::SpaceBetween => "space-between",
-                        AlignContent::SpaceAround => "space-around",
-                        AlignContent::SpaceEvenly => "space-evenly",
-                    },
-                ));
-            }
-
-            StyleAttr::JustifyContent(align) => {
-                elem.push_attribute((
-                    "justify-content",
-                    match align {
-                        JustifyContent::Default => "default",
-                        JustifyContent::Start => "start",
-                        JustifyContent::End => "end",
-                        JustifyContent::FlexStart => "flex-start",
-                        JustifyContent::FlexEnd => "flex-end",
-                        JustifyContent::Center => "center",
-                        JustifyContent::SpaceBetween => "space-between",
-                        JustifyContent::SpaceAround => "space-around",
-                        JustifyContent::SpaceEvenly => "space-evenly",
-                    },
-                ));
-            }
+            StyleAttr::Color(col) => StyleAttr::color_to_str(*col),
 
-            StyleAttr::Margin(val) => {
-                elem.push_attribute(("margin", StyleAttr::uirect_to_str(*val).as_str()));
-            }
-            StyleAttr::MarginLeft(val) => {
-                elem.push_attribute(("margin-left", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MarginRight(val) => {
-                elem.push_attribute(("margin-right", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MarginTop(val) => {
-                elem.push_attribute(("margin-top", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::MarginBottom(val) => {
-                elem.push_attribute(("margin-bottom", StyleAttr::val_to_str(*val).as_str()));
-            }
+            StyleAttr::ZIndex(Some(val)) => val.to_string(),
+            StyleAttr::ZIndex(None) => "auto".to_string(),
 
-            StyleAttr::Padding(val) => {
-                elem.push_attribute(("padding", StyleAttr::uirect_to_str(*val).as_str()));
-            }
-            StyleAttr::PaddingLeft(val) => {
-                elem.push_attribute(("padding-left", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::PaddingRight(val) => {
-                elem.push_attribute(("padding-right", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::PaddingTop(val) => {
-                elem.push_attribute(("padding-top", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::PaddingBottom(val) => {
-                elem.push_attribute(("padding-bottom", StyleAttr::val_to_str(*val).as_str()));
-            }
+            StyleAttr::Opacity(val) => val.to_string(),
 
-            StyleAttr::Border(val) => {
-                elem.push_attribute(("border", StyleAttr::uirect_to_str(*val).as_str()));
-            }
-            StyleAttr::BorderLeft(val) => {
-                elem.push_attribute(("border-left", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::BorderRight(val) => {
-                elem.push_attribute(("border-right", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::BorderTop(val) => {
-                elem.push_attribute(("border-top", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::BorderBottom(val) => {
-                elem.push_attribute(("border-bottom", StyleAttr::val_to_str(*val).as_str()));
-            }
+            StyleAttr::Content(Some(val)) => format!("\"{}\"", val),
+            StyleAttr::Content(None) => "none".to_string(),
 
-            StyleAttr::FlexDirection(dir) => {
-                elem.push_attribute((
-                    "flex-direction",
-                    match dir {
-                        bevy::ui::FlexDirection::Row => "row",
-                        bevy::ui::FlexDirection::Column => "column",
-                        bevy::ui::FlexDirection::RowReverse => "row-reverse",
-                        bevy::ui::FlexDirection::ColumnReverse => "column-reverse",
-                    },
-                ));
-            }
+            StyleAttr::Animation(Some(val)) => val.to_string(),
+            StyleAttr::Animation(None) => "none".to_string(),
 
-            StyleAttr::FlexWrap(dir) => {
-                elem.push_attribute((
-                    "flex-wrap",
-                    match dir {
-                        bevy::ui::FlexWrap::NoWrap => "nowrap",
-                        bevy::ui::FlexWrap::Wrap => "wrap",
-                        bevy::ui::FlexWrap::WrapReverse => "wrap-reverse",
-                    },
-                ));
+            StyleAttr::TextShadow(Some(shadow)) => {
+                format!(
+                    "{} {} {} {}",
+                    StyleAttr::val_to_str(shadow.offset_x),
+                    StyleAttr::val_to_str(shadow.offset_y),
+                    StyleAttr::val_to_str(shadow.blur),
+                    StyleAttr::color_to_str(shadow.color)
+                )
             }
+            StyleAttr::TextShadow(None) => "none".to_string(),
 
-            StyleAttr::FlexGrow(val) => {
-                elem.push_attribute(("flex-grow", f32::to_string(val).as_str()));
-            }
-            StyleAttr::FlexShrink(val) => {
-                elem.push_attribute(("flex-shrink", f32::to_string(val).as_str()));
-            }
-            StyleAttr::FlexBasis(val) => {
-                elem.push_attribute(("flex-basis", StyleAttr::val_to_str(*val).as_str()));
-            }
+            StyleAttr::DebugOutline(Some(color)) => StyleAttr::color_to_str(*color),
+            StyleAttr::DebugOutline(None) => "none".to_string(),
 
-            StyleAttr::RowGap(val) => {
-                elem.push_attribute(("row-gap", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::ColumnGap(val) => {
-                elem.push_attribute(("column-gap", StyleAttr::val_to_str(*val).as_str()));
-            }
-            StyleAttr::Gap(val) => {
-                elem.push_attribute(("gap", StyleAttr::val_to_str(*val).as_str()));
+            StyleAttr::Rotate(val) => format!("{}rad", val),
+            StyleAttr::Scale(x, y) if x == y => x.to_string(),
+            StyleAttr::Scale(x, y) => format!("{} {}", x, y),
+            StyleAttr::Translate(x, y) => {
+                format!(
+                    "{} {}",
+                    StyleAttr::val_to_str(*x),
+                    StyleAttr::val_to_str(*y)
+                )
             }
 
-            StyleAttr::GridAutoFlow(val) => {
-                elem.push_attribute((
-                    "grid-auto-flow",
-                    match val {
-                        GridAutoFlow::Row => "row",
-                        GridAutoFlow::Column => "column",
-                        GridAutoFlow::RowDense => "row-dense",
-                        GridAutoFlow::ColumnDense => "column-dense",
-                    },
-                ));
+            StyleAttr::WhiteSpace(ws) => match ws {
+                WhiteSpace::Normal => "normal",
+                WhiteSpace::NoWrap => "nowrap",
+                WhiteSpace::Pre => "pre",
+                WhiteSpace::PreWrap => "pre-wrap",
             }
+            .to_string(),
 
-            StyleAttr::GridRow(_) => {
-                panic!("Unsupported, can't write GridPlacement");
-            }
-            StyleAttr::GridRowStart(val) => {
-                elem.push_attribute(("grid-row-start", i16::to_string(val).as_str()));
-            }
-            StyleAttr::GridRowSpan(val) => {
-                elem.push_attribute(("grid-row-span", u16::to_string(val).as_str()));
-            }
-            StyleAttr::GridRowEnd(val) => {
-                elem.push_attribute(("grid-row-end", i16::to_string(val).as_str()));
+            StyleAttr::LineClamp(Some(lines)) => lines.to_string(),
+            StyleAttr::LineClamp(None) => "none".to_string(),
+
+            StyleAttr::Font(font) => font.name.clone(),
+
+            StyleAttr::FontSize(size) | StyleAttr::TextIndent(size) => match size {
+                FontSize::Px(px) => format!("{}px", px),
+                FontSize::Percent(pct) => format!("{}%", pct),
+                FontSize::Em(em) => format!("{}em", em),
+                FontSize::Rem(rem) => format!("{}rem", rem),
+            },
+
+            StyleAttr::TextAlign(align) => match align {
+                TextAlign::Start => "start",
+                TextAlign::End => "end",
+                TextAlign::Left => "left",
+                TextAlign::Center => "center",
+                TextAlign::Right => "right",
+            }
+            .to_string(),
+
+            StyleAttr::Display(disp) => match disp {
+                Display::None => "none",
+                Display::Flex => "flex",
+                Display::Grid => "grid",
+            }
+            .to_string(),
+
+            StyleAttr::Position(pos) => match pos {
+                PositionType::Absolute => "absolute",
+                PositionType::Relative => "relative",
+            }
+            .to_string(),
+
+            StyleAttr::Overflow(ov) => StyleAttr::overflow_to_str(*ov).to_string(),
+
+            StyleAttr::OverflowX(ov) => StyleAttr::overflow_to_str(*ov).to_string(),
+
+            StyleAttr::OverflowY(ov) => StyleAttr::overflow_to_str(*ov).to_string(),
+
+            StyleAttr::OverflowClipMargin(val) => StyleAttr::val_to_str(*val),
+
+            StyleAttr::Direction(dir) => match dir {
+                bevy::ui::Direction::Inherit => "inherit",
+                bevy::ui::Direction::LeftToRight => "ltr",
+                bevy::ui::Direction::RightToLeft => "rtl",
+            }
+            .to_string(),
+
+            StyleAttr::Left(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::Right(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::Top(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::Bottom(val) => StyleAttr::val_to_str(*val),
+
+            StyleAttr::Width(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::Height(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MaxContentWidth | StyleAttr::MaxContentHeight => "max-content".to_string(),
+            StyleAttr::MinWidth(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MinHeight(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MaxWidth(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MaxHeight(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::AspectRatio(Some(ratio)) => f32::to_string(ratio),
+            StyleAttr::AspectRatio(None) => "auto".to_string(),
+
+            StyleAttr::AlignItems(align) => match align {
+                AlignItems::Default => "default",
+                AlignItems::Start => "start",
+                AlignItems::End => "end",
+                AlignItems::FlexStart => "flex-start",
+                AlignItems::FlexEnd => "flex-end",
+                AlignItems::Center => "center",
+                AlignItems::Baseline => "baseline",
+                AlignItems::Stretch => "stretch",
+            }
+            .to_string(),
+
+            StyleAttr::JustifyItems(align) => match align {
+                JustifyItems::Default => "default",
+                JustifyItems::Start => "start",
+                JustifyItems::End => "end",
+                JustifyItems::Center => "center",
+                JustifyItems::Baseline => "baseline",
+                JustifyItems::Stretch => "stretch",
+            }
+            .to_string(),
+
+            StyleAttr::AlignSelf(align) => match align {
+                AlignSelf::Auto => "auto",
+                AlignSelf::Start => "start",
+                AlignSelf::End => "end",
+                AlignSelf::FlexStart => "flex-start",
+                AlignSelf::FlexEnd => "flex-end",
+                AlignSelf::Center => "center",
+                AlignSelf::Baseline => "baseline",
+                AlignSelf::Stretch => "stretch",
+            }
+            .to_string(),
+
+            StyleAttr::JustifySelf(align) => match align {
+                JustifySelf::Auto => "auto",
+                JustifySelf::Start => "start",
+                JustifySelf::End => "end",
+                JustifySelf::Center => "center",
+                JustifySelf::Baseline => "baseline",
+                JustifySelf::Stretch => "stretch",
+            }
+            .to_string(),
+
+            StyleAttr::AlignContent(align) => match align {
+                AlignContent::Default => "default",
+                AlignContent::Start => "start",
+                AlignContent::End => "end",
+                AlignContent::FlexStart => "flex-start",
+                AlignContent::FlexEnd => "flex-end",
+                AlignContent::Center => "center",
+                AlignContent::Stretch => "stretch",
+                AlignContent::SpaceBetween => "space-between",
+                AlignContent::SpaceAround => "space-around",
+                AlignContent::SpaceEvenly => "space-evenly",
+            }
+            .to_string(),
+
+            StyleAttr::JustifyContent(align) => match align {
+                JustifyContent::Default => "default",
+                JustifyContent::Start => "start",
+                JustifyContent::End => "end",
+                JustifyContent::FlexStart => "flex-start",
+                JustifyContent::FlexEnd => "flex-end",
+                JustifyContent::Center => "center",
+                JustifyContent::SpaceBetween => "space-between",
+                JustifyContent::SpaceAround => "space-around",
+                JustifyContent::SpaceEvenly => "space-evenly",
+            }
+            .to_string(),
+
+            StyleAttr::Margin(val) => StyleAttr::uirect_to_str(*val),
+            StyleAttr::MarginLeft(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MarginRight(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MarginTop(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::MarginBottom(val) => StyleAttr::val_to_str(*val),
+
+            StyleAttr::Padding(val) => StyleAttr::uirect_to_str(*val),
+            StyleAttr::PaddingLeft(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::PaddingRight(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::PaddingTop(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::PaddingBottom(val) => StyleAttr::val_to_str(*val),
+
+            StyleAttr::Border(val) => StyleAttr::uirect_to_str(*val),
+            StyleAttr::BorderLeft(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::BorderRight(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::BorderTop(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::BorderBottom(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::BorderRadius(val) => StyleAttr::corner_radii_to_str(*val),
+            StyleAttr::BorderStyle(BorderStyle::Solid) => "solid".to_string(),
+            StyleAttr::BorderStyle(BorderStyle::None) => "none".to_string(),
+
+            StyleAttr::BoxSizing(BoxSizing::BorderBox) => "border-box".to_string(),
+            StyleAttr::BoxSizing(BoxSizing::ContentBox) => "content-box".to_string(),
+
+            StyleAttr::FlexDirection(dir) => match dir {
+                bevy::ui::FlexDirection::Row => "row",
+                bevy::ui::FlexDirection::Column => "column",
+                bevy::ui::FlexDirection::RowReverse => "row-reverse",
+                bevy::ui::FlexDirection::ColumnReverse => "column-reverse",
+            }
+            .to_string(),
+
+            StyleAttr::FlexWrap(dir) => match dir {
+                bevy::ui::FlexWrap::NoWrap => "nowrap",
+                bevy::ui::FlexWrap::Wrap => "wrap",
+                bevy::ui::FlexWrap::WrapReverse => "wrap-reverse",
+            }
+            .to_string(),
+
+            StyleAttr::Flex(grow, shrink, basis) => {
+                format!("{} {} {}", grow, shrink, StyleAttr::val_to_str(*basis))
+            }
+            StyleAttr::FlexGrow(val) => f32::to_string(val),
+            StyleAttr::FlexShrink(val) => f32::to_string(val),
+            StyleAttr::FlexBasis(val) => StyleAttr::val_to_str(*val),
+
+            StyleAttr::RowGap(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::ColumnGap(val) => StyleAttr::val_to_str(*val),
+            StyleAttr::Gap(row, column) if row == column => StyleAttr::val_to_str(*row),
+            StyleAttr::Gap(row, column) => {
+                format!(
+                    "{} {}",
+                    StyleAttr::val_to_str(*row),
+                    StyleAttr::val_to_str(*column)
+                )
             }
 
-            StyleAttr::GridColumn(_) => {
-                panic!("Unsupported, can't write GridPlacement");
+            StyleAttr::GridAutoFlow(val) => match val {
+                GridAutoFlow::Row => "row",
+                GridAutoFlow::Column => "column",
+                GridAutoFlow::RowDense => "row-dense",
+                GridAutoFlow::ColumnDense => "column-dense",
             }
-            StyleAttr::GridColumnStart(val) => {
-                elem.push_attribute(("grid-column-start", i16::to_string(val).as_str()));
+            .to_string(),
+
+            StyleAttr::GridRow(placement) => {
+                StyleAttr::grid_placement_to_str(*placement).unwrap_or_else(|| "auto".to_string())
             }
-            StyleAttr::GridColumnSpan(val) => {
-                elem.push_attribute(("grid-column-span", u16::to_string(val).as_str()));
+            StyleAttr::GridRowStart(val) => i16::to_string(val),
+            StyleAttr::GridRowSpan(val) => u16::to_string(val),
+            StyleAttr::GridRowEnd(val) => i16::to_string(val),
+
+            StyleAttr::GridColumn(placement) => {
+                StyleAttr::grid_placement_to_str(*placement).unwrap_or_else(|| "auto".to_string())
             }
-            StyleAttr::GridColumnEnd(val) => {
-                elem.push_attribute(("grid-column-end", i16::to_string(val).as_str()));
-            } // _ => {
-              //     panic!("Unsupported tag")
-              // }
+            StyleAttr::GridColumnStart(val) => i16::to_string(val),
+            StyleAttr::GridColumnSpan(val) => u16::to_string(val),
+            StyleAttr::GridColumnEnd(val) => i16::to_string(val),
+            StyleAttr::Custom(attr) => attr.source.clone(),
+            StyleAttr::RawStyle(raw) => raw.source.clone(),
         }
     }
 
-    /// Convert a CSS-style color into a Color. Supports #hex, rgba() and hsla().
-    fn parse_color(str: &str) -> Result<Color, GuiseError> {
+    /// Convert a CSS-style color into a Color. Supports #hex, rgba(), hsla(), the basic color
+    /// keywords, and the `lighten`, `darken`, `saturate`, `desaturate` and `alpha` functions,
+    /// which take a color and a percentage. Surrounding whitespace is ignored, as is the case of
+    /// hex digits.
+    pub(crate) fn parse_color(str: &str) -> Result<Color, GuiseError> {
         lazy_static! {
             static ref RE_RGBA: Regex =
                 Regex::new(r"^rgba\(([\d\.]+),\s*([\d\.]+),\s*([\d\.]+),\s*([\d\.]+)\)$").unwrap();
             static ref RE_HSLA: Regex =
                 Regex::new(r"^hsla\(([\d\.]+),\s*([\d\.]+),\s*([\d\.]+),\s*([\d\.]+)\)$").unwrap();
+            // The amount comes last, so the color may itself contain commas.
+            static ref RE_ADJUST: Regex = Regex::new(
+                r"^(lighten|darken|saturate|desaturate|alpha)\((.+),\s*([\d\.]+)%\)$"
+            )
+            .unwrap();
+            static ref RE_LINEAR: Regex = Regex::new(
+                r"^color\(srgb-linear\s+([\d\.]+)\s+([\d\.]+)\s+([\d\.]+)(?:\s*/\s*([\d\.]+))?\)$"
+            )
+            .unwrap();
         }
 
+        let str = str.trim();
         let h = Color::hex(str);
         if h.is_ok() {
             return Ok(h.unwrap());
         }
 
+        if let Some(color) = StyleAttr::named_color(str) {
+            return Ok(color);
+        }
+
+        if let Some(cap) = RE_ADJUST.captures(str) {
+            let color = StyleAttr::parse_color(cap[2].trim())?;
+            let amount = f32::from_str(&cap[3])
+                .map_err(|_| GuiseError::InvalidAttributeValue(str.to_string()))?
+                / 100.;
+            return Ok(StyleAttr::adjust_color(color, &cap[1], amount));
+        }
+
         RE_RGBA
             .captures(str)
             .map(|cap| {
@@ -888,57 +1569,122 @@ impl StyleAttr {
                     f32::from_str(&cap[4]).unwrap(),
                 )
             }))
-            .ok_or(GuiseError::InvalidAttributeValue(str.to_string()))
-    }
+            .or(RE_LINEAR.captures(str).map(|cap| {
+                Color::rgba_linear(
+                    f32::from_str(&cap[1]).unwrap(),
+                    f32::from_str(&cap[2]).unwrap(),
+                    f32::from_str(&cap[3]).unwrap(),
+                    cap.get(4)
+                        .map_or(1., |alpha| f32::from_str(alpha.as_str()).unwrap()),
+                )
+            }))
+            .ok_or(GuiseError::InvalidAttributeValue(str.to_string()))
+    }
+
+    /// Look up one of the basic CSS color keywords. These have their CSS values, which differ
+    /// from Bevy's color constants of the same name in some cases, such as `green`.
+    fn named_color(name: &str) -> Option<Color> {
+        let (r, g, b) = match name {
+            "black" => (0, 0, 0),
+            "silver" => (192, 192, 192),
+            "gray" | "grey" => (128, 128, 128),
+            "white" => (255, 255, 255),
+            "maroon" => (128, 0, 0),
+            "red" => (255, 0, 0),
+            "purple" => (128, 0, 128),
+            "fuchsia" => (255, 0, 255),
+            "green" => (0, 128, 0),
+            "lime" => (0, 255, 0),
+            "olive" => (128, 128, 0),
+            "yellow" => (255, 255, 0),
+            "navy" => (0, 0, 128),
+            "blue" => (0, 0, 255),
+            "teal" => (0, 128, 128),
+            "aqua" => (0, 255, 255),
+            _ => return None,
+        };
+        Some(Color::rgb_u8(r, g, b))
+    }
+
+    /// Apply one of the color adjustment functions. `lighten`, `darken`, `saturate` and
+    /// `desaturate` add to or subtract from the lightness or saturation in HSL space, and
+    /// `alpha` replaces the alpha. `amount` is a fraction, and the results are clamped.
+    fn adjust_color(color: Color, function: &str, amount: f32) -> Color {
+        let [hue, saturation, lightness, alpha] = color.as_hsla_f32();
+        let (saturation, lightness, alpha) = match function {
+            "lighten" => (saturation, lightness + amount, alpha),
+            "darken" => (saturation, lightness - amount, alpha),
+            "saturate" => (saturation + amount, lightness, alpha),
+            "desaturate" => (saturation - amount, lightness, alpha),
+            "alpha" => (saturation, lightness, amount),
+            _ => unreachable!(),
+        };
+        Color::hsla(
+            hue,
+            saturation.clamp(0., 1.),
+            lightness.clamp(0., 1.),
+            alpha.clamp(0., 1.),
+        )
+        .as_rgba()
+    }
+
+    /// Convert a grid placement into the `start / end` or `start / span count` form parsed by
+    /// `parse_grid_placement`, or `None` if it has no start or no end or span. When both the
+    /// start and end are set, the span is ignored, as it is by layout.
+    fn grid_placement_to_str(placement: GridPlacement) -> Option<String> {
+        match (
+            placement.get_start(),
+            placement.get_end(),
+            placement.get_span(),
+        ) {
+            (Some(start), Some(end), _) => Some(format!("{} / {}", start, end)),
+            (Some(start), None, Some(span)) => Some(format!("{} / span {}", start, span)),
+            _ => None,
+        }
+    }
 
-    /// Convert a CSS-style color into a Color. Supports #hex, rgba() and hsla().
-    fn parse_grid_placement(str: &str) -> Result<GridPlacement, GuiseError> {
+    /// Parse a grid placement of the form `start / end` or `start / span count`. Negative lines
+    /// count from the end of the grid.
+    fn parse_grid_placement(name: &str, str: &str) -> Result<GridPlacement, GuiseError> {
         lazy_static! {
-            static ref RE_GRID_1: Regex = Regex::new(r"^([\d\.]+)\s*/\s*([\d\.]+)$").unwrap();
-            static ref RE_GRID_2: Regex =
-                Regex::new(r"^([\d\.]+)\s*/\s*span\s*([\d\.]+)$").unwrap();
+            static ref RE_GRID: Regex =
+                Regex::new(r"^(-?[\d\.]+)\s*/\s*(span\s*)?(-?[\d\.]+)$").unwrap();
         }
 
-        RE_GRID_1
+        let cap = RE_GRID
             .captures(str)
-            .map(|cap| {
-                GridPlacement::default()
-                    .set_start(i16::from_str(&cap[1]).unwrap())
-                    .set_end(i16::from_str(&cap[2]).unwrap())
-            })
-            .or(RE_GRID_2.captures(str).map(|cap| {
-                GridPlacement::default()
-                    .set_start(i16::from_str(&cap[1]).unwrap())
-                    .set_span(u16::from_str(&cap[2]).unwrap())
-            }))
-            .ok_or(GuiseError::InvalidAttributeValue(str.to_string()))
+            .ok_or(GuiseError::InvalidAttributeValue(str.to_string()))?;
+        let placement = GridPlacement::default().set_start(StyleAttr::parse_line(name, &cap[1])?);
+        Ok(match cap.get(2) {
+            Some(_) => placement.set_span(StyleAttr::parse_span(name, &cap[3])?),
+            None => placement.set_end(StyleAttr::parse_line(name, &cap[3])?),
+        })
     }
 
-    /// Convert a CSS-style length string into a `Val`.
+    /// Convert a CSS-style length string into a `Val`. The number may use scientific notation,
+    /// but must be finite. A unitless number is in pixels, so `0` is `Val::Px(0.)`, never `auto`.
     pub(crate) fn parse_val(str: &str) -> Result<Val, GuiseError> {
         if str == "auto" {
             return Ok(Val::Auto);
         }
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"^([\-\d\.]+)(px|vw|vh|vmin|vmax|%)?$").unwrap();
+            static ref RE: Regex = Regex::new(
+                r"^([\-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][\-+]?\d+)?)(px|vw|vh|vmin|vmax|%)?$"
+            )
+            .unwrap();
         }
         RE.captures(str)
             .and_then(|cap| {
-                let dist = f32::from_str(&cap[1]).unwrap();
-                if cap.get(2).is_none() {
+                let dist = f32::from_str(&cap[1]).ok().filter(|d| d.is_finite())?;
+                match cap.get(2).map_or("px", |unit| unit.as_str()) {
                     // Default to pixels if no unit
-                    return Some(Val::Px(dist));
-                }
-                match &cap[2] {
                     "px" => Some(Val::Px(dist)),
                     "%" => Some(Val::Percent(dist)),
                     "vw" => Some(Val::Vw(dist)),
                     "vh" => Some(Val::Vh(dist)),
                     "vmin" => Some(Val::VMin(dist)),
                     "vmax" => Some(Val::VMax(dist)),
-                    _ => {
-                        panic!("Invalid unit");
-                    }
+                    _ => None,
                 }
             })
             .ok_or(GuiseError::InvalidAttributeValue(str.to_string()))
@@ -948,60 +1694,228 @@ impl StyleAttr {
     /// These go in CSS order: (top, right, bottom, left).
     /// CSS shortcut forms are supported.
     pub(crate) fn parse_uirect(str: &str) -> Result<UiRect, GuiseError> {
-        let mut rect = UiRect::new(Val::Auto, Val::Auto, Val::Auto, Val::Auto);
-        let mut sides = str.split_whitespace();
-
-        // Top
-        if let Some(top) = sides.next() {
-            rect.top = StyleAttr::parse_val(top)?;
-
-            // Right defaults to top if not specified
-            rect.right = match sides.next() {
-                Some(val) => StyleAttr::parse_val(val)?,
-                None => rect.top,
-            };
-
-            // Bottom defaults to top if not specified
-            rect.bottom = match sides.next() {
-                Some(val) => StyleAttr::parse_val(val)?,
-                None => rect.top,
-            };
-
-            // Left defaults to right if not specified
-            rect.left = match sides.next() {
-                Some(val) => StyleAttr::parse_val(val)?,
-                None => rect.right,
-            };
-
-            // Should be no more values.
-            if sides.next().is_none() {
-                Ok(rect)
-            } else {
-                Err(GuiseError::InvalidAttributeValue(str.to_string()))
+        // Fast path for the common case of a single length applied to all sides.
+        if !str.contains(char::is_whitespace) {
+            return StyleAttr::parse_val(str).map(UiRect::all);
+        }
+        StyleAttr::parse_uirect_sides(str.split_whitespace(), str)
+    }
+
+    /// Parse between one and four already-split side lengths, in CSS order (top, right, bottom,
+    /// left). `full` is the original attribute value, used for error reporting.
+    fn parse_uirect_sides<'a>(
+        sides: impl Iterator<Item = &'a str>,
+        full: &str,
+    ) -> Result<UiRect, GuiseError> {
+        let mut vals = [Val::Auto; 4];
+        let mut count = 0;
+        for side in sides {
+            if count == vals.len() {
+                // Should be no more values.
+                return Err(GuiseError::InvalidAttributeValue(full.to_string()));
+            }
+            vals[count] = StyleAttr::parse_val(side)?;
+            count += 1;
+        }
+
+        let [top, right, bottom, left] = vals;
+        match count {
+            1 => Ok(UiRect::all(top)),
+            // Bottom defaults to top, left defaults to right.
+            2 => Ok(UiRect::new(right, right, top, top)),
+            3 => Ok(UiRect::new(right, right, top, bottom)),
+            4 => Ok(UiRect::new(left, right, top, bottom)),
+            _ => Err(GuiseError::InvalidAttributeValue(full.to_string())),
+        }
+    }
+
+    /// Convert a CSS-style `border-radius` string into a `CornerRadii`. Corners go in CSS order:
+    /// (top-left, top-right, bottom-right, bottom-left), and CSS shortcut forms are supported.
+    /// Elliptical radii (`h / v`) are accepted, but only the horizontal radii are kept.
+    pub(crate) fn parse_corner_radii(str: &str) -> Result<CornerRadii, GuiseError> {
+        let mut parts = str.split('/');
+        let horizontal = StyleAttr::parse_corners(parts.next().unwrap_or_default(), str)?;
+        if let Some(vertical) = parts.next() {
+            let vertical = StyleAttr::parse_corners(vertical, str)?;
+            if parts.next().is_some() {
+                return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+            }
+            if vertical != horizontal {
+                warn!(
+                    "Elliptical border-radius '{}' is not supported, using horizontal radii",
+                    str
+                );
             }
+        }
+        Ok(horizontal)
+    }
+
+    /// Parse an opacity, either as a number or a percentage. Values outside of the range 0 to 1
+    /// are clamped.
+    fn parse_opacity(str: &str) -> Result<f32, GuiseError> {
+        let opacity = match str.strip_suffix('%') {
+            Some(pct) => StyleAttr::parse_f32(pct)? / 100.,
+            None => StyleAttr::parse_f32(str)?,
+        };
+        if !(0. ..=1.).contains(&opacity) {
+            warn!("Opacity '{}' is out of range, clamping to [0, 1]", str);
+        }
+        Ok(opacity.clamp(0., 1.))
+    }
+
+    /// Parse a font size: a length in pixels, a percentage, or a multiple of the inherited
+    /// (`em`) or root (`rem`) font size. Negative sizes are an error.
+    fn parse_font_size(str: &str) -> Result<FontSize, GuiseError> {
+        let size = if let Some(rem) = str.strip_suffix("rem") {
+            FontSize::Rem(StyleAttr::parse_f32(rem)?)
+        } else if let Some(em) = str.strip_suffix("em") {
+            FontSize::Em(StyleAttr::parse_f32(em)?)
+        } else if let Some(pct) = str.strip_suffix('%') {
+            FontSize::Percent(StyleAttr::parse_f32(pct)?)
         } else {
-            Err(GuiseError::InvalidAttributeValue(str.to_string()))
+            FontSize::Px(StyleAttr::parse_f32(str.strip_suffix("px").unwrap_or(str))?)
+        };
+        match size {
+            FontSize::Px(v) | FontSize::Percent(v) | FontSize::Em(v) | FontSize::Rem(v)
+                if v < 0. =>
+            {
+                Err(GuiseError::InvalidAttributeValue(str.to_string()))
+            }
+            _ => Ok(size),
         }
     }
 
+    /// Parse an asset path written as `url(path)`, where the path may be quoted.
+    fn parse_url(str: &str) -> Result<String, GuiseError> {
+        let path = str
+            .trim()
+            .strip_prefix("url(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .map(|path| path.trim())
+            .ok_or_else(|| GuiseError::InvalidAttributeValue(str.to_string()))?;
+        let path = ['"', '\'']
+            .into_iter()
+            .find_map(|quote| path.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(path);
+        if path.is_empty() {
+            return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+        }
+        Ok(path.to_string())
+    }
+
+    /// Parse the value of a `content` attribute. `none` clears generated content; otherwise
+    /// the value is used as text, with one pair of surrounding quotes removed.
+    fn parse_content(str: &str) -> Option<String> {
+        if str == "none" {
+            return None;
+        }
+        for quote in ['"', '\''] {
+            if str.len() >= 2 && str.starts_with(quote) && str.ends_with(quote) {
+                return Some(str[1..str.len() - 1].to_string());
+            }
+        }
+        Some(str.to_string())
+    }
+
+    /// Parse between one and four corner radii.
+    fn parse_corners(str: &str, full: &str) -> Result<CornerRadii, GuiseError> {
+        let mut corners = str.split_whitespace();
+        let Some(top_left) = corners.next() else {
+            return Err(GuiseError::InvalidAttributeValue(full.to_string()));
+        };
+        let top_left = StyleAttr::parse_val(top_left)?;
+
+        // Top-right defaults to top-left if not specified
+        let top_right = match corners.next() {
+            Some(val) => StyleAttr::parse_val(val)?,
+            None => top_left,
+        };
+
+        // Bottom-right defaults to top-left if not specified
+        let bottom_right = match corners.next() {
+            Some(val) => StyleAttr::parse_val(val)?,
+            None => top_left,
+        };
+
+        // Bottom-left defaults to top-right if not specified
+        let bottom_left = match corners.next() {
+            Some(val) => StyleAttr::parse_val(val)?,
+            None => top_right,
+        };
+
+        // Should be no more values.
+        if corners.next().is_some() {
+            return Err(GuiseError::InvalidAttributeValue(full.to_string()));
+        }
+
+        Ok(CornerRadii {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        })
+    }
+
     /// Parse a scalar float.
     fn parse_f32(str: &str) -> Result<f32, GuiseError> {
         f32::from_str(str).or_else(|_| Err(GuiseError::InvalidAttributeValue(str.to_string())))
     }
 
-    /// Parse a scalar i32.
-    fn parse_i32(str: &str) -> Result<i32, GuiseError> {
-        i32::from_str(str).or_else(|_| Err(GuiseError::InvalidAttributeValue(str.to_string())))
+    /// Parse an aspect ratio, written either as a single number or as `width / height`.
+    fn parse_aspect_ratio(str: &str) -> Result<Option<f32>, GuiseError> {
+        if str == "auto" {
+            return Ok(None);
+        }
+        let ratio = match str.split_once('/') {
+            Some((width, height)) => {
+                StyleAttr::parse_f32(width.trim())? / StyleAttr::parse_f32(height.trim())?
+            }
+            None => StyleAttr::parse_f32(str)?,
+        };
+        if ratio.is_finite() && ratio > 0. {
+            Ok(Some(ratio))
+        } else {
+            Err(GuiseError::InvalidAttributeValue(str.to_string()))
+        }
     }
 
-    /// Parse a scalar i32.
-    fn parse_i16(str: &str) -> Result<i16, GuiseError> {
-        i16::from_str(str).or_else(|_| Err(GuiseError::InvalidAttributeValue(str.to_string())))
+    /// Parse an integer-valued attribute. Whole numbers written with a fraction, such as `2.0`,
+    /// are accepted.
+    fn parse_integer<T: FromStr + TryFrom<i64>>(name: &str, str: &str) -> Result<T, GuiseError> {
+        T::from_str(str)
+            .ok()
+            .or_else(|| {
+                f64::from_str(str)
+                    .ok()
+                    .filter(|num| num.fract() == 0.)
+                    .and_then(|num| T::try_from(num as i64).ok())
+            })
+            .ok_or_else(|| GuiseError::ExpectedInteger {
+                name: name.to_string(),
+                value: str.to_string(),
+            })
     }
 
-    /// Parse a scalar i32.
-    fn parse_u16(str: &str) -> Result<u16, GuiseError> {
-        u16::from_str(str).or_else(|_| Err(GuiseError::InvalidAttributeValue(str.to_string())))
+    /// Parse a grid line, which may be negative to count from the end, but can't be zero.
+    fn parse_line(name: &str, str: &str) -> Result<i16, GuiseError> {
+        match StyleAttr::parse_integer(name, str)? {
+            0 => Err(GuiseError::ExpectedInteger {
+                name: name.to_string(),
+                value: str.to_string(),
+            }),
+            line => Ok(line),
+        }
+    }
+
+    /// Parse a grid span, which must be a positive integer.
+    fn parse_span(name: &str, str: &str) -> Result<u16, GuiseError> {
+        match StyleAttr::parse_integer(name, str)? {
+            0 => Err(GuiseError::ExpectedInteger {
+                name: name.to_string(),
+                value: str.to_string(),
+            }),
+            span => Ok(span),
+        }
     }
 
     /// Convert a `Val` into a CSS-style string.
@@ -1029,6 +1943,183 @@ impl StyleAttr {
         )
     }
 
+    /// Convert a `CornerRadii` into a CSS-style string. The order of the values is (top-left,
+    /// top-right, bottom-right, bottom-left).
+    fn corner_radii_to_str(val: CornerRadii) -> String {
+        format!(
+            "{} {} {} {}",
+            StyleAttr::val_to_str(val.top_left),
+            StyleAttr::val_to_str(val.top_right),
+            StyleAttr::val_to_str(val.bottom_right),
+            StyleAttr::val_to_str(val.bottom_left)
+        )
+    }
+
+    /// Parse the `flex` shorthand, following CSS: a keyword (`none`, `auto` or `initial`), or a
+    /// grow factor optionally followed by a shrink factor and / or a basis. A number on its own
+    /// sets the basis to zero, so `flex="1"` means `1 1 0`.
+    fn parse_flex(str: &str) -> Result<StyleAttr, GuiseError> {
+        match str {
+            "none" => return Ok(StyleAttr::Flex(0., 0., Val::Auto)),
+            "auto" => return Ok(StyleAttr::Flex(1., 1., Val::Auto)),
+            "initial" => return Ok(StyleAttr::Flex(0., 1., Val::Auto)),
+            _ => {}
+        }
+
+        let parts: Vec<&str> = str.split_whitespace().collect();
+        let number = |s: &str| f32::from_str(s).ok();
+        match parts[..] {
+            [grow] => match number(grow) {
+                Some(grow) => Ok(StyleAttr::Flex(grow, 1., Val::Px(0.))),
+                None => Ok(StyleAttr::Flex(1., 1., StyleAttr::parse_flex_basis(grow)?)),
+            },
+            [grow, second] => {
+                let grow = StyleAttr::parse_f32(grow)?;
+                match number(second) {
+                    Some(shrink) => Ok(StyleAttr::Flex(grow, shrink, Val::Px(0.))),
+                    None => Ok(StyleAttr::Flex(
+                        grow,
+                        1.,
+                        StyleAttr::parse_flex_basis(second)?,
+                    )),
+                }
+            }
+            [grow, shrink, basis] => Ok(StyleAttr::Flex(
+                StyleAttr::parse_f32(grow)?,
+                StyleAttr::parse_f32(shrink)?,
+                StyleAttr::parse_flex_basis(basis)?,
+            )),
+            _ => Err(GuiseError::InvalidAttributeValue(str.to_string())),
+        }
+    }
+
+    /// Parse a flex basis. Bevy 0.11 has no `content` basis, so it is treated as `auto`, which
+    /// sizes the item from its content whenever its width or height along the main axis is
+    /// also `auto`.
+    fn parse_flex_basis(str: &str) -> Result<Val, GuiseError> {
+        match str {
+            "content" => Ok(Val::Auto),
+            _ => StyleAttr::parse_val(str),
+        }
+    }
+
+    /// Parse a `text-shadow` value: two or three lengths (x offset, y offset and optional blur)
+    /// and a color, which can come either before or after the lengths. The color defaults to
+    /// black.
+    fn parse_text_shadow(str: &str) -> Result<TextShadow, GuiseError> {
+        let tokens: Vec<&str> = str.split_whitespace().collect();
+        let is_length = |t: &&&str| StyleAttr::parse_val(t).is_ok();
+        let leading = tokens.iter().take_while(is_length).count();
+        let (lengths, color) = if leading > 0 {
+            (&tokens[..leading], &tokens[leading..])
+        } else {
+            let trailing = tokens.iter().rev().take_while(is_length).count();
+            let split = tokens.len() - trailing;
+            (&tokens[split..], &tokens[..split])
+        };
+
+        let lengths = lengths
+            .iter()
+            .map(|t| StyleAttr::parse_val(t))
+            .collect::<Result<Vec<Val>, _>>()?;
+        let (offset_x, offset_y, blur) = match lengths[..] {
+            [x, y] => (x, y, Val::Px(0.)),
+            [x, y, blur] => (x, y, blur),
+            _ => return Err(GuiseError::InvalidAttributeValue(str.to_string())),
+        };
+        let color = if color.is_empty() {
+            Color::BLACK
+        } else {
+            StyleAttr::parse_color(&color.join(" "))?
+        };
+
+        Ok(TextShadow {
+            offset_x,
+            offset_y,
+            blur,
+            color,
+        })
+    }
+
+    /// Parse a CSS angle, in `deg`, `rad` or `turn` units, into radians. A unitless zero is also
+    /// accepted.
+    fn parse_angle(str: &str) -> Result<f32, GuiseError> {
+        let (num, scale) = if let Some(num) = str.strip_suffix("deg") {
+            (num, std::f32::consts::PI / 180.)
+        } else if let Some(num) = str.strip_suffix("rad") {
+            (num, 1.)
+        } else if let Some(num) = str.strip_suffix("turn") {
+            (num, std::f32::consts::TAU)
+        } else if str == "0" {
+            (str, 0.)
+        } else {
+            return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+        };
+        Ok(StyleAttr::parse_f32(num)? * scale)
+    }
+
+    /// Parse the `scale` attribute: one factor for both axes, or separate horizontal and
+    /// vertical factors.
+    fn parse_scale(str: &str) -> Result<StyleAttr, GuiseError> {
+        let mut parts = str.split_whitespace();
+        let x = StyleAttr::parse_f32(parts.next().unwrap_or_default())?;
+        let y = match parts.next() {
+            Some(y) => StyleAttr::parse_f32(y)?,
+            None => x,
+        };
+        if parts.next().is_some() {
+            return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+        }
+        Ok(StyleAttr::Scale(x, y))
+    }
+
+    /// Parse the `translate` attribute: a horizontal offset, optionally followed by a vertical
+    /// offset which defaults to zero.
+    fn parse_translate(str: &str) -> Result<StyleAttr, GuiseError> {
+        let mut parts = str.split_whitespace();
+        let x = StyleAttr::parse_val(parts.next().unwrap_or_default())?;
+        let y = match parts.next() {
+            Some(y) => StyleAttr::parse_val(y)?,
+            None => Val::Px(0.),
+        };
+        if parts.next().is_some() {
+            return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+        }
+        Ok(StyleAttr::Translate(x, y))
+    }
+
+    /// Parse the `gap` shorthand: either a single length for both gaps, or a row gap followed
+    /// by a column gap.
+    fn parse_gap(str: &str) -> Result<StyleAttr, GuiseError> {
+        let mut parts = str.split_whitespace();
+        let row = StyleAttr::parse_val(parts.next().unwrap_or_default())?;
+        let column = match parts.next() {
+            Some(column) => StyleAttr::parse_val(column)?,
+            None => row,
+        };
+        if parts.next().is_some() {
+            return Err(GuiseError::InvalidAttributeValue(str.to_string()));
+        }
+        Ok(StyleAttr::Gap(row, column))
+    }
+
+    /// Parse an overflow keyword. Bevy 0.11 only supports `visible` and `clip`; `hidden` is
+    /// accepted as an alias for `clip`, since without scrolling the two look the same.
+    fn parse_overflow(str: &str) -> Result<OverflowAxis, GuiseError> {
+        match str {
+            "visible" => Ok(OverflowAxis::Visible),
+            "clip" | "hidden" => Ok(OverflowAxis::Clip),
+            _ => Err(GuiseError::UnknownAttributeValue(str.to_string())),
+        }
+    }
+
+    fn overflow_to_str(ov: OverflowAxis) -> &'static str {
+        match ov {
+            OverflowAxis::Clip => "clip",
+            OverflowAxis::Visible => "visible",
+        }
+    }
+
     fn color_to_str(col: Color) -> String {
         match col {
             Color::Rgba {
@@ -1045,6 +2136,13 @@ impl StyleAttr {
                 alpha,
             } => format!("hsla({}, {}, {}, {})", hue, saturation, lightness, alpha),
 
+            Color::RgbaLinear {
+                red,
+                green,
+                blue,
+                alpha,
+            } => format!("color(srgb-linear {} {} {} / {})", red, green, blue, alpha),
+
             _ => {
                 panic!("Unsupported color format")
             }
@@ -1070,6 +2168,23 @@ mod tests {
         assert!(StyleAttr::parse_val("1.1.1bad").is_err());
     }
 
+    #[test]
+    fn test_parse_val_scientific() {
+        assert_eq!(StyleAttr::parse_val("1e3px").unwrap(), Val::Px(1000.));
+        assert_eq!(StyleAttr::parse_val("1.5E2%").unwrap(), Val::Percent(150.));
+        assert_eq!(StyleAttr::parse_val("-.5vw").unwrap(), Val::Vw(-0.5));
+        assert_eq!(StyleAttr::parse_val("3e38").unwrap(), Val::Px(3e38));
+
+        // Too large to fit in an `f32`.
+        assert!(StyleAttr::parse_val("1e39px").is_err());
+        assert!(StyleAttr::parse_val("-1e400").is_err());
+        assert!(StyleAttr::parse_val("1.1.1px").is_err());
+        assert!(StyleAttr::parse_val("--1px").is_err());
+        assert!(StyleAttr::parse_val("1e").is_err());
+        assert!(StyleAttr::parse_val("NaN").is_err());
+        assert!(StyleAttr::parse_val("inf").is_err());
+    }
+
     #[test]
     fn test_parse_uirect() {
         assert_eq!(
@@ -1090,6 +2205,893 @@ mod tests {
         );
 
         assert!(StyleAttr::parse_uirect("1.1bad").is_err());
+        assert!(StyleAttr::parse_uirect("1px 2px 3px 4px 5px").is_err());
+        assert!(StyleAttr::parse_uirect(" ").is_err());
+    }
+
+    #[test]
+    fn test_parse_corner_radii() {
+        assert_eq!(
+            StyleAttr::parse_corner_radii("1px 2px 3px 4px").unwrap(),
+            CornerRadii {
+                top_left: Val::Px(1.),
+                top_right: Val::Px(2.),
+                bottom_right: Val::Px(3.),
+                bottom_left: Val::Px(4.),
+            }
+        );
+        assert_eq!(
+            StyleAttr::parse_corner_radii("10px 20px").unwrap(),
+            CornerRadii {
+                top_left: Val::Px(10.),
+                top_right: Val::Px(20.),
+                bottom_right: Val::Px(10.),
+                bottom_left: Val::Px(20.),
+            }
+        );
+        assert_eq!(
+            StyleAttr::parse_corner_radii("5%").unwrap(),
+            CornerRadii::all(Val::Percent(5.))
+        );
+
+        // Elliptical radii keep the horizontal part.
+        assert_eq!(
+            StyleAttr::parse_corner_radii("10px / 20px").unwrap(),
+            CornerRadii::all(Val::Px(10.))
+        );
+
+        assert!(StyleAttr::parse_corner_radii("").is_err());
+        assert!(StyleAttr::parse_corner_radii("1px 2px 3px 4px 5px").is_err());
+        assert!(StyleAttr::parse_corner_radii("1px / 2px / 3px").is_err());
+        assert!(StyleAttr::parse_corner_radii("1px / bad").is_err());
+    }
+
+    #[test]
+    fn test_auto_lengths() {
+        let mut computed = ComputedStyle::default();
+        computed.style.left = Val::Px(1.);
+        computed.style.width = Val::Px(1.);
+        computed.style.margin.left = Val::Px(1.);
+        computed.style.flex_basis = Val::Px(1.);
+        let names: [&[u8]; 4] = [b"left", b"width", b"margin-left", b"flex-basis"];
+        for name in names {
+            StyleAttr::parse(name, "auto")
+                .unwrap()
+                .unwrap()
+                .apply(&mut computed);
+        }
+        assert_eq!(computed.style.left, Val::Auto);
+        assert_eq!(computed.style.width, Val::Auto);
+        assert_eq!(computed.style.margin.left, Val::Auto);
+        assert_eq!(computed.style.flex_basis, Val::Auto);
+
+        assert_eq!(
+            StyleAttr::parse_uirect("auto 1px").unwrap(),
+            UiRect::new(Val::Px(1.), Val::Px(1.), Val::Auto, Val::Auto)
+        );
+    }
+
+    #[test]
+    fn test_parse_z_index() {
+        assert_eq!(
+            StyleAttr::parse(b"z-index", "auto").unwrap().unwrap(),
+            StyleAttr::ZIndex(None)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"z-index", "-1").unwrap().unwrap(),
+            StyleAttr::ZIndex(Some(-1))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"z-index", "5").unwrap().unwrap(),
+            StyleAttr::ZIndex(Some(5))
+        );
+        assert!(StyleAttr::parse(b"z-index", "bad").is_err());
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::ZIndex(Some(5)).apply(&mut computed);
+        assert_eq!(computed.z_index, Some(5));
+        StyleAttr::ZIndex(None).apply(&mut computed);
+        assert_eq!(computed.z_index, None);
+
+        let mut elem = BytesStart::new("style");
+        StyleAttr::ZIndex(Some(-1)).write_xml(&mut elem);
+        let attr = elem.attributes().next().unwrap().unwrap();
+        let value = attr.unescape_value().unwrap();
+        assert_eq!(
+            StyleAttr::parse(b"z-index", &value).unwrap().unwrap(),
+            StyleAttr::ZIndex(Some(-1))
+        );
+    }
+
+    #[test]
+    fn test_parse_content() {
+        assert_eq!(
+            StyleAttr::parse(b"content", "\"★\"").unwrap().unwrap(),
+            StyleAttr::Content(Some("★".to_string()))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"content", "'none'").unwrap().unwrap(),
+            StyleAttr::Content(Some("none".to_string()))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"content", "★").unwrap().unwrap(),
+            StyleAttr::Content(Some("★".to_string()))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"content", "none").unwrap().unwrap(),
+            StyleAttr::Content(None)
+        );
+    }
+
+    #[test]
+    fn test_parse_linear_color() {
+        assert_eq!(
+            StyleAttr::parse_color("color(srgb-linear 1 0 0)").unwrap(),
+            Color::rgba_linear(1., 0., 0., 1.)
+        );
+        assert_eq!(
+            StyleAttr::parse_color("color(srgb-linear 0.2 0.4 0.6 / 0.5)").unwrap(),
+            Color::rgba_linear(0.2, 0.4, 0.6, 0.5)
+        );
+        assert!(StyleAttr::parse_color("color(display-p3 1 0 0)").is_err());
+
+        let color = Color::rgba_linear(0.25, 0.5, 1., 0.75);
+        assert_eq!(
+            StyleAttr::parse_color(&StyleAttr::color_to_str(color)).unwrap(),
+            color
+        );
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        let color = Color::rgb_u8(0xff, 0xaa, 0xbb);
+        assert_eq!(StyleAttr::parse_color("#ffaabb").unwrap(), color);
+        assert_eq!(StyleAttr::parse_color(" #ffaabb ").unwrap(), color);
+        assert_eq!(StyleAttr::parse_color("#FFAABB").unwrap(), color);
+        assert_eq!(StyleAttr::parse_color("\t#FFaaBB\n").unwrap(), color);
+        assert_eq!(
+            StyleAttr::parse_color("alpha( #FFAABB , 50%)").unwrap().a(),
+            0.5
+        );
+        assert!(StyleAttr::parse_color("# ffaabb").is_err());
+    }
+
+    #[test]
+    fn test_parse_color_functions() {
+        assert_eq!(StyleAttr::parse_color("red").unwrap(), Color::RED);
+        assert!(StyleAttr::parse_color("reddish").is_err());
+
+        let darkened = StyleAttr::parse_color("darken(#808080, 50%)").unwrap();
+        assert!(darkened.r() < 0.01 && darkened.g() < 0.01 && darkened.b() < 0.01);
+        assert_eq!(darkened.a(), 1.);
+
+        let faded = StyleAttr::parse_color("alpha(red, 50%)").unwrap();
+        assert_eq!(faded.a(), 0.5);
+        assert!((faded.r() - 1.).abs() < 1e-4 && faded.g() < 1e-4);
+
+        // Results are clamped, and functions can be nested.
+        assert_eq!(
+            StyleAttr::parse_color("lighten(white, 20%)").unwrap(),
+            Color::WHITE.as_rgba()
+        );
+        let gray = StyleAttr::parse_color("desaturate(rgba(1, 0, 0, 1), 100%)").unwrap();
+        assert!((gray.r() - gray.g()).abs() < 1e-4);
+        assert!(StyleAttr::parse_color("alpha(saturate(blue, 10%), 25%)").is_ok());
+        assert!(StyleAttr::parse_color("darken(#808080, 50)").is_err());
+        assert!(StyleAttr::parse_color("darken(nope, 50%)").is_err());
+    }
+
+    #[test]
+    fn test_parse_flex_basis() {
+        let cases = [
+            ("content", Val::Auto),
+            ("auto", Val::Auto),
+            ("0", Val::Px(0.)),
+            ("0%", Val::Percent(0.)),
+            ("25%", Val::Percent(25.)),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                StyleAttr::parse(b"flex-basis", value).unwrap().unwrap(),
+                StyleAttr::FlexBasis(expected),
+                "flex-basis=\"{}\"",
+                value
+            );
+        }
+        assert!(StyleAttr::parse(b"flex-basis", "fit-content").is_err());
+    }
+
+    #[test]
+    fn test_parse_flex() {
+        assert_eq!(
+            StyleAttr::parse(b"flex", "none").unwrap().unwrap(),
+            StyleAttr::Flex(0., 0., Val::Auto)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "auto").unwrap().unwrap(),
+            StyleAttr::Flex(1., 1., Val::Auto)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "initial").unwrap().unwrap(),
+            StyleAttr::Flex(0., 1., Val::Auto)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "1").unwrap().unwrap(),
+            StyleAttr::Flex(1., 1., Val::Px(0.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "0").unwrap().unwrap(),
+            StyleAttr::Flex(0., 1., Val::Px(0.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "2 3").unwrap().unwrap(),
+            StyleAttr::Flex(2., 3., Val::Px(0.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "2 30%").unwrap().unwrap(),
+            StyleAttr::Flex(2., 1., Val::Percent(30.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "2 0 10px").unwrap().unwrap(),
+            StyleAttr::Flex(2., 0., Val::Px(10.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "10px").unwrap().unwrap(),
+            StyleAttr::Flex(1., 1., Val::Px(10.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"flex", "1 content").unwrap().unwrap(),
+            StyleAttr::Flex(1., 1., Val::Auto)
+        );
+        assert!(StyleAttr::parse(b"flex", "1 2 3 4").is_err());
+        assert!(StyleAttr::parse(b"flex", "").is_err());
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Flex(1., 1., Val::Px(0.)).apply(&mut computed);
+        assert_eq!(computed.style.flex_grow, 1.);
+        assert_eq!(computed.style.flex_shrink, 1.);
+        assert_eq!(computed.style.flex_basis, Val::Px(0.));
+    }
+
+    #[test]
+    fn test_parse_gap() {
+        assert_eq!(
+            StyleAttr::parse(b"gap", "5%").unwrap().unwrap(),
+            StyleAttr::Gap(Val::Percent(5.), Val::Percent(5.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"gap", "10px 5%").unwrap().unwrap(),
+            StyleAttr::Gap(Val::Px(10.), Val::Percent(5.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"row-gap", "10px").unwrap().unwrap(),
+            StyleAttr::RowGap(Val::Px(10.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"column-gap", "5%").unwrap().unwrap(),
+            StyleAttr::ColumnGap(Val::Percent(5.))
+        );
+        assert!(StyleAttr::parse(b"gap", "1px 2px 3px").is_err());
+        assert!(StyleAttr::parse(b"gap", "").is_err());
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Gap(Val::Px(10.), Val::Percent(5.)).apply(&mut computed);
+        assert_eq!(computed.style.row_gap, Val::Px(10.));
+        assert_eq!(computed.style.column_gap, Val::Percent(5.));
+
+        for gap in ["5%", "10px 5%"] {
+            let attr = StyleAttr::parse(b"gap", gap).unwrap().unwrap();
+            let mut elem = BytesStart::new("style");
+            attr.write_xml(&mut elem);
+            let value = elem.attributes().next().unwrap().unwrap();
+            assert_eq!(
+                StyleAttr::parse(b"gap", &value.unescape_value().unwrap())
+                    .unwrap()
+                    .unwrap(),
+                attr
+            );
+        }
+    }
+
+    #[test]
+    fn test_attr_names() {
+        let attrs = [
+            StyleAttr::BackgroundColor(None),
+            StyleAttr::ZIndex(Some(1)),
+            StyleAttr::MarginLeft(Val::Px(1.)),
+            StyleAttr::Flex(1., 1., Val::Auto),
+            StyleAttr::Gap(Val::Px(1.), Val::Px(2.)),
+            StyleAttr::GridColumnSpan(2),
+        ];
+        for attr in attrs {
+            // The name is the one the attribute is written and parsed with.
+            let mut elem = BytesStart::new("style");
+            attr.write_xml(&mut elem);
+            let xml_attr = elem.attributes().next().unwrap().unwrap();
+            assert_eq!(xml_attr.key.as_ref(), attr.name().as_bytes());
+            assert_eq!(
+                StyleAttr::parse(attr.name().as_bytes(), &xml_attr.unescape_value().unwrap())
+                    .unwrap()
+                    .unwrap(),
+                attr
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_css_string() {
+        let attrs = [
+            StyleAttr::BackgroundColor(Some(Color::rgba(1., 0.5, 0., 1.))),
+            StyleAttr::BorderColor(Some(Color::hsla(120., 0.5, 0.5, 1.))),
+            StyleAttr::BackgroundColor(Some(Color::rgba_linear(0.25, 0.5, 1., 0.5))),
+            StyleAttr::BorderColor(None),
+            StyleAttr::BackgroundImage(Some("editor/ui/panel.png".to_string())),
+            StyleAttr::BackgroundImage(None),
+            StyleAttr::Color(Color::rgba(0.5, 0.5, 0.5, 1.)),
+            StyleAttr::ZIndex(None),
+            StyleAttr::Content(Some("★".to_string())),
+            StyleAttr::Content(None),
+            StyleAttr::TextShadow(Some(TextShadow {
+                offset_x: Val::Px(1.),
+                offset_y: Val::Px(2.),
+                blur: Val::Px(3.),
+                color: Color::rgba(0., 0., 0., 0.5),
+            })),
+            StyleAttr::WhiteSpace(WhiteSpace::PreWrap),
+            StyleAttr::TextAlign(TextAlign::End),
+            StyleAttr::BoxSizing(BoxSizing::ContentBox),
+            StyleAttr::Font(FontRef {
+                name: "heading".to_string(),
+                handle: None,
+            }),
+            StyleAttr::FontSize(FontSize::Rem(1.5)),
+            StyleAttr::FontSize(FontSize::Px(14.)),
+            StyleAttr::TextIndent(FontSize::Em(2.)),
+            StyleAttr::Display(Display::Grid),
+            StyleAttr::Overflow(OverflowAxis::Clip),
+            StyleAttr::Width(Val::Percent(50.)),
+            StyleAttr::MaxContentWidth,
+            StyleAttr::MaxContentHeight,
+            StyleAttr::MaxHeight(Val::Vh(10.)),
+            StyleAttr::MinWidth(Val::Auto),
+            StyleAttr::AspectRatio(Some(1.5)),
+            StyleAttr::AspectRatio(None),
+            StyleAttr::AlignContent(AlignContent::SpaceEvenly),
+            StyleAttr::Margin(UiRect::new(
+                Val::Px(1.),
+                Val::Px(2.),
+                Val::Px(3.),
+                Val::Px(4.),
+            )),
+            StyleAttr::BorderRadius(CornerRadii::all(Val::Px(4.))),
+            StyleAttr::Flex(2., 1., Val::Px(10.)),
+            StyleAttr::FlexGrow(1.5),
+            StyleAttr::Gap(Val::Px(1.), Val::Px(1.)),
+            StyleAttr::Gap(Val::Px(1.), Val::Percent(2.)),
+            StyleAttr::GridRowStart(-1),
+            StyleAttr::GridRow(GridPlacement::start_span(1, 2)),
+            StyleAttr::GridColumn(GridPlacement::default().set_start(2).set_end(4)),
+            StyleAttr::GridRow(GridPlacement::start_span(-2, 2)),
+            StyleAttr::GridColumn(GridPlacement::default().set_start(1).set_end(-1)),
+        ];
+        for attr in attrs {
+            let value = attr.to_css_string();
+            assert_eq!(
+                StyleAttr::parse(attr.name().as_bytes(), &value)
+                    .unwrap()
+                    .unwrap(),
+                attr,
+                "{} = {}",
+                attr.name(),
+                value
+            );
+        }
+
+        assert_eq!(StyleAttr::Width(Val::Px(10.)).to_css_string(), "10px");
+        assert_eq!(
+            StyleAttr::Gap(Val::Px(1.), Val::Px(1.)).to_css_string(),
+            "1px"
+        );
+    }
+
+    #[test]
+    fn test_write_grid_placement() {
+        let mut elem = BytesStart::new("style");
+        StyleAttr::GridRow(GridPlacement::start_span(1, 2)).write_xml(&mut elem);
+        // A span on its own can't be written, so it's skipped rather than panicking.
+        StyleAttr::GridColumn(GridPlacement::span(3)).write_xml(&mut elem);
+        let attrs: Vec<(Vec<u8>, Vec<u8>)> = elem
+            .attributes()
+            .map(|attr| {
+                let attr = attr.unwrap();
+                (attr.key.as_ref().to_vec(), attr.value.to_vec())
+            })
+            .collect();
+        assert_eq!(attrs, vec![(b"grid-row".to_vec(), b"1 / span 2".to_vec())]);
+    }
+
+    #[test]
+    fn test_parse_white_space() {
+        let keywords = [
+            ("normal", WhiteSpace::Normal),
+            ("nowrap", WhiteSpace::NoWrap),
+            ("pre", WhiteSpace::Pre),
+            ("pre-wrap", WhiteSpace::PreWrap),
+        ];
+        for (keyword, expected) in keywords {
+            let attr = StyleAttr::parse(b"white-space", keyword).unwrap().unwrap();
+            assert_eq!(attr, StyleAttr::WhiteSpace(expected));
+            assert_eq!(expected.line_break(), BreakLineOn::WordBoundary);
+        }
+        assert!(StyleAttr::parse(b"white-space", "break-spaces").is_err());
+
+        let text = "\n  fn main() {\n      run();\n  }";
+        assert_eq!(WhiteSpace::Normal.process(text), "fn main() { run(); }");
+        assert_eq!(WhiteSpace::NoWrap.process(text), "fn main() { run(); }");
+        assert_eq!(
+            WhiteSpace::Pre.process(text),
+            "  fn main() {\n      run();\n  }"
+        );
+        assert_eq!(WhiteSpace::PreWrap.process("a  b"), "a  b");
+
+        assert_eq!(
+            WhiteSpace::Normal.process("first line\\n  second line"),
+            "first line\nsecond line"
+        );
+        assert_eq!(WhiteSpace::Pre.process("a\\\\nb\\x"), "a\\nb\\x");
+    }
+
+    #[test]
+    fn test_parse_aspect_ratio() {
+        assert_eq!(
+            StyleAttr::parse(b"aspect-ratio", "16 / 9")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::AspectRatio(Some(16. / 9.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"aspect-ratio", "2").unwrap().unwrap(),
+            StyleAttr::AspectRatio(Some(2.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"aspect-ratio", "auto").unwrap().unwrap(),
+            StyleAttr::AspectRatio(None)
+        );
+        assert!(StyleAttr::parse(b"aspect-ratio", "16 / 0").is_err());
+        assert!(StyleAttr::parse(b"aspect-ratio", "-1").is_err());
+        assert!(StyleAttr::parse(b"aspect-ratio", "wide").is_err());
+    }
+
+    #[test]
+    fn test_parse_line_clamp() {
+        assert_eq!(
+            StyleAttr::parse(b"line-clamp", "3").unwrap().unwrap(),
+            StyleAttr::LineClamp(Some(3))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"line-clamp", "none").unwrap().unwrap(),
+            StyleAttr::LineClamp(None)
+        );
+        assert!(StyleAttr::parse(b"line-clamp", "0").is_err());
+        assert!(StyleAttr::parse(b"line-clamp", "-1").is_err());
+        assert!(StyleAttr::parse(b"line-clamp", "auto").is_err());
+    }
+
+    #[test]
+    fn test_parse_font_size() {
+        let parse = |value| match StyleAttr::parse(b"font-size", value).unwrap().unwrap() {
+            StyleAttr::FontSize(size) => size,
+            attr => panic!("expected a font size, got {:?}", attr),
+        };
+        assert_eq!(parse("14"), FontSize::Px(14.));
+        assert_eq!(parse("14px"), FontSize::Px(14.));
+        assert_eq!(parse("150%"), FontSize::Percent(150.));
+        assert_eq!(parse("1.5em"), FontSize::Em(1.5));
+        assert_eq!(parse("1.5rem"), FontSize::Rem(1.5));
+        assert!(StyleAttr::parse(b"font-size", "-2px").is_err());
+        assert!(StyleAttr::parse(b"font-size", "large").is_err());
+
+        assert_eq!(FontSize::Percent(150.).resolve(16., 20.), 24.);
+        assert_eq!(FontSize::Rem(1.5).resolve(16., 20.), 30.);
+        assert_eq!(FontSize::Em(2.).resolve(16., 20.), 32.);
+    }
+
+    #[test]
+    fn test_parse_direction() {
+        use bevy::ui::Direction;
+
+        for (value, direction) in [
+            ("inherit", Direction::Inherit),
+            ("ltr", Direction::LeftToRight),
+            ("rtl", Direction::RightToLeft),
+        ] {
+            let attr = StyleAttr::parse(b"direction", value).unwrap().unwrap();
+            assert_eq!(attr, StyleAttr::Direction(direction));
+            assert_eq!(attr.to_css_string(), value);
+
+            let mut computed = ComputedStyle::default();
+            attr.apply(&mut computed);
+            assert_eq!(computed.style.direction, direction);
+        }
+
+        // Only the CSS keywords are accepted, in lower case.
+        for value in ["LTR", "left-to-right", "auto", ""] {
+            assert!(StyleAttr::parse(b"direction", value).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_self_alignment() {
+        for (value, align, justify) in [
+            ("auto", AlignSelf::Auto, JustifySelf::Auto),
+            ("start", AlignSelf::Start, JustifySelf::Start),
+            ("end", AlignSelf::End, JustifySelf::End),
+            ("center", AlignSelf::Center, JustifySelf::Center),
+            ("stretch", AlignSelf::Stretch, JustifySelf::Stretch),
+            ("baseline", AlignSelf::Baseline, JustifySelf::Baseline),
+        ] {
+            let align_attr = StyleAttr::parse(b"align-self", value).unwrap().unwrap();
+            assert_eq!(align_attr, StyleAttr::AlignSelf(align));
+            assert_eq!(align_attr.to_css_string(), value);
+            let justify_attr = StyleAttr::parse(b"justify-self", value).unwrap().unwrap();
+            assert_eq!(justify_attr, StyleAttr::JustifySelf(justify));
+            assert_eq!(justify_attr.to_css_string(), value);
+
+            // `auto` resets an earlier value, so a child can opt out of a shared style.
+            let mut computed = ComputedStyle::default();
+            StyleAttr::AlignSelf(AlignSelf::Center).apply(&mut computed);
+            StyleAttr::JustifySelf(JustifySelf::Center).apply(&mut computed);
+            align_attr.apply(&mut computed);
+            justify_attr.apply(&mut computed);
+            assert_eq!(computed.style.align_self, align);
+            assert_eq!(computed.style.justify_self, justify);
+        }
+
+        // The flex-only keywords apply to `align-self` alone.
+        assert!(StyleAttr::parse(b"align-self", "flex-start").is_ok());
+        assert!(StyleAttr::parse(b"justify-self", "flex-start").is_err());
+        for value in ["Auto", "normal", "left", ""] {
+            assert!(StyleAttr::parse(b"align-self", value).is_err());
+            assert!(StyleAttr::parse(b"justify-self", value).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_text_indent() {
+        let indent = |value| {
+            let mut computed = ComputedStyle::default();
+            StyleAttr::parse(b"text-indent", value)
+                .unwrap()
+                .unwrap()
+                .apply(&mut computed);
+            computed.text_indent
+        };
+        assert_eq!(indent("2em"), Some(FontSize::Em(2.)));
+        assert_eq!(indent("20px"), Some(FontSize::Px(20.)));
+        assert_eq!(indent("1rem"), Some(FontSize::Rem(1.)));
+        assert!(StyleAttr::parse(b"text-indent", "10%").is_err());
+        assert!(StyleAttr::parse(b"text-indent", "-1em").is_err());
+    }
+
+    #[test]
+    fn test_parse_text_align() {
+        assert_eq!(
+            StyleAttr::parse(b"text-align", "start").unwrap().unwrap(),
+            StyleAttr::TextAlign(TextAlign::Start)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"text-align", "center").unwrap().unwrap(),
+            StyleAttr::TextAlign(TextAlign::Center)
+        );
+        assert!(StyleAttr::parse(b"text-align", "justify").is_err());
+
+        let ltr = bevy::ui::Direction::LeftToRight;
+        let rtl = bevy::ui::Direction::RightToLeft;
+        assert_eq!(TextAlign::Start.resolve(ltr), TextAlignment::Left);
+        assert_eq!(TextAlign::Start.resolve(rtl), TextAlignment::Right);
+        assert_eq!(TextAlign::End.resolve(ltr), TextAlignment::Right);
+        assert_eq!(TextAlign::End.resolve(rtl), TextAlignment::Left);
+        assert_eq!(TextAlign::Left.resolve(rtl), TextAlignment::Left);
+        assert_eq!(
+            TextAlign::Start.resolve(bevy::ui::Direction::Inherit),
+            TextAlignment::Left
+        );
+    }
+
+    #[test]
+    fn test_parse_text_shadow() {
+        let shadow = TextShadow {
+            offset_x: Val::Px(2.),
+            offset_y: Val::Px(2.),
+            blur: Val::Px(0.),
+            color: Color::hex("#000").unwrap(),
+        };
+        assert_eq!(
+            StyleAttr::parse(b"text-shadow", "2px 2px #000")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::TextShadow(Some(shadow))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"text-shadow", "#000 2px 2px")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::TextShadow(Some(shadow))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"text-shadow", "1px 2px 3px rgba(0, 0, 0, 0.5)")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::TextShadow(Some(TextShadow {
+                offset_x: Val::Px(1.),
+                offset_y: Val::Px(2.),
+                blur: Val::Px(3.),
+                color: Color::rgba(0., 0., 0., 0.5),
+            }))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"text-shadow", "none").unwrap().unwrap(),
+            StyleAttr::TextShadow(None)
+        );
+        assert!(StyleAttr::parse(b"text-shadow", "2px #000").is_err());
+        assert!(StyleAttr::parse(b"text-shadow", "2px 2px #zzz").is_err());
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::parse(b"text-shadow", "2px 2px #000")
+            .unwrap()
+            .unwrap()
+            .apply(&mut computed);
+        assert_eq!(computed.text_shadow, Some(shadow));
+    }
+
+    #[test]
+    fn test_parse_transforms() {
+        match StyleAttr::parse(b"rotate", "45deg").unwrap().unwrap() {
+            StyleAttr::Rotate(angle) => {
+                assert!((angle - std::f32::consts::FRAC_PI_4).abs() < 1e-6)
+            }
+            attr => panic!("expected rotate, got {:?}", attr),
+        }
+        assert_eq!(
+            StyleAttr::parse(b"rotate", "0.5turn").unwrap().unwrap(),
+            StyleAttr::Rotate(std::f32::consts::PI)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"rotate", "0").unwrap().unwrap(),
+            StyleAttr::Rotate(0.)
+        );
+        assert!(StyleAttr::parse(b"rotate", "45").is_err());
+
+        assert_eq!(
+            StyleAttr::parse(b"scale", "1.2").unwrap().unwrap(),
+            StyleAttr::Scale(1.2, 1.2)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"scale", "1 -1").unwrap().unwrap(),
+            StyleAttr::Scale(1., -1.)
+        );
+        assert!(StyleAttr::parse(b"scale", "1 2 3").is_err());
+
+        assert_eq!(
+            StyleAttr::parse(b"translate", "10px 5px").unwrap().unwrap(),
+            StyleAttr::Translate(Val::Px(10.), Val::Px(5.))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"translate", "50%").unwrap().unwrap(),
+            StyleAttr::Translate(Val::Percent(50.), Val::Px(0.))
+        );
+        assert!(StyleAttr::parse(b"translate", "10px 5px 0").is_err());
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Rotate(1.).apply(&mut computed);
+        StyleAttr::Scale(2., 2.).apply(&mut computed);
+        assert_eq!(computed.transform.rotate, 1.);
+        assert_eq!(computed.transform.scale, Vec2::splat(2.));
+        assert_eq!(computed.transform.translate, (Val::Px(0.), Val::Px(0.)));
+    }
+
+    #[test]
+    fn test_parse_opacity() {
+        assert_eq!(
+            StyleAttr::parse(b"opacity", "0.5").unwrap().unwrap(),
+            StyleAttr::Opacity(0.5)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"opacity", "25%").unwrap().unwrap(),
+            StyleAttr::Opacity(0.25)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"opacity", "1.5").unwrap().unwrap(),
+            StyleAttr::Opacity(1.)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"opacity", "-1").unwrap().unwrap(),
+            StyleAttr::Opacity(0.)
+        );
+        assert!(StyleAttr::parse(b"opacity", "half").is_err());
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::BackgroundColor(Some(Color::rgba(1., 0., 0., 0.5))).apply(&mut computed);
+        StyleAttr::Opacity(0.5).apply(&mut computed);
+        assert_eq!(
+            computed.rendered_background_color(),
+            Some(Color::rgba(1., 0., 0., 0.25))
+        );
+        assert_eq!(computed.rendered_border_color(), None);
+    }
+
+    #[test]
+    fn test_parse_border_style() {
+        assert_eq!(
+            StyleAttr::parse(b"border-style", "none").unwrap().unwrap(),
+            StyleAttr::BorderStyle(BorderStyle::None)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"border-style", "dashed")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::BorderStyle(BorderStyle::Solid)
+        );
+        assert!(StyleAttr::parse(b"border-style", "wavy").is_err());
+
+        // `none` wins regardless of where the border widths are set.
+        let mut computed = ComputedStyle::default();
+        StyleAttr::BorderStyle(BorderStyle::None).apply(&mut computed);
+        StyleAttr::Border(UiRect::all(Val::Px(2.))).apply(&mut computed);
+        StyleAttr::BorderLeft(Val::Px(4.)).apply(&mut computed);
+        computed.resolve();
+        assert_eq!(computed.style.border, UiRect::all(Val::Px(0.)));
+
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Border(UiRect::all(Val::Px(2.))).apply(&mut computed);
+        StyleAttr::BorderStyle(BorderStyle::Solid).apply(&mut computed);
+        computed.resolve();
+        assert_eq!(computed.style.border, UiRect::all(Val::Px(2.)));
+    }
+
+    #[test]
+    fn test_parse_box_sizing() {
+        assert_eq!(
+            StyleAttr::parse(b"box-sizing", "content-box")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::BoxSizing(BoxSizing::ContentBox)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"box-sizing", "border-box")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::BoxSizing(BoxSizing::BorderBox)
+        );
+        assert!(StyleAttr::parse(b"box-sizing", "padding-box").is_err());
+    }
+
+    #[test]
+    fn test_parse_integers() {
+        assert_eq!(
+            StyleAttr::parse(b"grid-row-span", "2").unwrap().unwrap(),
+            StyleAttr::GridRowSpan(2)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"grid-row-span", "2.0").unwrap().unwrap(),
+            StyleAttr::GridRowSpan(2)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"grid-column-start", "-1")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::GridColumnStart(-1)
+        );
+        assert_eq!(
+            StyleAttr::parse(b"z-index", "3").unwrap().unwrap(),
+            StyleAttr::ZIndex(Some(3))
+        );
+
+        let err = StyleAttr::parse(b"grid-row-span", "2.5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'grid-row-span' expects an integer, got '2.5'"
+        );
+        assert!(StyleAttr::parse(b"grid-row-span", "0").is_err());
+        assert!(StyleAttr::parse(b"grid-row-span", "-2").is_err());
+        assert!(StyleAttr::parse(b"grid-row-start", "40000").is_err());
+        assert!(StyleAttr::parse(b"grid-row-start", "0").is_err());
+
+        assert_eq!(
+            StyleAttr::parse(b"grid-row", "2 / span 3")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::GridRow(GridPlacement::start_span(2, 3))
+        );
+        assert_eq!(
+            StyleAttr::parse(b"grid-column", "1 / 4").unwrap().unwrap(),
+            StyleAttr::GridColumn(GridPlacement::start(1).set_end(4))
+        );
+
+        // Placements which look valid but aren't are errors, rather than panics.
+        let err = StyleAttr::parse(b"grid-row", "1.5 / 2").unwrap_err();
+        assert_eq!(err.to_string(), "'grid-row' expects an integer, got '1.5'");
+        assert!(StyleAttr::parse(b"grid-row", "1 / span 2.5").is_err());
+        assert!(StyleAttr::parse(b"grid-row", "1 / span 0").is_err());
+        assert!(StyleAttr::parse(b"grid-row", "0 / 2").is_err());
+        assert!(StyleAttr::parse(b"grid-column", "1 / 0").is_err());
+        assert!(StyleAttr::parse(b"grid-column", "1 / span -2").is_err());
+    }
+
+    #[test]
+    fn test_parse_overflow() {
+        let keywords = [
+            ("visible", OverflowAxis::Visible),
+            ("clip", OverflowAxis::Clip),
+            ("hidden", OverflowAxis::Clip),
+        ];
+        for (keyword, expected) in keywords {
+            assert_eq!(
+                StyleAttr::parse(b"overflow", keyword).unwrap().unwrap(),
+                StyleAttr::Overflow(expected)
+            );
+            assert_eq!(
+                StyleAttr::parse(b"overflow-x", keyword).unwrap().unwrap(),
+                StyleAttr::OverflowX(expected)
+            );
+            assert_eq!(
+                StyleAttr::parse(b"overflow-y", keyword).unwrap().unwrap(),
+                StyleAttr::OverflowY(expected)
+            );
+            assert_eq!(
+                StyleAttr::overflow_to_str(expected),
+                keyword.replace("hidden", "clip")
+            );
+        }
+
+        // Bevy 0.11 has no scrolling.
+        assert!(StyleAttr::parse(b"overflow", "scroll").is_err());
+        assert!(StyleAttr::parse(b"overflow-x", "auto").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_names_attribute() {
+        let err = StyleAttr::parse(b"width", "wide").unwrap_err();
+        match &err {
+            GuiseError::InvalidAttribute {
+                name,
+                location,
+                error,
+            } => {
+                assert_eq!(name, "width");
+                assert_eq!(*location, None);
+                assert!(matches!(**error, GuiseError::InvalidAttributeValue(_)));
+            }
+            other => panic!("expected an invalid attribute, got {:?}", other),
+        }
+        assert!(err.to_string().contains("width"));
+    }
+
+    #[test]
+    fn test_parse_overflow_clip_margin() {
+        let attr = StyleAttr::parse(b"overflow-clip-margin", "4px")
+            .unwrap()
+            .unwrap();
+        assert_eq!(attr, StyleAttr::OverflowClipMargin(Val::Px(4.)));
+        assert_eq!(attr.to_css_string(), "4px");
+        assert_eq!(
+            StyleAttr::parse(b"overflow-clip-margin", "1vw")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::OverflowClipMargin(Val::Vw(1.))
+        );
+
+        // Stored, though Bevy 0.11 can't widen the clip region.
+        let mut computed = ComputedStyle::default();
+        attr.apply(&mut computed);
+        assert_eq!(computed.overflow_clip_margin, Some(Val::Px(4.)));
+        assert!(attr.style_fields().is_empty());
+
+        // As in CSS, the margin is a non-negative length, not a percentage.
+        assert!(StyleAttr::parse(b"overflow-clip-margin", "-2px").is_err());
+        assert!(StyleAttr::parse(b"overflow-clip-margin", "10%").is_err());
+        assert!(StyleAttr::parse(b"overflow-clip-margin", "auto").is_err());
+        assert!(StyleAttr::parse(b"overflow-clip-margin", "wide").is_err());
     }
 
     #[test]
@@ -1122,7 +3124,7 @@ mod tests {
 
         assert_eq!(
             StyleAttr::parse(b"z-index", "33").unwrap().unwrap(),
-            StyleAttr::ZIndex(33)
+            StyleAttr::ZIndex(Some(33))
         );
 
         assert_eq!(
@@ -1137,6 +3139,8 @@ mod tests {
             StyleAttr::parse(b"display", "grid").unwrap().unwrap(),
             StyleAttr::Display(bevy::ui::Display::Grid)
         );
+        assert!(StyleAttr::parse(b"display", "block").is_err());
+        assert!(StyleAttr::parse(b"display", "Grid").is_err());
 
         assert_eq!(
             StyleAttr::parse(b"position", "absolute").unwrap().unwrap(),
@@ -1146,6 +3150,20 @@ mod tests {
             StyleAttr::parse(b"position", "relative").unwrap().unwrap(),
             StyleAttr::Position(bevy::ui::PositionType::Relative)
         );
+        for value in ["static", "fixed", "sticky"] {
+            match StyleAttr::parse(b"position", value) {
+                Err(err @ GuiseError::UnsupportedAttributeValue { .. }) => {
+                    assert!(err
+                        .to_string()
+                        .starts_with(&format!("'position: {}'", value)))
+                }
+                other => panic!("expected an unsupported value, got {:?}", other),
+            }
+        }
+        assert!(matches!(
+            StyleAttr::parse_value(b"position", "floating"),
+            Err(GuiseError::UnknownAttributeValue(_))
+        ));
 
         assert_eq!(
             StyleAttr::parse(b"overflow", "clip").unwrap().unwrap(),
@@ -1,5 +1,13 @@
 use bevy::ecs::system::Command;
 use bevy::prelude::*;
+use bevy::text::BreakLineOn;
+
+use crate::guise::view::ViewText;
+
+use super::{
+    AnimationSpec, BorderStyle, BoxSizing, CornerRadii, CustomValues, FontRef, FontSize,
+    MaxContent, PartialStyle, StyleAnimation, StyleTransform, TextAlign, TextShadow, WhiteSpace,
+};
 
 /// A computed style represents the composition of one or more `PartialStyle`s.
 #[derive(Default, Clone, PartialEq, Debug)]
@@ -7,7 +15,592 @@ pub struct ComputedStyle {
     pub style: Style,
     pub border_color: Option<Color>,
     pub background_color: Option<Color>,
+
+    /// Asset path of an image drawn over the background color. Bevy 0.11 uses a node's
+    /// background color to tint its image, so the image is drawn by a child node instead.
+    pub background_image: Option<String>,
     pub z_index: Option<i32>,
+
+    /// Opacity of the node's own background and border, from 0 to 1. Unlike CSS, this does
+    /// not fade the node's children, since Bevy 0.11 has no way to composite a subtree.
+    pub opacity: Option<f32>,
+
+    /// Corner radii. Bevy 0.11 can't draw rounded corners, so this is not yet applied to the
+    /// node.
+    pub border_radius: Option<CornerRadii>,
+
+    /// How far outside the node its content may paint before being clipped. Bevy 0.11 clips at
+    /// the node's edge, so this is not yet applied.
+    pub overflow_clip_margin: Option<Val>,
+
+    /// Border style. Applied to the border widths by `resolve`.
+    pub border_style: BorderStyle,
+
+    /// What the width and height measure. Applied to the sizes by `resolve`.
+    pub box_sizing: BoxSizing,
+
+    /// Text generated by the style, displayed as the node's first child.
+    pub content: Option<String>,
+
+    /// Keyframe animation played on top of this style.
+    pub animation: Option<AnimationSpec>,
+
+    /// Whitespace handling for the node's text children.
+    pub white_space: WhiteSpace,
+
+    /// Maximum number of lines of the node's text children. Bevy 0.11 can't tell where text
+    /// will wrap until after layout, so only line breaks in the text itself are counted: the
+    /// text is cut after the last allowed line, and ends with an ellipsis.
+    pub line_clamp: Option<u16>,
+
+    /// Indent of the first line of the node's text children. Bevy 0.11 can't offset the first
+    /// line of a text block, so the indent is approximated with leading spaces.
+    pub text_indent: Option<FontSize>,
+
+    /// Alignment of the node's text children.
+    pub text_align: TextAlign,
+
+    /// Font of the node's text children. Unlike the font size, this isn't inherited, so nodes
+    /// without a font use Bevy's default font.
+    pub font: Option<FontRef>,
+
+    /// Size of the node's text. If not set, the size is inherited.
+    pub font_size: Option<FontSize>,
+
+    /// Color of the node's text. If not set, the color is inherited.
+    pub color: Option<Color>,
+
+    /// Shadow behind the node's text, drawn by a copy of each text child. See
+    /// `TextShadowLayer`.
+    pub text_shadow: Option<TextShadow>,
+
+    /// Color of an outline drawn over the edges of the node, as a development aid. Only drawn
+    /// in debug builds; release builds ignore it.
+    pub debug_outline: Option<Color>,
+
+    /// Rotation, scale and translation applied to the node after layout.
+    pub transform: StyleTransform,
+
+    /// Axes along which the node is sized to its text after layout, from a `width` or `height`
+    /// of `max-content`.
+    pub max_content: BVec2,
+
+    /// Values of custom properties, set by their `apply` functions.
+    pub custom: CustomValues,
+
+    /// The fields of `style` which were set by style attributes. Only these are written to the
+    /// node, so that fields set by other systems aren't clobbered.
+    pub style_fields: StyleFields,
+}
+
+impl ComputedStyle {
+    /// True if no property differs from its default value.
+    pub fn is_default(&self) -> bool {
+        *self == ComputedStyle::default()
+    }
+
+    /// Apply properties which depend on other properties, regardless of the order in which
+    /// they were set. Called before the style is written to the node.
+    pub fn resolve(&mut self) {
+        // Sizes measured from the content are written by `update_max_content`, so they mustn't
+        // be overwritten by the style.
+        if self.max_content.x {
+            self.style_fields.remove(StyleField::Width);
+        }
+        if self.max_content.y {
+            self.style_fields.remove(StyleField::Height);
+        }
+
+        if self.border_style == BorderStyle::None {
+            self.style.border = UiRect::all(Val::Px(0.));
+        }
+
+        if self.box_sizing == BoxSizing::ContentBox {
+            let style = &mut self.style;
+            let horizontal = [
+                style.padding.left,
+                style.padding.right,
+                style.border.left,
+                style.border.right,
+            ];
+            let vertical = [
+                style.padding.top,
+                style.padding.bottom,
+                style.border.top,
+                style.border.bottom,
+            ];
+            for size in [&mut style.width, &mut style.min_width, &mut style.max_width] {
+                *size = add_px(*size, &horizontal);
+            }
+            for size in [
+                &mut style.height,
+                &mut style.min_height,
+                &mut style.max_height,
+            ] {
+                *size = add_px(*size, &vertical);
+            }
+        }
+    }
+
+    /// Compose a computed style from a list of styles, applied in order, followed by an
+    /// optional inline style. Styles which aren't loaded are skipped. This is the same
+    /// composition that controllers do, without the element.
+    pub fn from_stylesets(
+        handles: &[Handle<PartialStyle>],
+        assets: &Assets<PartialStyle>,
+        inline: Option<&PartialStyle>,
+    ) -> ComputedStyle {
+        let mut computed = ComputedStyle::default();
+        for style in handles.iter().filter_map(|handle| assets.get(handle)) {
+            style.apply_to(&mut computed);
+        }
+        if let Some(inline) = inline {
+            inline.apply_to(&mut computed);
+        }
+        computed
+    }
+
+    /// The Bevy `Style` which is written to the node.
+    pub fn bevy_style(&self) -> &Style {
+        &self.style
+    }
+
+    /// The color of the node's own text, if set. If not, the color is inherited.
+    pub fn color(&self) -> Option<Color> {
+        self.color
+    }
+
+    /// The node's z-index, if set.
+    pub fn z_index(&self) -> Option<i32> {
+        self.z_index
+    }
+
+    /// How the node's text children are broken into lines.
+    pub fn line_break(&self) -> BreakLineOn {
+        self.white_space.line_break()
+    }
+
+    /// The alignment of the node's text, with `start` and `end` resolved using the node's
+    /// direction. This doesn't depend on the order in which `text-align` and `direction` are
+    /// set.
+    pub fn text_alignment(&self) -> TextAlignment {
+        self.text_align.resolve(self.style.direction)
+    }
+
+    /// The background color with `opacity` applied.
+    pub fn rendered_background_color(&self) -> Option<Color> {
+        self.background_color.map(|color| self.fade(color))
+    }
+
+    /// The border color with `opacity` applied.
+    pub fn rendered_border_color(&self) -> Option<Color> {
+        self.border_color.map(|color| self.fade(color))
+    }
+
+    fn fade(&self, color: Color) -> Color {
+        match self.opacity {
+            Some(opacity) => color.with_a(color.a() * opacity),
+            None => color,
+        }
+    }
+}
+
+/// Add the pixel lengths in `extra` to `size`. If the size or any of the lengths isn't in
+/// pixels, the size is returned unchanged.
+fn add_px(size: Val, extra: &[Val]) -> Val {
+    let Val::Px(mut px) = size else {
+        return size;
+    };
+    for val in extra {
+        match val {
+            Val::Px(v) => px += v,
+            _ => return size,
+        }
+    }
+    Val::Px(px)
+}
+
+/// A field of Bevy's `Style` which can be set by a style attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleField {
+    Display,
+    PositionType,
+    Overflow,
+    Direction,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Width,
+    Height,
+    MinWidth,
+    MinHeight,
+    MaxWidth,
+    MaxHeight,
+    AspectRatio,
+    AlignItems,
+    JustifyItems,
+    AlignSelf,
+    JustifySelf,
+    AlignContent,
+    JustifyContent,
+    Margin,
+    Padding,
+    Border,
+    FlexDirection,
+    FlexWrap,
+    FlexGrow,
+    FlexShrink,
+    FlexBasis,
+    RowGap,
+    ColumnGap,
+    GridAutoFlow,
+    GridTemplateRows,
+    GridTemplateColumns,
+    GridAutoRows,
+    GridAutoColumns,
+    GridRow,
+    GridColumn,
+}
+
+impl StyleField {
+    /// The field with the given name in Bevy's `Style`, e.g. `min_width`.
+    pub fn from_name(name: &str) -> Option<StyleField> {
+        Some(match name {
+            "display" => StyleField::Display,
+            "position_type" => StyleField::PositionType,
+            "overflow" => StyleField::Overflow,
+            "direction" => StyleField::Direction,
+            "left" => StyleField::Left,
+            "right" => StyleField::Right,
+            "top" => StyleField::Top,
+            "bottom" => StyleField::Bottom,
+            "width" => StyleField::Width,
+            "height" => StyleField::Height,
+            "min_width" => StyleField::MinWidth,
+            "min_height" => StyleField::MinHeight,
+            "max_width" => StyleField::MaxWidth,
+            "max_height" => StyleField::MaxHeight,
+            "aspect_ratio" => StyleField::AspectRatio,
+            "align_items" => StyleField::AlignItems,
+            "justify_items" => StyleField::JustifyItems,
+            "align_self" => StyleField::AlignSelf,
+            "justify_self" => StyleField::JustifySelf,
+            "align_content" => StyleField::AlignContent,
+            "justify_content" => StyleField::JustifyContent,
+            "margin" => StyleField::Margin,
+            "padding" => StyleField::Padding,
+            "border" => StyleField::Border,
+            "flex_direction" => StyleField::FlexDirection,
+            "flex_wrap" => StyleField::FlexWrap,
+            "flex_grow" => StyleField::FlexGrow,
+            "flex_shrink" => StyleField::FlexShrink,
+            "flex_basis" => StyleField::FlexBasis,
+            "row_gap" => StyleField::RowGap,
+            "column_gap" => StyleField::ColumnGap,
+            "grid_auto_flow" => StyleField::GridAutoFlow,
+            "grid_template_rows" => StyleField::GridTemplateRows,
+            "grid_template_columns" => StyleField::GridTemplateColumns,
+            "grid_auto_rows" => StyleField::GridAutoRows,
+            "grid_auto_columns" => StyleField::GridAutoColumns,
+            "grid_row" => StyleField::GridRow,
+            "grid_column" => StyleField::GridColumn,
+            _ => return None,
+        })
+    }
+}
+
+/// A set of `StyleField`s. As a component, records the fields which were last written to the
+/// node's `Style` by guise.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StyleFields(u64);
+
+impl StyleFields {
+    pub fn insert(&mut self, field: StyleField) {
+        self.0 |= 1 << field as u64;
+    }
+
+    pub fn remove(&mut self, field: StyleField) {
+        self.0 &= !(1 << field as u64);
+    }
+
+    pub fn contains(&self, field: StyleField) -> bool {
+        self.0 & (1 << field as u64) != 0
+    }
+
+    pub fn union(&self, other: StyleFields) -> StyleFields {
+        StyleFields(self.0 | other.0)
+    }
+
+    /// The fields which are in this set, but not in `other`.
+    pub fn difference(&self, other: StyleFields) -> StyleFields {
+        StyleFields(self.0 & !other.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Expand `$m!`, appending the `StyleField` variants and the `Style` fields they name to its
+/// arguments.
+macro_rules! with_style_fields {
+    ($m:ident!($($args:tt)*)) => {
+        $m!($($args)*
+            Display => display,
+            PositionType => position_type,
+            Overflow => overflow,
+            Direction => direction,
+            Left => left,
+            Right => right,
+            Top => top,
+            Bottom => bottom,
+            Width => width,
+            Height => height,
+            MinWidth => min_width,
+            MinHeight => min_height,
+            MaxWidth => max_width,
+            MaxHeight => max_height,
+            AspectRatio => aspect_ratio,
+            AlignItems => align_items,
+            JustifyItems => justify_items,
+            AlignSelf => align_self,
+            JustifySelf => justify_self,
+            AlignContent => align_content,
+            JustifyContent => justify_content,
+            Margin => margin,
+            Padding => padding,
+            Border => border,
+            FlexDirection => flex_direction,
+            FlexWrap => flex_wrap,
+            FlexGrow => flex_grow,
+            FlexShrink => flex_shrink,
+            FlexBasis => flex_basis,
+            RowGap => row_gap,
+            ColumnGap => column_gap,
+            GridAutoFlow => grid_auto_flow,
+            GridTemplateRows => grid_template_rows,
+            GridTemplateColumns => grid_template_columns,
+            GridAutoRows => grid_auto_rows,
+            GridAutoColumns => grid_auto_columns,
+            GridRow => grid_row,
+            GridColumn => grid_column,
+        )
+    };
+}
+
+macro_rules! copy_fields {
+    ($src:expr, $dst:expr, $fields:expr, $($variant:ident => $field:ident),* $(,)?) => {
+        $(
+            if $fields.contains(StyleField::$variant) && $dst.$field != $src.$field {
+                $dst.$field = $src.$field.clone();
+            }
+        )*
+    };
+}
+
+macro_rules! diff_fields {
+    ($a:expr, $b:expr, $($variant:ident => $field:ident),* $(,)?) => {{
+        let mut changed = StyleFields::default();
+        $(
+            if $a.$field != $b.$field {
+                changed.insert(StyleField::$variant);
+            }
+        )*
+        changed
+    }};
+}
+
+/// Copy the given fields of `src` into `dst`, leaving the other fields alone.
+pub(crate) fn copy_style_fields(src: &Style, dst: &mut Style, fields: StyleFields) {
+    with_style_fields!(copy_fields!(src, dst, fields,));
+}
+
+/// The fields which differ between `a` and `b`.
+pub(crate) fn changed_style_fields(a: &Style, b: &Style) -> StyleFields {
+    with_style_fields!(diff_fields!(a, b,))
+}
+
+/// The font size which `rem` font sizes are relative to, and which nodes without a font size
+/// inherit at the root. Defaults to Bevy's default text size, so that text without a font size
+/// is unchanged.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct RootFontSize(pub f32);
+
+impl Default for RootFontSize {
+    fn default() -> Self {
+        RootFontSize(TextStyle::default().font_size)
+    }
+}
+
+/// The font size of a node in pixels, after resolving relative sizes. Descendants with relative
+/// font sizes are resolved against this.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ComputedFontSize(pub f32);
+
+/// The color of a node's text, after inheritance. Descendants without a color of their own
+/// use this.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ComputedTextColor(pub Color);
+
+/// The nearest ancestor of `entity` which has a component of type `T`. Ancestors whose style
+/// hasn't been computed yet are skipped.
+fn inherited<T: Component + Copy>(world: &World, entity: Entity) -> Option<T> {
+    let mut current = entity;
+    while let Some(parent) = world.get::<Parent>(current) {
+        current = parent.get();
+        if let Some(value) = world.get::<T>(current) {
+            return Some(*value);
+        }
+    }
+    None
+}
+
+/// Text properties which a node passes down to descendants that don't set their own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct InheritedText {
+    font_size: f32,
+    color: Color,
+}
+
+impl InheritedText {
+    /// The text properties of a node with the given computed style, whose parent's are `self`.
+    fn child(&self, computed: &ComputedStyle, root_font_size: f32) -> InheritedText {
+        InheritedText {
+            font_size: computed.font_size.map_or(self.font_size, |size| {
+                size.resolve(self.font_size, root_font_size)
+            }),
+            color: computed.color.unwrap_or(self.color),
+        }
+    }
+
+    /// A text style with these properties, for a new text section.
+    fn text_style(&self) -> TextStyle {
+        TextStyle {
+            font_size: self.font_size,
+            color: self.color,
+            ..default()
+        }
+    }
+
+    /// Update an existing text section, leaving it unchanged if it already matches.
+    fn update_section(&self, section: &mut TextSection) {
+        if section.style.font_size != self.font_size {
+            section.style.font_size = self.font_size;
+        }
+        if section.style.color != self.color {
+            section.style.color = self.color;
+        }
+    }
+}
+
+/// Write resolved text properties to a node: its computed font size and color, and the text
+/// of its text children and generated content.
+fn apply_text(world: &mut World, entity: Entity, computed: &ComputedStyle, text: InheritedText) {
+    if let Some(mut e) = world.get_entity_mut(entity) {
+        match e.get_mut::<ComputedFontSize>() {
+            Some(mut size) => {
+                if size.0 != text.font_size {
+                    size.0 = text.font_size;
+                }
+            }
+
+            None => {
+                e.insert(ComputedFontSize(text.font_size));
+            }
+        }
+
+        match e.get_mut::<ComputedTextColor>() {
+            Some(mut color) => {
+                if color.0 != text.color {
+                    color.0 = text.color;
+                }
+            }
+
+            None => {
+                e.insert(ComputedTextColor(text.color));
+            }
+        }
+    }
+
+    update_text_children(world, entity, computed, text);
+    update_generated_content(
+        world,
+        entity,
+        computed.content.clone(),
+        computed.font.as_ref(),
+        text,
+    );
+}
+
+/// Pass a node's text properties down to its descendants. Styled descendants which set their
+/// own font size or color override them for their subtree; descendants which haven't been
+/// styled yet are passed through, and pick up the properties when they are styled. Subtrees
+/// whose properties haven't changed are skipped.
+fn inherit_text(world: &mut World, entity: Entity, text: InheritedText, root_font_size: f32) {
+    let children: Vec<Entity> = match world.get::<Children>(entity) {
+        Some(children) => children.to_vec(),
+        None => return,
+    };
+
+    for child in children {
+        match world.get::<LastComputedStyle>(child) {
+            Some(last) => {
+                let computed = last.0.clone();
+                let child_text = text.child(&computed, root_font_size);
+                let unchanged = world.get::<ComputedFontSize>(child).map(|size| size.0)
+                    == Some(child_text.font_size)
+                    && world.get::<ComputedTextColor>(child).map(|color| color.0)
+                        == Some(child_text.color);
+                if !unchanged {
+                    apply_text(world, child, &computed, child_text);
+                    inherit_text(world, child, child_text, root_font_size);
+                }
+            }
+
+            None => inherit_text(world, child, text, root_font_size),
+        }
+    }
+}
+
+/// Marker for nodes generated by a style rather than by the template, such as the text node
+/// generated from a style's `content` attribute. Rebuilding the view leaves these alone.
+#[derive(Component)]
+pub struct GeneratedContent;
+
+/// Marker for the node which draws a style's `debug-outline`. Bevy 0.11 has no outlines, so
+/// this is an absolutely positioned child covering the node, with a border and nothing else.
+#[derive(Component)]
+pub struct DebugOutline;
+
+/// The node which draws a style's `background-image`, stretched over the node's padding box.
+/// It is the node's first child and sits below its siblings, so it is drawn after the node's
+/// background color and before the node's content. Holds the image's asset path.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct BackgroundImage(pub String);
+
+/// A copy of a text node which draws the `text-shadow` of its parent's style. Bevy 0.11 can't
+/// draw text shadows, so this is a generated sibling of the text node, in the shadow color and
+/// drawn below its siblings. After layout, `update_text_shadows` moves it to the text node's
+/// position plus the shadow offset. The blur radius is not drawn.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct TextShadowLayer {
+    /// The text node which this is the shadow of.
+    pub text: Entity,
+    pub shadow: TextShadow,
+}
+
+/// The computed style which was most recently applied to a node, after `resolve`. Marks the node
+/// as styled, and holds the text properties which it passes down to its descendants.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct LastComputedStyle(pub ComputedStyle);
+
+/// Sent when applying a computed style changes fields of a node's `Style`, so that the node's
+/// controller can react. See `Controller::style_changed`.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct StyleChanged {
+    pub entity: Entity,
+    pub fields: StyleFields,
 }
 
 /// Custom command that updates the style of an entity.
@@ -17,21 +610,69 @@ pub struct UpdateComputedStyle {
 }
 
 impl Command for UpdateComputedStyle {
-    fn apply(self, world: &mut World) {
+    fn apply(mut self, world: &mut World) {
+        self.computed.resolve();
+
+        // Keep the un-animated style, since the animation interpolates from it.
+        let animation = self
+            .computed
+            .animation
+            .as_ref()
+            .map(|spec| StyleAnimation::new(spec.clone(), self.computed.clone()));
+        let background_color = self.computed.rendered_background_color();
+        let border_color = self.computed.rendered_border_color();
+        let snapshot = LastComputedStyle(self.computed.clone());
+        let root_font_size = world
+            .get_resource::<RootFontSize>()
+            .copied()
+            .unwrap_or_default()
+            .0;
+        let parent_text = InheritedText {
+            font_size: inherited::<ComputedFontSize>(world, self.entity)
+                .map_or(root_font_size, |size| size.0),
+            color: inherited::<ComputedTextColor>(world, self.entity)
+                .map_or(TextStyle::default().color, |color| color.0),
+        };
+        let text = parent_text.child(&self.computed, root_font_size);
+
         if let Some(mut e) = world.get_entity_mut(self.entity) {
-            if let Some(mut style) = e.get_mut::<Style>() {
-                // Update the existing style
-                if !style.eq(&self.computed.style) {
-                    *style = self.computed.style;
+            match e.get_mut::<LastComputedStyle>() {
+                Some(mut last) => {
+                    if *last != snapshot {
+                        *last = snapshot;
+                    }
+                }
+
+                None => {
+                    e.insert(snapshot);
                 }
+            }
+
+            let fields = self.computed.style_fields;
+            let previous = e.get::<StyleFields>().copied().unwrap_or_default();
+            let changed = if let Some(mut style) = e.get_mut::<Style>() {
+                // Update the fields of the existing style which guise owns. Fields which were
+                // set last time, but no longer are, go back to their defaults.
+                let mut merged = style.clone();
+                copy_style_fields(&Style::default(), &mut merged, previous.difference(fields));
+                copy_style_fields(&self.computed.style, &mut merged, fields);
+                let changed = changed_style_fields(&style, &merged);
+                if !changed.is_empty() {
+                    *style = merged;
+                }
+                changed
             } else {
                 // Insert a new style component
-                e.insert(self.computed.style);
+                e.insert(self.computed.style.clone());
+                changed_style_fields(&Style::default(), &self.computed.style)
+            };
+            if previous != fields {
+                e.insert(fields);
             }
 
             match e.get_mut::<BackgroundColor>() {
                 Some(mut bg_comp) => {
-                    if let Some(bg_computed) = self.computed.background_color {
+                    if let Some(bg_computed) = background_color {
                         // Mutate the background
                         if bg_comp.0 != bg_computed {
                             bg_comp.0 = bg_computed
@@ -43,7 +684,7 @@ impl Command for UpdateComputedStyle {
                 }
 
                 None => {
-                    if let Some(bg_comp) = self.computed.background_color {
+                    if let Some(bg_comp) = background_color {
                         // Insert a new background
                         e.insert(BackgroundColor(bg_comp));
                     }
@@ -52,7 +693,7 @@ impl Command for UpdateComputedStyle {
 
             match e.get_mut::<BorderColor>() {
                 Some(mut bc_comp) => {
-                    if let Some(bc_computed) = self.computed.border_color {
+                    if let Some(bc_computed) = border_color {
                         // Mutate the border color
                         if bc_comp.0 != bc_computed {
                             bc_comp.0 = bc_computed
@@ -64,12 +705,1323 @@ impl Command for UpdateComputedStyle {
                 }
 
                 None => {
-                    if let Some(bc_comp) = self.computed.border_color {
+                    if let Some(bc_comp) = border_color {
                         // Insert a new background color
                         e.insert(BorderColor(bc_comp));
                     }
                 }
             }
+
+            // An unset z-index leaves the node at Bevy's default stacking order.
+            let z_index = self.computed.z_index.unwrap_or(0);
+            match e.get_mut::<ZIndex>() {
+                Some(mut z_comp) => {
+                    if !matches!(*z_comp, ZIndex::Local(z) if z == z_index) {
+                        *z_comp = ZIndex::Local(z_index);
+                    }
+                }
+
+                None => {
+                    if self.computed.z_index.is_some() {
+                        e.insert(ZIndex::Local(z_index));
+                    }
+                }
+            }
+
+            if let Some(next) = animation {
+                match e.get_mut::<StyleAnimation>() {
+                    Some(mut current) => {
+                        if current.spec != next.spec {
+                            // Restart the animation
+                            *current = next;
+                        } else {
+                            current.base = next.base;
+                        }
+                    }
+
+                    None => {
+                        e.insert(next);
+                    }
+                }
+            } else if e.contains::<StyleAnimation>() {
+                e.remove::<StyleAnimation>();
+            }
+
+            if self.computed.transform.is_identity() {
+                if e.contains::<StyleTransform>() {
+                    e.remove::<StyleTransform>();
+                    // Layout restores the translation, but not the rotation or scale.
+                    if let Some(mut transform) = e.get_mut::<Transform>() {
+                        transform.rotation = Quat::IDENTITY;
+                        transform.scale = Vec3::ONE;
+                    }
+                }
+            } else {
+                match e.get_mut::<StyleTransform>() {
+                    Some(mut st) => {
+                        if *st != self.computed.transform {
+                            *st = self.computed.transform;
+                        }
+                    }
+
+                    None => {
+                        e.insert(self.computed.transform);
+                    }
+                }
+            }
+
+            // Sizes measured by `update_max_content` aren't owned by the style, so reset them
+            // when the axis stops being measured, unless the style now sets them.
+            let measured = e
+                .get::<MaxContent>()
+                .map_or(BVec2::default(), |mc| mc.0 & !self.computed.max_content);
+            if measured.any() {
+                if let Some(mut style) = e.get_mut::<Style>() {
+                    if measured.x && !fields.contains(StyleField::Width) {
+                        style.width = Style::default().width;
+                    }
+                    if measured.y && !fields.contains(StyleField::Height) {
+                        style.height = Style::default().height;
+                    }
+                }
+            }
+
+            if !self.computed.max_content.any() {
+                if e.contains::<MaxContent>() {
+                    e.remove::<MaxContent>();
+                }
+            } else {
+                match e.get_mut::<MaxContent>() {
+                    Some(mut mc) => {
+                        if mc.0 != self.computed.max_content {
+                            mc.0 = self.computed.max_content;
+                        }
+                    }
+
+                    None => {
+                        e.insert(MaxContent(self.computed.max_content));
+                    }
+                }
+            }
+
+            update_background_image(
+                world,
+                self.entity,
+                self.computed.background_image.clone(),
+                self.computed.fade(Color::WHITE),
+            );
+            apply_text(world, self.entity, &self.computed, text);
+            update_text_shadow_layers(world, self.entity, self.computed.text_shadow);
+            inherit_text(world, self.entity, text, root_font_size);
+            #[cfg(debug_assertions)]
+            update_debug_outline(world, self.entity, self.computed.debug_outline);
+
+            if !changed.is_empty() {
+                if let Some(mut events) = world.get_resource_mut::<Events<StyleChanged>>() {
+                    events.send(StyleChanged {
+                        entity: self.entity,
+                        fields: changed,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Re-process the template text of the node's text children using the node's whitespace
+/// handling, text alignment, font size and color.
+fn update_text_children(
+    world: &mut World,
+    entity: Entity,
+    computed: &ComputedStyle,
+    text_style: InheritedText,
+) {
+    let white_space = computed.white_space;
+    let alignment = computed.text_alignment();
+    let root_font_size = world
+        .get_resource::<RootFontSize>()
+        .copied()
+        .unwrap_or_default()
+        .0;
+    let indent = computed.text_indent.map_or(0., |indent| {
+        indent.resolve(text_style.font_size, root_font_size)
+    });
+    let children: Vec<Entity> = match world.get::<Children>(entity) {
+        Some(children) => children.to_vec(),
+        None => return,
+    };
+
+    // Only the first text child starts the first line of the element.
+    let mut indent = Some(indent);
+    for child in children {
+        let content = match world.get::<ViewText>(child) {
+            Some(view_text) => indent_first_line(
+                clamp_lines(white_space.process(&view_text.source), computed.line_clamp),
+                indent.take().unwrap_or(0.),
+                text_style.font_size,
+            ),
+            None => continue,
+        };
+        if let Some(mut text) = world.get_mut::<Text>(child) {
+            if text.linebreak_behavior != white_space.line_break() {
+                text.linebreak_behavior = white_space.line_break();
+            }
+            if text.alignment != alignment {
+                text.alignment = alignment;
+            }
+            match text.sections.first_mut() {
+                Some(section) => {
+                    if section.value != content {
+                        section.value = content;
+                    }
+                    text_style.update_section(section);
+                    update_font(section, computed.font.as_ref());
+                }
+                None => {
+                    let mut section = TextSection::new(content, text_style.text_style());
+                    update_font(&mut section, computed.font.as_ref());
+                    text.sections.push(section);
+                }
+            }
+        }
+    }
+}
+
+/// Set the font of a text section to `font`, or to the default font if it isn't set or hasn't
+/// been resolved.
+fn update_font(section: &mut TextSection, font: Option<&FontRef>) {
+    let handle = font
+        .and_then(|font| font.handle.clone())
+        .unwrap_or_default();
+    if section.style.font != handle {
+        section.style.font = handle;
+    }
+}
+
+/// Cut `text` after `max_lines` lines, ending it with an ellipsis if anything was removed. A
+/// limit of zero lines leaves no text at all.
+fn clamp_lines(text: String, max_lines: Option<u16>) -> String {
+    let Some(max_lines) = max_lines else {
+        return text;
+    };
+    let Some(last) = (max_lines as usize).checked_sub(1) else {
+        return String::new();
+    };
+    match text.match_indices('\n').nth(last) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// Approximate width of a space, as a fraction of the font size, which is typical of
+/// proportional fonts.
+const SPACE_WIDTH: f32 = 0.25;
+
+/// The most spaces an indent is approximated with.
+const MAX_INDENT_SPACES: f32 = 256.;
+
+/// Indent `text` by about `indent` pixels, using as many spaces as fit.
+fn indent_first_line(text: String, indent: f32, font_size: f32) -> String {
+    if font_size <= 0. {
+        return text;
+    }
+    let spaces = (indent / (font_size * SPACE_WIDTH)).round();
+    if spaces.is_finite() && spaces >= 1. {
+        format!(
+            "{}{}",
+            " ".repeat(spaces.min(MAX_INDENT_SPACES) as usize),
+            text
+        )
+    } else {
+        text
+    }
+}
+
+/// Create, update or remove the text child generated from the `content` attribute.
+fn update_generated_content(
+    world: &mut World,
+    entity: Entity,
+    content: Option<String>,
+    font: Option<&FontRef>,
+    text_style: InheritedText,
+) {
+    let generated = world.get::<Children>(entity).and_then(|children| {
+        children.iter().copied().find(|child| {
+            world.get::<GeneratedContent>(*child).is_some()
+                && world.get::<DebugOutline>(*child).is_none()
+                && world.get::<BackgroundImage>(*child).is_none()
+                && world.get::<TextShadowLayer>(*child).is_none()
+        })
+    });
+
+    match (generated, content) {
+        (Some(child), Some(content)) => {
+            if let Some(mut text) = world.get_mut::<Text>(child) {
+                match text.sections.first_mut() {
+                    Some(section) => {
+                        if section.value != content {
+                            section.value = content;
+                        }
+                        text_style.update_section(section);
+                        update_font(section, font);
+                    }
+                    None => {
+                        let mut section = TextSection::new(content, text_style.text_style());
+                        update_font(&mut section, font);
+                        text.sections.push(section);
+                    }
+                }
+            }
+        }
+
+        (Some(child), None) => {
+            world.entity_mut(child).despawn_recursive();
+        }
+
+        (None, Some(content)) => {
+            let mut section = TextSection::new(content, text_style.text_style());
+            update_font(&mut section, font);
+            let child = world
+                .spawn((
+                    TextBundle {
+                        text: Text::from_sections([section]),
+                        ..default()
+                    },
+                    GeneratedContent,
+                ))
+                .id();
+            world.entity_mut(entity).insert_children(0, &[child]);
+        }
+
+        (None, None) => {}
+    }
+}
+
+/// Create, update or remove the nodes which draw the `text-shadow` of each of the node's text
+/// children. Layers whose text node has gone are removed too.
+fn update_text_shadow_layers(world: &mut World, entity: Entity, shadow: Option<TextShadow>) {
+    let children: Vec<Entity> = match world.get::<Children>(entity) {
+        Some(children) => children.to_vec(),
+        None => return,
+    };
+    let texts: Vec<Entity> = children
+        .iter()
+        .copied()
+        .filter(|child| world.get::<ViewText>(*child).is_some())
+        .collect();
+
+    let mut shadowed = Vec::with_capacity(texts.len());
+    for child in children {
+        let Some(layer) = world.get::<TextShadowLayer>(child).copied() else {
+            continue;
+        };
+        match shadow {
+            Some(shadow) if texts.contains(&layer.text) => {
+                if layer.shadow != shadow {
+                    world.entity_mut(child).insert(TextShadowLayer {
+                        text: layer.text,
+                        shadow,
+                    });
+                }
+                shadowed.push(layer.text);
+            }
+
+            _ => {
+                world.entity_mut(child).despawn_recursive();
+            }
+        }
+    }
+
+    let Some(shadow) = shadow else {
+        return;
+    };
+    for text in texts {
+        if !shadowed.contains(&text) {
+            let layer = world
+                .spawn((
+                    TextBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            ..default()
+                        },
+                        z_index: ZIndex::Local(-1),
+                        ..default()
+                    },
+                    GeneratedContent,
+                    TextShadowLayer { text, shadow },
+                ))
+                .id();
+            world.entity_mut(entity).insert_children(0, &[layer]);
+        }
+    }
+}
+
+/// Convert a shadow offset to pixels. Only pixel lengths can be resolved here; other units are
+/// treated as zero.
+fn resolve_px(val: Val) -> f32 {
+    match val {
+        Val::Px(px) => px,
+        _ => 0.,
+    }
+}
+
+/// Copies each text node's text, size and position to its `TextShadowLayer`. Layout
+/// overwrites the position of the layer every time it changes, so this has to run after layout
+/// and before transform propagation. Layers whose text node has been despawned are removed.
+pub fn update_text_shadows(
+    mut commands: Commands,
+    mut layers: Query<(
+        Entity,
+        Ref<TextShadowLayer>,
+        &mut Text,
+        &mut Style,
+        &mut Transform,
+    )>,
+    texts: Query<(Ref<Text>, &Node, &Transform), Without<TextShadowLayer>>,
+) {
+    for (entity, layer, mut text, mut style, mut transform) in layers.iter_mut() {
+        let Ok((source, node, source_transform)) = texts.get(layer.text) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        if layer.is_changed() || source.is_changed() {
+            let mut shadow_text = (*source).clone();
+            for section in shadow_text.sections.iter_mut() {
+                section.style.color = layer.shadow.color;
+            }
+            *text = shadow_text;
+        }
+
+        // Give the copy the same size, so that it wraps in the same places.
+        let size = node.size();
+        if style.width != Val::Px(size.x) || style.height != Val::Px(size.y) {
+            style.width = Val::Px(size.x);
+            style.height = Val::Px(size.y);
+        }
+
+        let translation = source_transform.translation
+            + Vec3::new(
+                resolve_px(layer.shadow.offset_x),
+                resolve_px(layer.shadow.offset_y),
+                0.,
+            );
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
+
+/// Create, update or remove the node which draws the image from the `background-image`
+/// attribute. `tint` carries the node's opacity.
+fn update_background_image(world: &mut World, entity: Entity, path: Option<String>, tint: Color) {
+    let layer = world.get::<Children>(entity).and_then(|children| {
+        children
+            .iter()
+            .copied()
+            .find(|child| world.get::<BackgroundImage>(*child).is_some())
+    });
+    let load = |world: &World, path: &str| -> Handle<Image> {
+        world
+            .get_resource::<AssetServer>()
+            .map_or_else(Handle::default, |server| server.load(path))
+    };
+
+    match (layer, path) {
+        (Some(child), Some(path)) => {
+            if world.get::<BackgroundImage>(child).unwrap().0 != path {
+                let texture = load(world, &path);
+                let mut e = world.entity_mut(child);
+                e.insert(BackgroundImage(path));
+                e.get_mut::<UiImage>().unwrap().texture = texture;
+            }
+            if let Some(mut color) = world.get_mut::<BackgroundColor>(child) {
+                if color.0 != tint {
+                    color.0 = tint;
+                }
+            }
+        }
+
+        (Some(child), None) => {
+            world.entity_mut(child).despawn_recursive();
+        }
+
+        (None, Some(path)) => {
+            let texture = load(world, &path);
+            let child = world
+                .spawn((
+                    ImageBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(0.),
+                            right: Val::Px(0.),
+                            top: Val::Px(0.),
+                            bottom: Val::Px(0.),
+                            ..default()
+                        },
+                        image: UiImage::new(texture),
+                        background_color: BackgroundColor(tint),
+                        z_index: ZIndex::Local(i32::MIN),
+                        ..default()
+                    },
+                    GeneratedContent,
+                    BackgroundImage(path),
+                ))
+                .id();
+            world.entity_mut(entity).insert_children(0, &[child]);
+        }
+
+        (None, None) => {}
+    }
+}
+
+/// Create, update or remove the node which draws the outline from the `debug-outline`
+/// attribute.
+#[cfg(debug_assertions)]
+fn update_debug_outline(world: &mut World, entity: Entity, color: Option<Color>) {
+    let outline = world.get::<Children>(entity).and_then(|children| {
+        children
+            .iter()
+            .copied()
+            .find(|child| world.get::<DebugOutline>(*child).is_some())
+    });
+
+    match (outline, color) {
+        (Some(child), Some(color)) => {
+            if let Some(mut border_color) = world.get_mut::<BorderColor>(child) {
+                if border_color.0 != color {
+                    border_color.0 = color;
+                }
+            }
+        }
+
+        (Some(child), None) => {
+            world.entity_mut(child).despawn_recursive();
+        }
+
+        (None, Some(color)) => {
+            let child = world
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(0.),
+                            right: Val::Px(0.),
+                            top: Val::Px(0.),
+                            bottom: Val::Px(0.),
+                            border: UiRect::all(Val::Px(1.)),
+                            ..default()
+                        },
+                        border_color: BorderColor(color),
+                        z_index: ZIndex::Local(i32::MAX),
+                        ..default()
+                    },
+                    GeneratedContent,
+                    DebugOutline,
+                ))
+                .id();
+            world.entity_mut(entity).add_child(child);
+        }
+
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::AssetPlugin;
+
+    use super::*;
+    use crate::guise::style::StyleAttr;
+
+    #[test]
+    fn test_generated_content() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle {
+                content: Some("★".to_string()),
+                ..default()
+            },
+        }
+        .apply(&mut world);
+
+        let children = world.get::<Children>(entity).unwrap();
+        assert_eq!(children.len(), 1);
+        let text = world.get::<Text>(children[0]).unwrap();
+        assert_eq!(text.sections[0].value, "★");
+
+        // Clearing the content removes the generated child.
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        assert!(world
+            .get::<Children>(entity)
+            .map_or(true, |children| children.is_empty()));
+    }
+
+    #[test]
+    fn test_from_stylesets() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<PartialStyle>();
+        let mut assets = app.world.resource_mut::<Assets<PartialStyle>>();
+        let base = assets.add(PartialStyle::from_attrs(&[
+            StyleAttr::Width(Val::Px(10.)),
+            StyleAttr::Height(Val::Px(10.)),
+            StyleAttr::BackgroundColor(Some(Color::RED)),
+        ]));
+        let wide = assets.add(PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(20.))]));
+        let inline = PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::BLUE))]);
+
+        let computed = ComputedStyle::from_stylesets(
+            &[base, wide, Handle::default()],
+            app.world.resource::<Assets<PartialStyle>>(),
+            Some(&inline),
+        );
+        assert_eq!(computed.style.width, Val::Px(20.));
+        assert_eq!(computed.style.height, Val::Px(10.));
+        assert_eq!(computed.background_color, Some(Color::BLUE));
+    }
+
+    #[test]
+    fn test_content_box() {
+        let style = PartialStyle::from_attrs(&[
+            StyleAttr::BoxSizing(BoxSizing::ContentBox),
+            StyleAttr::Width(Val::Px(100.)),
+            StyleAttr::Height(Val::Percent(50.)),
+            StyleAttr::Padding(UiRect::all(Val::Px(10.))),
+            StyleAttr::Border(UiRect::all(Val::Px(2.))),
+        ]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+        computed.resolve();
+        // The outer width includes the padding and border on both sides.
+        assert_eq!(computed.style.width, Val::Px(124.));
+        // Percentages can't be added to pixels without layout.
+        assert_eq!(computed.style.height, Val::Percent(50.));
+
+        // Border-box sizes are left alone.
+        let mut computed = ComputedStyle::default();
+        style.attrs()[1..]
+            .iter()
+            .for_each(|attr| attr.apply(&mut computed));
+        computed.resolve();
+        assert_eq!(computed.style.width, Val::Px(100.));
+    }
+
+    #[test]
+    fn test_min_width_zero() {
+        // Flex items default to `min-width: auto`, which stops them shrinking below their
+        // content; an explicit zero must come through as zero pixels, not as `auto`.
+        for value in ["0", "0px"] {
+            let style = PartialStyle::from_attrs(&[
+                StyleAttr::parse(b"flex", "1 1 0").unwrap().unwrap(),
+                StyleAttr::parse(b"min-width", value).unwrap().unwrap(),
+            ]);
+            let mut computed = ComputedStyle::default();
+            style.apply_to(&mut computed);
+            computed.resolve();
+            assert_eq!(computed.style.min_width, Val::Px(0.));
+            assert_eq!(computed.style.flex_shrink, 1.);
+        }
+    }
+
+    #[test]
+    fn test_accessors() {
+        let style = PartialStyle::from_attrs(&[
+            StyleAttr::Width(Val::Px(40.)),
+            StyleAttr::Color(Color::RED),
+            StyleAttr::ZIndex(Some(3)),
+        ]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+        assert_eq!(computed.bevy_style().width, Val::Px(40.));
+        assert_eq!(computed.color(), Some(Color::RED));
+        assert_eq!(computed.z_index(), Some(3));
+        assert_eq!(computed.line_break(), BreakLineOn::WordBoundary);
+    }
+
+    #[test]
+    fn test_owned_style_fields() {
+        let mut world = World::new();
+        let entity = world
+            .spawn(Style {
+                width: Val::Px(50.),
+                ..default()
+            })
+            .id();
+
+        // A style which doesn't set the width leaves it alone.
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Height(Val::Px(20.)).apply(&mut computed);
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        let style = world.get::<Style>(entity).unwrap();
+        assert_eq!(style.width, Val::Px(50.));
+        assert_eq!(style.height, Val::Px(20.));
+
+        // A field which is no longer set by the style is reset.
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Width(Val::Px(10.)).apply(&mut computed);
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        let style = world.get::<Style>(entity).unwrap();
+        assert_eq!(style.width, Val::Px(10.));
+        assert_eq!(style.height, Val::Auto);
+    }
+
+    #[test]
+    fn test_max_content() {
+        let mut world = World::new();
+        let entity = world
+            .spawn(Style {
+                width: Val::Px(50.),
+                ..default()
+            })
+            .id();
+
+        // The width is left to `update_max_content`, rather than reset by the style.
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Width(Val::Px(10.)).apply(&mut computed);
+        StyleAttr::MaxContentWidth.apply(&mut computed);
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        assert_eq!(world.get::<Style>(entity).unwrap().width, Val::Px(50.));
+        assert_eq!(
+            world.get::<MaxContent>(entity),
+            Some(&MaxContent(BVec2::new(true, false)))
+        );
+
+        // A later width replaces it.
+        let mut computed = ComputedStyle::default();
+        StyleAttr::MaxContentWidth.apply(&mut computed);
+        StyleAttr::Width(Val::Px(10.)).apply(&mut computed);
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        assert_eq!(world.get::<Style>(entity).unwrap().width, Val::Px(10.));
+        assert!(world.get::<MaxContent>(entity).is_none());
+
+        // Turning it off without a replacement resets the measured width.
+        let mut computed = ComputedStyle::default();
+        StyleAttr::MaxContentWidth.apply(&mut computed);
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        world.get_mut::<Style>(entity).unwrap().width = Val::Px(64.);
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<Style>(entity).unwrap().width, Val::Auto);
+        assert!(world.get::<MaxContent>(entity).is_none());
+    }
+
+    #[test]
+    fn test_text_shadow() {
+        let mut world = World::new();
+        let text = world
+            .spawn((
+                TextBundle::from_section("Title", default()),
+                ViewText {
+                    source: "Title".to_string(),
+                },
+            ))
+            .id();
+        let entity = world.spawn_empty().push_children(&[text]).id();
+
+        let shadow = TextShadow {
+            offset_x: Val::Px(2.),
+            offset_y: Val::Px(3.),
+            blur: Val::Px(0.),
+            color: Color::BLACK,
+        };
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle {
+                text_shadow: Some(shadow),
+                ..default()
+            },
+        }
+        .apply(&mut world);
+
+        // The layer is drawn first, below the text.
+        let children = world.get::<Children>(entity).unwrap().to_vec();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1], text);
+        let layer = children[0];
+        assert_eq!(
+            world.get::<TextShadowLayer>(layer),
+            Some(&TextShadowLayer { text, shadow })
+        );
+        assert!(world.get::<GeneratedContent>(layer).is_some());
+
+        world.get_mut::<Transform>(text).unwrap().translation = Vec3::new(10., 20., 0.);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_text_shadows);
+        schedule.run(&mut world);
+        let shadow_text = world.get::<Text>(layer).unwrap();
+        assert_eq!(shadow_text.sections[0].value, "Title");
+        assert_eq!(shadow_text.sections[0].style.color, Color::BLACK);
+        assert_eq!(
+            world.get::<Transform>(layer).unwrap().translation,
+            Vec3::new(12., 23., 0.)
+        );
+
+        // Removing the shadow removes the layer.
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        assert!(world.get_entity(layer).is_none());
+        assert_eq!(world.get::<Children>(entity).unwrap().to_vec(), vec![text]);
+    }
+
+    #[test]
+    fn test_text_shadow_with_content() {
+        let mut world = World::new();
+        let text = world
+            .spawn((
+                TextBundle::from_section("Body", default()),
+                ViewText {
+                    source: "Body".to_string(),
+                },
+            ))
+            .id();
+        let entity = world.spawn_empty().push_children(&[text]).id();
+
+        let shadow = TextShadow {
+            offset_x: Val::Px(1.),
+            offset_y: Val::Px(1.),
+            blur: Val::Px(0.),
+            color: Color::BLACK,
+        };
+        for _ in 0..2 {
+            UpdateComputedStyle {
+                entity,
+                computed: ComputedStyle {
+                    content: Some("Label".to_string()),
+                    text_shadow: Some(shadow),
+                    ..default()
+                },
+            }
+            .apply(&mut world);
+        }
+
+        let children = world.get::<Children>(entity).unwrap().to_vec();
+        assert_eq!(children.len(), 3);
+        let layer = children
+            .iter()
+            .copied()
+            .find(|child| world.get::<TextShadowLayer>(*child).is_some())
+            .unwrap();
+        assert_eq!(world.get::<TextShadowLayer>(layer).unwrap().text, text);
+        assert!(world.get::<Text>(layer).unwrap().sections.is_empty());
+        let generated = children
+            .iter()
+            .copied()
+            .find(|child| *child != layer && world.get::<GeneratedContent>(*child).is_some())
+            .unwrap();
+        assert_eq!(
+            world.get::<Text>(generated).unwrap().sections[0].value,
+            "Label"
+        );
+        assert_eq!(world.get::<Text>(text).unwrap().sections[0].value, "Body");
+
+        // Restyling without content keeps the shadow.
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle {
+                text_shadow: Some(shadow),
+                ..default()
+            },
+        }
+        .apply(&mut world);
+        let children = world.get::<Children>(entity).unwrap().to_vec();
+        assert_eq!(children, vec![layer, text]);
+    }
+
+    #[test]
+    fn test_text_indent() {
+        let mut world = World::new();
+        let texts: Vec<Entity> = ["First", "Second"]
+            .into_iter()
+            .map(|source| {
+                world
+                    .spawn((
+                        TextBundle::from_section(source, default()),
+                        ViewText {
+                            source: source.to_string(),
+                        },
+                    ))
+                    .id()
+            })
+            .collect();
+        let entity = world.spawn_empty().push_children(&texts).id();
+
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle {
+                font_size: Some(FontSize::Px(16.)),
+                text_indent: Some(FontSize::Px(8.)),
+                ..default()
+            },
+        }
+        .apply(&mut world);
+        let value = |world: &World, text: Entity| {
+            world.get::<Text>(text).unwrap().sections[0].value.clone()
+        };
+        assert_eq!(value(&world, texts[0]), "  First");
+        assert_eq!(value(&world, texts[1]), "Second");
+
+        // A zero font size or a huge indent doesn't overflow.
+        assert_eq!(indent_first_line("a".to_string(), 8., 0.), "a");
+        assert_eq!(
+            indent_first_line("a".to_string(), f32::MAX, 16.).len(),
+            MAX_INDENT_SPACES as usize + 1
+        );
+    }
+
+    #[test]
+    fn test_white_space() {
+        let mut world = World::new();
+        let source = "\n  let x = 1;\n  let y = 2;";
+        let text = world
+            .spawn((
+                TextBundle::from_section(WhiteSpace::Normal.process(source), default()),
+                ViewText {
+                    source: source.to_string(),
+                },
+            ))
+            .id();
+        let entity = world.spawn_empty().push_children(&[text]).id();
+
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle {
+                white_space: WhiteSpace::Pre,
+                ..default()
+            },
+        }
+        .apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].value,
+            "  let x = 1;\n  let y = 2;"
+        );
+
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].value,
+            "let x = 1; let y = 2;"
+        );
+
+        // Escaped line breaks survive collapsing.
+        world.get_mut::<ViewText>(text).unwrap().source = "first line\\n second line".to_string();
+        UpdateComputedStyle {
+            entity,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].value,
+            "first line\nsecond line"
+        );
+    }
+
+    #[test]
+    fn test_line_clamp() {
+        let mut world = World::new();
+        let source = "one\ntwo \nthree";
+        let text = world
+            .spawn((
+                TextBundle::from_section(source, default()),
+                ViewText {
+                    source: source.to_string(),
+                },
+            ))
+            .id();
+        let entity = world.spawn_empty().push_children(&[text]).id();
+        let mut clamp = |line_clamp| {
+            UpdateComputedStyle {
+                entity,
+                computed: ComputedStyle {
+                    white_space: WhiteSpace::Pre,
+                    line_clamp,
+                    ..default()
+                },
+            }
+            .apply(&mut world);
+            world.get::<Text>(text).unwrap().sections[0].value.clone()
+        };
+
+        assert_eq!(clamp(Some(1)), "one…");
+        assert_eq!(clamp(Some(2)), "one\ntwo…");
+        assert_eq!(clamp(Some(3)), "one\ntwo \nthree");
+        assert_eq!(clamp(None), "one\ntwo \nthree");
+        assert_eq!(clamp(Some(0)), "");
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_debug_outline() {
+        let mut world = World::new();
+        let entity = world.spawn(NodeBundle::default()).id();
+        let outline = |world: &World| {
+            world.get::<Children>(entity).and_then(|children| {
+                children
+                    .iter()
+                    .copied()
+                    .find(|child| world.get::<DebugOutline>(*child).is_some())
+            })
+        };
+        let update = |world: &mut World, debug_outline| {
+            UpdateComputedStyle {
+                entity,
+                computed: ComputedStyle {
+                    debug_outline,
+                    content: Some("label".to_string()),
+                    ..default()
+                },
+            }
+            .apply(world)
+        };
+
+        update(&mut world, Some(Color::FUCHSIA));
+        let child = outline(&world).expect("outline should be inserted");
+        assert_eq!(world.get::<BorderColor>(child).unwrap().0, Color::FUCHSIA);
+        assert_eq!(
+            world.get::<Style>(child).unwrap().position_type,
+            PositionType::Absolute
+        );
+
+        // The outline is separate from the generated content.
+        assert_eq!(world.get::<Children>(entity).unwrap().len(), 2);
+
+        update(&mut world, Some(Color::CYAN));
+        assert_eq!(outline(&world), Some(child));
+        assert_eq!(world.get::<BorderColor>(child).unwrap().0, Color::CYAN);
+
+        update(&mut world, None);
+        assert_eq!(outline(&world), None);
+        assert_eq!(world.get::<Children>(entity).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_background_layers() {
+        let mut world = World::new();
+        let entity = world.spawn(NodeBundle::default()).id();
+        let label = world.spawn(NodeBundle::default()).id();
+        world.entity_mut(entity).add_child(label);
+
+        let mut computed = ComputedStyle::default();
+        PartialStyle::from_attrs(&[
+            StyleAttr::parse(b"background-color", "#336")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::parse(b"background-image", "url('ui/panel.png')")
+                .unwrap()
+                .unwrap(),
+            StyleAttr::Opacity(0.5),
+        ])
+        .apply_to(&mut computed);
+        assert_eq!(computed.background_color, Some(Color::hex("#336").unwrap()));
+        assert_eq!(computed.background_image.as_deref(), Some("ui/panel.png"));
+
+        UpdateComputedStyle {
+            entity,
+            computed: computed.clone(),
+        }
+        .apply(&mut world);
+
+        // The color is drawn by the node itself, and the image by its first child, which is
+        // drawn after the node and before the node's other children.
+        assert_eq!(
+            world.get::<BackgroundColor>(entity).unwrap().0,
+            Color::hex("#336").unwrap().with_a(0.5)
+        );
+        let children = world.get::<Children>(entity).unwrap().to_vec();
+        assert_eq!(children.len(), 2);
+        let layer = children[0];
+        assert_eq!(children[1], label);
+        assert_eq!(
+            world.get::<BackgroundImage>(layer).unwrap().0,
+            "ui/panel.png"
+        );
+        assert!(world.get::<UiImage>(layer).is_some());
+        assert!(matches!(
+            world.get::<ZIndex>(layer),
+            Some(ZIndex::Local(i32::MIN))
+        ));
+        assert_eq!(
+            world.get::<BackgroundColor>(layer).unwrap().0,
+            Color::WHITE.with_a(0.5)
+        );
+
+        // Changing the image reuses the layer; removing it removes the layer.
+        computed.background_image = Some("ui/button.png".to_string());
+        UpdateComputedStyle {
+            entity,
+            computed: computed.clone(),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<Children>(entity).unwrap()[0], layer);
+        assert_eq!(
+            world.get::<BackgroundImage>(layer).unwrap().0,
+            "ui/button.png"
+        );
+
+        computed.background_image = None;
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        assert_eq!(world.get::<Children>(entity).unwrap().to_vec(), vec![label]);
+        assert!(world.get::<BackgroundColor>(entity).is_some());
+    }
+
+    #[test]
+    fn test_text_align() {
+        let mut world = World::new();
+        let text = world
+            .spawn((
+                TextBundle::from_section("hello", default()),
+                ViewText {
+                    source: "hello".to_string(),
+                },
+            ))
+            .id();
+        let entity = world.spawn_empty().push_children(&[text]).id();
+
+        // `text-align` comes before `direction`, so `start` must be resolved afterwards.
+        let style = PartialStyle::from_attrs(&[
+            StyleAttr::TextAlign(TextAlign::Start),
+            StyleAttr::Direction(bevy::ui::Direction::RightToLeft),
+        ]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+        assert_eq!(computed.text_alignment(), TextAlignment::Right);
+
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().alignment,
+            TextAlignment::Right
+        );
+
+        let style = PartialStyle::from_attrs(&[StyleAttr::TextAlign(TextAlign::Start)]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+        assert_eq!(computed.text_alignment(), TextAlignment::Left);
+
+        UpdateComputedStyle { entity, computed }.apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().alignment,
+            TextAlignment::Left
+        );
+    }
+
+    #[test]
+    fn test_last_computed_style() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+
+        let mut computed = ComputedStyle::default();
+        PartialStyle::from_attrs(&[
+            StyleAttr::Width(Val::Px(10.)),
+            StyleAttr::BackgroundColor(Some(Color::RED)),
+        ])
+        .apply_to(&mut computed);
+        UpdateComputedStyle {
+            entity,
+            computed: computed.clone(),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<LastComputedStyle>(entity).unwrap().0, computed);
+
+        let mut computed = ComputedStyle::default();
+        PartialStyle::from_attrs(&[
+            StyleAttr::Border(UiRect::all(Val::Px(2.))),
+            StyleAttr::BorderStyle(BorderStyle::None),
+        ])
+        .apply_to(&mut computed);
+        UpdateComputedStyle {
+            entity,
+            computed: computed.clone(),
+        }
+        .apply(&mut world);
+
+        // The snapshot is taken after `resolve`.
+        computed.resolve();
+        let last = &world.get::<LastComputedStyle>(entity).unwrap().0;
+        assert_eq!(*last, computed);
+        assert_eq!(last.style.border, UiRect::all(Val::Px(0.)));
+    }
+
+    #[test]
+    fn test_font_size() {
+        let mut world = World::new();
+        world.insert_resource(RootFontSize(20.));
+        let text = world
+            .spawn((
+                TextBundle::from_section("hello", default()),
+                ViewText {
+                    source: "hello".to_string(),
+                },
+            ))
+            .id();
+        let child = world.spawn_empty().push_children(&[text]).id();
+        let parent = world
+            .spawn(ComputedFontSize(16.))
+            .push_children(&[child])
+            .id();
+
+        let font_size = |size: FontSize| ComputedStyle {
+            font_size: Some(size),
+            ..default()
+        };
+
+        UpdateComputedStyle {
+            entity: child,
+            computed: font_size(FontSize::Percent(150.)),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<ComputedFontSize>(child).unwrap().0, 24.);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].style.font_size,
+            24.
+        );
+
+        UpdateComputedStyle {
+            entity: child,
+            computed: font_size(FontSize::Rem(1.5)),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<ComputedFontSize>(child).unwrap().0, 30.);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].style.font_size,
+            30.
+        );
+
+        // Without a font size, the parent's size is inherited.
+        UpdateComputedStyle {
+            entity: child,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<ComputedFontSize>(child).unwrap().0, 16.);
+
+        // With no ancestor font size, relative sizes are resolved against the root.
+        world.entity_mut(parent).remove::<ComputedFontSize>();
+        UpdateComputedStyle {
+            entity: child,
+            computed: font_size(FontSize::Em(2.)),
+        }
+        .apply(&mut world);
+        assert_eq!(world.get::<ComputedFontSize>(child).unwrap().0, 40.);
+    }
+
+    #[test]
+    fn test_inherited_color() {
+        let mut world = World::new();
+        let text = world
+            .spawn((
+                TextBundle::from_section("hello", default()),
+                ViewText {
+                    source: "hello".to_string(),
+                },
+            ))
+            .id();
+        let inner = world.spawn_empty().push_children(&[text]).id();
+        let container = world.spawn_empty().push_children(&[inner]).id();
+
+        // The inner element is styled first, so the color reaches it through the inheritance
+        // pass when the container is styled.
+        UpdateComputedStyle {
+            entity: inner,
+            computed: ComputedStyle::default(),
+        }
+        .apply(&mut world);
+        let mut computed = ComputedStyle::default();
+        PartialStyle::from_attrs(&[
+            StyleAttr::Color(Color::RED),
+            StyleAttr::Width(Val::Px(100.)),
+        ])
+        .apply_to(&mut computed);
+        UpdateComputedStyle {
+            entity: container,
+            computed,
+        }
+        .apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].style.color,
+            Color::RED
+        );
+        assert_eq!(world.get::<ComputedTextColor>(inner).unwrap().0, Color::RED);
+        // Layout properties aren't inherited.
+        assert_eq!(world.get::<Style>(inner).unwrap().width, Val::Auto);
+
+        // A color on the inner element overrides the inherited one.
+        UpdateComputedStyle {
+            entity: inner,
+            computed: ComputedStyle {
+                color: Some(Color::BLUE),
+                ..default()
+            },
+        }
+        .apply(&mut world);
+        assert_eq!(
+            world.get::<Text>(text).unwrap().sections[0].style.color,
+            Color::BLUE
+        );
+    }
+
+    #[test]
+    fn test_initial_text() {
+        let mut world = World::new();
+        world.insert_resource(RootFontSize(20.));
+        let text = world
+            .spawn((
+                TextBundle::from_section("hello", default()),
+                ViewText {
+                    source: "hello".to_string(),
+                },
+            ))
+            .id();
+        let child = world.spawn_empty().push_children(&[text]).id();
+        let parent = world.spawn_empty().push_children(&[child]).id();
+
+        let style = |attrs: &[(&[u8], &str)]| {
+            let mut computed = ComputedStyle::default();
+            let attrs: Vec<StyleAttr> = attrs
+                .iter()
+                .map(|(name, value)| StyleAttr::parse(name, value).unwrap().unwrap())
+                .collect();
+            PartialStyle::from_attrs(&attrs).apply_to(&mut computed);
+            computed
+        };
+        UpdateComputedStyle {
+            entity: parent,
+            computed: style(&[(b"color", "red"), (b"font-size", "32px")]),
+        }
+        .apply(&mut world);
+        UpdateComputedStyle {
+            entity: child,
+            computed: style(&[(b"color", "initial"), (b"font-size", "initial")]),
+        }
+        .apply(&mut world);
+
+        // The child uses the defaults rather than the parent's text properties.
+        let section = &world.get::<Text>(text).unwrap().sections[0];
+        assert_eq!(section.style.color, TextStyle::default().color);
+        assert_eq!(section.style.font_size, 20.);
+
+        // Restyling the parent doesn't override the reset.
+        UpdateComputedStyle {
+            entity: parent,
+            computed: style(&[(b"color", "blue")]),
         }
+        .apply(&mut world);
+        let section = &world.get::<Text>(text).unwrap().sections[0];
+        assert_eq!(section.style.color, TextStyle::default().color);
     }
 }
@@ -0,0 +1,147 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use bevy::utils::HashMap;
+use lazy_static::lazy_static;
+
+use crate::guise::GuiseError;
+
+use super::ComputedStyle;
+
+/// The parsed value of a custom style property.
+pub type CustomValue = Arc<dyn Any + Send + Sync>;
+
+/// A style property defined outside of guise, such as the blur radius of a custom effect.
+/// Register it with `register_style_property` before loading the documents which use it.
+#[derive(Clone, Copy)]
+pub struct CustomProperty {
+    /// Attribute name, e.g. `blur`. Built-in properties take precedence over custom ones.
+    pub name: &'static str,
+
+    /// Parse an attribute value, returning `None` if it isn't valid.
+    pub parse: fn(&str) -> Option<CustomValue>,
+
+    /// Apply a parsed value to a computed style, usually with `CustomValues::set`.
+    pub apply: fn(&CustomValue, &mut ComputedStyle),
+}
+
+lazy_static! {
+    static ref CUSTOM_PROPERTIES: RwLock<HashMap<&'static str, CustomProperty>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Register a custom style property, so that styles parsed from then on can use it. Returns
+/// false, leaving the registry unchanged, if a property of the same name is already registered.
+pub fn register_style_property(property: CustomProperty) -> bool {
+    let mut properties = CUSTOM_PROPERTIES.write().unwrap();
+    if properties.contains_key(property.name) {
+        return false;
+    }
+    properties.insert(property.name, property);
+    true
+}
+
+/// The registered custom property with the given name.
+pub(crate) fn custom_property(name: &str) -> Option<CustomProperty> {
+    CUSTOM_PROPERTIES.read().unwrap().get(name).copied()
+}
+
+/// A custom property set by a style, along with the value as written.
+#[derive(Clone)]
+pub struct CustomAttr {
+    pub property: CustomProperty,
+    pub source: String,
+    pub value: CustomValue,
+}
+
+impl CustomAttr {
+    pub(crate) fn parse(property: CustomProperty, value: &str) -> Result<Self, GuiseError> {
+        match (property.parse)(value) {
+            Some(parsed) => Ok(Self {
+                property,
+                source: value.to_string(),
+                value: parsed,
+            }),
+            None => Err(GuiseError::InvalidAttributeValue(value.to_string())),
+        }
+    }
+}
+
+impl PartialEq for CustomAttr {
+    fn eq(&self, other: &Self) -> bool {
+        self.property.name == other.property.name && self.source == other.source
+    }
+}
+
+impl fmt::Debug for CustomAttr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}", self.property.name, self.source)
+    }
+}
+
+/// The values of custom properties in a computed style, by property name.
+#[derive(Default, Clone)]
+pub struct CustomValues(HashMap<&'static str, CustomValue>);
+
+impl CustomValues {
+    /// The value of the named property, if it is set and of type `T`.
+    pub fn get<T: Any>(&self, name: &str) -> Option<&T> {
+        self.0.get(name).and_then(|value| value.downcast_ref::<T>())
+    }
+
+    pub fn set(&mut self, name: &'static str, value: CustomValue) {
+        self.0.insert(name, value);
+    }
+}
+
+/// Values are compared by identity. Since a style attribute is parsed once, recomputing a
+/// style from the same attributes gives equal values.
+impl PartialEq for CustomValues {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().all(|(name, value)| {
+                other
+                    .0
+                    .get(name)
+                    .map_or(false, |other| Arc::ptr_eq(value, other))
+            })
+    }
+}
+
+impl fmt::Debug for CustomValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guise::style::StyleAttr;
+
+    #[test]
+    fn test_custom_property() {
+        let blur = CustomProperty {
+            name: "blur",
+            parse: |value| {
+                let radius = value.strip_suffix("px")?.parse::<f32>().ok()?;
+                Some(Arc::new(radius))
+            },
+            apply: |value, computed| computed.custom.set("blur", value.clone()),
+        };
+        assert!(register_style_property(blur));
+        assert!(!register_style_property(blur));
+
+        let attr = StyleAttr::parse(b"blur", "4px").unwrap().unwrap();
+        assert_eq!(attr.name(), "blur");
+        assert_eq!(attr.to_css_string(), "4px");
+        let mut computed = ComputedStyle::default();
+        attr.apply(&mut computed);
+        assert_eq!(computed.custom.get::<f32>("blur"), Some(&4.));
+        assert_eq!(computed.custom.get::<i32>("blur"), None);
+
+        assert!(StyleAttr::parse(b"blur", "4").is_err());
+        assert_eq!(StyleAttr::parse(b"unregistered", "4").unwrap(), None);
+    }
+}
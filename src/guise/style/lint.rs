@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use bevy::ui::Val;
+
+use super::val_math::{self, ValContext};
+use super::{ComputedStyle, StyleAttr};
+
+/// A likely mistake found in a style by `PartialStyle::lint`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintWarning {
+    /// A property is set more than once in the same style, so only the last value has any effect.
+    Duplicate(&'static str),
+
+    /// A size, padding, border or gap is negative.
+    NegativeLength(&'static str),
+
+    /// The minimum size is larger than the maximum size, so the maximum is ignored.
+    MinExceedsMax {
+        min: &'static str,
+        max: &'static str,
+    },
+
+    /// An animation refers to keyframes which weren't declared earlier in the file.
+    UnresolvedKeyframes(String),
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintWarning::Duplicate(name) => write!(f, "'{}' is set more than once", name),
+            LintWarning::NegativeLength(name) => write!(f, "'{}' is negative", name),
+            LintWarning::MinExceedsMax { min, max } => {
+                write!(f, "'{}' is greater than '{}'", min, max)
+            }
+            LintWarning::UnresolvedKeyframes(name) => {
+                write!(f, "animation refers to unknown keyframes '{}'", name)
+            }
+        }
+    }
+}
+
+/// Check a list of style attributes, appending any findings to `warnings`.
+pub(crate) fn lint_attrs(attrs: &[StyleAttr], warnings: &mut Vec<LintWarning>) {
+    let mut seen: Vec<&'static str> = Vec::with_capacity(attrs.len());
+    for attr in attrs {
+        let name = attr.name();
+        if seen.contains(&name) {
+            if !warnings.contains(&LintWarning::Duplicate(name)) {
+                warnings.push(LintWarning::Duplicate(name));
+            }
+        } else {
+            seen.push(name);
+        }
+
+        let negative = match attr {
+            StyleAttr::Width(val)
+            | StyleAttr::Height(val)
+            | StyleAttr::MinWidth(val)
+            | StyleAttr::MinHeight(val)
+            | StyleAttr::MaxWidth(val)
+            | StyleAttr::MaxHeight(val)
+            | StyleAttr::PaddingLeft(val)
+            | StyleAttr::PaddingRight(val)
+            | StyleAttr::PaddingTop(val)
+            | StyleAttr::PaddingBottom(val)
+            | StyleAttr::BorderLeft(val)
+            | StyleAttr::BorderRight(val)
+            | StyleAttr::BorderTop(val)
+            | StyleAttr::BorderBottom(val)
+            | StyleAttr::FlexBasis(val)
+            | StyleAttr::RowGap(val)
+            | StyleAttr::ColumnGap(val) => is_negative(*val),
+            StyleAttr::Padding(rect) | StyleAttr::Border(rect) => {
+                [rect.left, rect.right, rect.top, rect.bottom]
+                    .into_iter()
+                    .any(is_negative)
+            }
+            StyleAttr::Gap(row, column) => is_negative(*row) || is_negative(*column),
+            _ => false,
+        };
+        if negative {
+            warnings.push(LintWarning::NegativeLength(name));
+        }
+
+        if let StyleAttr::Animation(Some(spec)) = attr {
+            if spec.keyframes.is_none() {
+                warnings.push(LintWarning::UnresolvedKeyframes(spec.name.clone()));
+            }
+        }
+    }
+
+    let mut computed = ComputedStyle::default();
+    for attr in attrs {
+        attr.apply(&mut computed);
+    }
+    let style = &computed.style;
+    if exceeds(style.min_width, style.max_width) {
+        warnings.push(LintWarning::MinExceedsMax {
+            min: "min-width",
+            max: "max-width",
+        });
+    }
+    if exceeds(style.min_height, style.max_height) {
+        warnings.push(LintWarning::MinExceedsMax {
+            min: "min-height",
+            max: "max-height",
+        });
+    }
+}
+
+fn is_negative(val: Val) -> bool {
+    match val {
+        Val::Auto => false,
+        Val::Px(v) | Val::Percent(v) | Val::Vw(v) | Val::Vh(v) | Val::VMin(v) | Val::VMax(v) => {
+            v < 0.
+        }
+    }
+}
+
+/// Whether `min` is larger than `max`. Lengths in different units can't be compared without
+/// layout, so they never exceed each other.
+fn exceeds(min: Val, max: Val) -> bool {
+    val_math::cmp(min, max, &ValContext::default()) == Some(Ordering::Greater)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ui::UiRect;
+
+    use super::*;
+    use crate::guise::style::{AnimationSpec, PartialStyle};
+
+    #[test]
+    fn test_lint() {
+        let style = PartialStyle::from_attrs(&[
+            StyleAttr::Width(Val::Px(10.)),
+            StyleAttr::MinWidth(Val::Px(200.)),
+            StyleAttr::MaxWidth(Val::Px(100.)),
+            StyleAttr::MinHeight(Val::Percent(50.)),
+            StyleAttr::MaxHeight(Val::Px(10.)),
+            StyleAttr::Padding(UiRect::top(Val::Px(-1.))),
+            StyleAttr::MarginLeft(Val::Px(-4.)),
+            StyleAttr::Width(Val::Px(20.)),
+            StyleAttr::Animation(Some(AnimationSpec::parse("pulse 1s").unwrap())),
+        ]);
+        assert_eq!(
+            style.lint(),
+            vec![
+                LintWarning::NegativeLength("padding"),
+                LintWarning::Duplicate("width"),
+                LintWarning::UnresolvedKeyframes("pulse".to_string()),
+                LintWarning::MinExceedsMax {
+                    min: "min-width",
+                    max: "max-width",
+                },
+            ]
+        );
+        assert!(PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(10.))])
+            .lint()
+            .is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::{prelude::*, text::TextLayoutInfo, window::PrimaryWindow};
+
+/// Sizes a node to its text, for the axes whose `width` or `height` style attribute is
+/// `max-content`.
+///
+/// Bevy 0.11 has no intrinsic sizing keywords, so instead the node's text children are measured
+/// after layout, and the result is written to the node's `Style` in pixels. The new size takes
+/// effect at the next layout, a frame later. Only text is measured: the node is as wide as its
+/// widest text child and as tall as its text children stacked, plus its padding and border.
+/// Text which had to wrap is measured as wrapped.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxContent(pub BVec2);
+
+/// Convert a length to pixels. Only pixel lengths can be resolved here; other units are treated
+/// as zero.
+fn resolve_px(val: Val) -> f32 {
+    match val {
+        Val::Px(px) => px,
+        _ => 0.,
+    }
+}
+
+/// True if `val` is within half a pixel of `px`. Sizes which are already close enough are left
+/// alone, so that text measurements which jitter between frames don't trigger another layout.
+fn is_near(val: Val, px: f32) -> bool {
+    matches!(val, Val::Px(current) if (current - px).abs() < 0.5)
+}
+
+/// Measures the text children of `MaxContent` nodes and sets the node sizes to fit. Runs after
+/// layout, since that is when the text is measured.
+pub fn update_max_content(
+    mut query: Query<(&MaxContent, &Children, &mut Style)>,
+    texts: Query<&TextLayoutInfo>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_scale: Res<UiScale>,
+) {
+    // Text layouts are measured in physical pixels.
+    let scale_factor = windows
+        .get_single()
+        .map(|window| window.resolution.scale_factor())
+        .unwrap_or(1.)
+        * ui_scale.scale;
+    for (max_content, children, mut style) in query.iter_mut() {
+        let mut content = Vec2::ZERO;
+        for layout in texts.iter_many(children) {
+            let size = layout.size / scale_factor as f32;
+            content.x = content.x.max(size.x);
+            content.y += size.y;
+        }
+
+        let size = content
+            + Vec2::new(
+                [
+                    style.padding.left,
+                    style.padding.right,
+                    style.border.left,
+                    style.border.right,
+                ]
+                .into_iter()
+                .map(resolve_px)
+                .sum(),
+                [
+                    style.padding.top,
+                    style.padding.bottom,
+                    style.border.top,
+                    style.border.bottom,
+                ]
+                .into_iter()
+                .map(resolve_px)
+                .sum(),
+            );
+        if max_content.0.x && !is_near(style.width, size.x) {
+            style.width = Val::Px(size.x);
+        }
+        if max_content.0.y && !is_near(style.height, size.y) {
+            style.height = Val::Px(size.y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_content_width() {
+        let mut world = World::new();
+        world.insert_resource(UiScale { scale: 2. });
+        let text = world
+            .spawn(TextLayoutInfo {
+                glyphs: Vec::new(),
+                size: Vec2::new(120.4, 36.),
+            })
+            .id();
+        let node = world
+            .spawn((
+                MaxContent(BVec2::new(true, false)),
+                Style {
+                    padding: UiRect::horizontal(Val::Px(4.)),
+                    ..default()
+                },
+            ))
+            .push_children(&[text])
+            .id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_max_content);
+        schedule.run(&mut world);
+
+        let style = world.get::<Style>(node).unwrap();
+        let Val::Px(width) = style.width else {
+            panic!("width should be set in pixels: {:?}", style.width);
+        };
+        assert!((width - 68.2).abs() < 0.5, "width {width}");
+        assert_eq!(style.height, Val::Auto);
+    }
+}
@@ -1,7 +1,20 @@
+mod animation;
 mod attr;
 mod computed;
+mod custom;
+mod lint;
+mod max_content;
 mod partial;
+mod raw;
+mod transform;
+pub(crate) mod val_math;
 
+pub use animation::*;
 pub use attr::*;
 pub use computed::*;
+pub use custom::*;
+pub use lint::LintWarning;
+pub use max_content::*;
 pub use partial::*;
+pub use raw::RawStyle;
+pub use transform::*;
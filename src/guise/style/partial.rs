@@ -1,8 +1,11 @@
+use std::fmt;
+
 use crate::guise::GuiseError;
 use lazy_static::lazy_static;
 use regex::Regex;
 
 use super::attr::StyleAttr;
+use super::lint::{lint_attrs, LintWarning};
 use super::ComputedStyle;
 use bevy::reflect::{TypePath, TypeUuid};
 use quick_xml::writer::Writer;
@@ -28,37 +31,243 @@ pub enum Selector {
 
     /// Negation of a selector
     Negation(Box<Selector>),
+
+    /// Matches elements whose one-based position among their siblings is `a * n + b`, for some
+    /// `n >= 0`.
+    NthChild(i32, i32),
+
+    /// Like `NthChild`, but counting from the last sibling.
+    NthLastChild(i32, i32),
+
+    /// Matches while the width of the primary window, in logical pixels, is between the two
+    /// values, inclusive. This is what a `<media>` style in a document becomes.
+    WindowWidth(f32, f32),
     // TODO:
     // Child
     // Descendant
 }
 
+/// The state of an element that selectors are tested against.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SelectorTarget<'a> {
+    /// The element's current class names.
+    pub classes: &'a [&'a str],
+
+    /// Zero-based index of the element among its parent's element children.
+    pub index: usize,
+
+    /// Number of element children of the element's parent, including the element itself.
+    pub sibling_count: usize,
+
+    /// Width of the primary window in logical pixels, if there is one.
+    pub window_width: Option<f32>,
+}
+
 impl Selector {
     /// Parse a selector expression
     pub fn parse(input: &str) -> Result<Selector, GuiseError> {
-        // TODO: Do a real parser later. For now, just support `self.<classname>`.
-        lazy_static! {
-            static ref RE_SEL: Regex = Regex::new(r"^self\.(\w+)$").unwrap();
+        // TODO: Do a real parser later. For now, just support `self` followed by any number of
+        // `.<classname>`, structural pseudo-class and `:not()` terms.
+        let invalid = || GuiseError::InvalidAttributeValue(input.to_string());
+        let rest = input.trim().strip_prefix("self").ok_or_else(invalid)?;
+        let mut terms = parse_terms(rest, true).ok_or_else(invalid)?;
+        match terms.len() {
+            0 => Err(invalid()),
+            1 => Ok(terms.pop().unwrap()),
+            _ => Ok(Selector::Conjunction(terms.into_boxed_slice())),
         }
+    }
 
-        RE_SEL
-            .captures(input)
-            .and_then(|cap| Some(Selector::ClassName(cap[1].to_string())))
-            .ok_or(GuiseError::InvalidAttributeValue(input.to_string()))
+    /// True if the selector refers to the class `name`, whether or not it requires it.
+    pub fn uses_class(&self, name: &str) -> bool {
+        match self {
+            Selector::ClassName(cls) => cls == name,
+            Selector::Conjunction(terms) => terms.iter().any(|t| t.uses_class(name)),
+            Selector::Negation(expr) => expr.uses_class(name),
+            Selector::Always
+            | Selector::NthChild(..)
+            | Selector::NthLastChild(..)
+            | Selector::WindowWidth(..) => false,
+        }
     }
 
-    /// Tests whether the selector expression matches the array of input names.
-    pub fn test(&self, input: &[&str]) -> bool {
-        Selector::test_expr(self, input)
+    /// True if the selector would match differently at the two window widths.
+    pub fn window_width_changes(&self, from: Option<f32>, to: Option<f32>) -> bool {
+        match self {
+            Selector::WindowWidth(min, max) => {
+                let matches = |width: Option<f32>| width.map_or(false, |w| w >= *min && w <= *max);
+                matches(from) != matches(to)
+            }
+            Selector::Conjunction(terms) => terms.iter().any(|t| t.window_width_changes(from, to)),
+            Selector::Negation(expr) => expr.window_width_changes(from, to),
+            Selector::Always
+            | Selector::ClassName(_)
+            | Selector::NthChild(..)
+            | Selector::NthLastChild(..) => false,
+        }
+    }
+
+    /// The specificity of the selector, which decides the order that matching selectors are
+    /// applied in. Selectors can only refer to the element itself, so there are no id or type
+    /// terms; the score is the number of class and pseudo-class terms. As in CSS, `:not()`
+    /// scores the term it contains, and `:only-child` counts as two terms, since it is the
+    /// same as `:first-child:last-child`.
+    pub fn specificity(&self) -> usize {
+        match self {
+            Selector::Always => 0,
+            Selector::ClassName(_)
+            | Selector::NthChild(..)
+            | Selector::NthLastChild(..)
+            | Selector::WindowWidth(..) => 1,
+            Selector::Conjunction(terms) => terms.iter().map(Selector::specificity).sum(),
+            Selector::Negation(expr) => expr.specificity(),
+        }
     }
 
-    fn test_expr(expr: &Self, input: &[&str]) -> bool {
+    /// Tests whether the selector expression matches the given element state.
+    pub fn test(&self, target: &SelectorTarget) -> bool {
+        Selector::test_expr(self, target)
+    }
+
+    fn test_expr(expr: &Self, target: &SelectorTarget) -> bool {
         match expr {
             Selector::Always => true,
-            Selector::ClassName(cls) => input.iter().any(|n| *n == cls),
-            Selector::Conjunction(terms) => terms.iter().all(|t| Selector::test_expr(t, input)),
-            Selector::Negation(expr) => !Selector::test_expr(expr, input),
+            Selector::ClassName(cls) => target.classes.iter().any(|n| *n == cls),
+            Selector::Conjunction(terms) => terms.iter().all(|t| Selector::test_expr(t, target)),
+            Selector::Negation(expr) => !Selector::test_expr(expr, target),
+            Selector::NthChild(a, b) => nth_matches(*a, *b, target.index),
+            Selector::NthLastChild(a, b) => {
+                // An element outside of its parent's count can't be matched from the end.
+                match target.sibling_count.checked_sub(target.index + 1) {
+                    Some(index) => nth_matches(*a, *b, index),
+                    None => false,
+                }
+            }
+            Selector::WindowWidth(min, max) => target
+                .window_width
+                .map_or(false, |width| width >= *min && width <= *max),
+        }
+    }
+}
+
+/// Writes the selector in the form that `Selector::parse` reads, e.g. `self.selected:not(:hover)`.
+/// Window widths come from `<media>` elements rather than expressions, so they are left out.
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "self")?;
+        self.fmt_terms(f)
+    }
+}
+
+impl Selector {
+    fn fmt_terms(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selector::Always | Selector::WindowWidth(..) => Ok(()),
+            Selector::ClassName(cls) => write!(f, ".{}", cls),
+            Selector::Conjunction(terms) => terms.iter().try_for_each(|term| term.fmt_terms(f)),
+            Selector::Negation(expr) => {
+                write!(f, ":not(")?;
+                expr.fmt_terms(f)?;
+                write!(f, ")")
+            }
+            Selector::NthChild(a, b) => write!(f, ":nth-child({})", nth_to_str(*a, *b)),
+            Selector::NthLastChild(a, b) => write!(f, ":nth-last-child({})", nth_to_str(*a, *b)),
+        }
+    }
+}
+
+/// Parse a sequence of class and pseudo-class terms. As in CSS, `:not()` takes a single term,
+/// and can't be nested, so it is only accepted when `negatable` is true.
+fn parse_terms(mut rest: &str, negatable: bool) -> Option<Vec<Selector>> {
+    lazy_static! {
+        // The argument may contain one level of parentheses, for `:not(:nth-child(2n))`.
+        static ref RE_TERM: Regex =
+            Regex::new(r"^(?:\.(\w+)|:([\w-]+)(?:\(\s*((?:[^()]|\([^()]*\))*?)\s*\))?)")
+                .unwrap();
+    }
+
+    let mut terms: Vec<Selector> = Vec::new();
+    while !rest.is_empty() {
+        let cap = RE_TERM.captures(rest)?;
+        if let Some(cls) = cap.get(1) {
+            terms.push(Selector::ClassName(cls.as_str().to_string()));
+        } else {
+            let arg = cap.get(3).map(|arg| arg.as_str());
+            match (&cap[2], arg) {
+                ("nth-child", Some(arg)) => {
+                    let (a, b) = parse_nth(arg)?;
+                    terms.push(Selector::NthChild(a, b));
+                }
+                ("nth-last-child", Some(arg)) => {
+                    let (a, b) = parse_nth(arg)?;
+                    terms.push(Selector::NthLastChild(a, b));
+                }
+                // The dynamic pseudo-classes are the same as the classes of the same name.
+                ("hover" | "active" | "focus", None) => {
+                    terms.push(Selector::ClassName(cap[2].to_string()))
+                }
+                ("first-child", None) => terms.push(Selector::NthChild(0, 1)),
+                ("last-child", None) => terms.push(Selector::NthLastChild(0, 1)),
+                ("only-child", None) => terms.push(Selector::Conjunction(Box::new([
+                    Selector::NthChild(0, 1),
+                    Selector::NthLastChild(0, 1),
+                ]))),
+                ("not", Some(arg)) if negatable => {
+                    let mut inner = parse_terms(arg, false)?;
+                    if inner.len() != 1 {
+                        return None;
+                    }
+                    terms.push(Selector::Negation(Box::new(inner.pop().unwrap())));
+                }
+                _ => return None,
+            }
         }
+        rest = &rest[cap[0].len()..];
+    }
+    Some(terms)
+}
+
+/// Whether the zero-based `index` is at a one-based position of `a * n + b`, for some `n >= 0`.
+fn nth_matches(a: i32, b: i32, index: usize) -> bool {
+    let offset = index as i32 + 1 - b;
+    match a {
+        0 => offset == 0,
+        _ => offset % a == 0 && offset / a >= 0,
+    }
+}
+
+/// Parse the argument of `:nth-child`: `even`, `odd`, or a formula of the form `An+B`, where
+/// either part may be omitted. Returns the pair `(A, B)`.
+fn parse_nth(arg: &str) -> Option<(i32, i32)> {
+    let arg: String = arg.chars().filter(|c| !c.is_whitespace()).collect();
+    match arg.as_str() {
+        "even" => return Some((2, 0)),
+        "odd" => return Some((2, 1)),
+        _ => {}
+    }
+
+    let Some((a, b)) = arg.split_once('n') else {
+        return arg.parse().ok().map(|b| (0, b));
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        _ => a.parse().ok()?,
+    };
+    let b = match b {
+        "" => 0,
+        _ if b.starts_with('+') || b.starts_with('-') => b.trim_start_matches('+').parse().ok()?,
+        _ => return None,
+    };
+    Some((a, b))
+}
+
+/// Format the argument of `:nth-child`, the reverse of `parse_nth`.
+fn nth_to_str(a: i32, b: i32) -> String {
+    match (a, b) {
+        (0, b) => b.to_string(),
+        (a, 0) => format!("{}n", a),
+        (a, b) => format!("{}n{:+}", a, b),
     }
 }
 
@@ -96,18 +305,237 @@ impl PartialStyle {
         }
     }
 
-    /// Construct a new `PartialStyle` from a list of `StyleAttr`s.
+    /// Construct a new `PartialStyle` from a list of `StyleAttr`s. Attributes are applied in the
+    /// order given, so a shorthand such as `margin` overrides the longhands before it. Raw
+    /// styles are moved to the front, so that the typed attributes override them.
     pub fn from_attrs(attrs: &[StyleAttr]) -> Self {
+        let (mut attrs, typed): (Vec<StyleAttr>, Vec<StyleAttr>) = attrs
+            .iter()
+            .cloned()
+            .partition(|attr| matches!(attr, StyleAttr::RawStyle(_)));
+        attrs.extend(typed);
         Self {
-            attrs: Vec::from(attrs),
+            attrs,
             selectors: Vec::new(),
         }
     }
 
+    /// Construct a style which reproduces a computed style: applying it to a default
+    /// `ComputedStyle` gives back `computed`. Only properties which differ from their default
+    /// values are included, so properties which were explicitly set to their defaults are left
+    /// out. Side-specific margins, padding and borders are combined into the shorthand. Custom
+    /// properties can't be recovered from their values, so they are not included.
+    pub fn from_computed(computed: &ComputedStyle) -> Self {
+        let default = ComputedStyle::default();
+        let mut attrs = Vec::new();
+
+        if computed.background_color.is_some() {
+            attrs.push(StyleAttr::BackgroundColor(computed.background_color));
+        }
+        if computed.background_image.is_some() {
+            attrs.push(StyleAttr::BackgroundImage(
+                computed.background_image.clone(),
+            ));
+        }
+        if computed.border_color.is_some() {
+            attrs.push(StyleAttr::BorderColor(computed.border_color));
+        }
+        if let Some(color) = computed.color {
+            attrs.push(StyleAttr::Color(color));
+        }
+        if computed.z_index.is_some() {
+            attrs.push(StyleAttr::ZIndex(computed.z_index));
+        }
+        if let Some(opacity) = computed.opacity {
+            attrs.push(StyleAttr::Opacity(opacity));
+        }
+        if computed.content.is_some() {
+            attrs.push(StyleAttr::Content(computed.content.clone()));
+        }
+        if computed.animation.is_some() {
+            attrs.push(StyleAttr::Animation(computed.animation.clone()));
+        }
+        if computed.white_space != default.white_space {
+            attrs.push(StyleAttr::WhiteSpace(computed.white_space));
+        }
+        if computed.line_clamp.is_some() {
+            attrs.push(StyleAttr::LineClamp(computed.line_clamp));
+        }
+        if computed.text_align != default.text_align {
+            attrs.push(StyleAttr::TextAlign(computed.text_align));
+        }
+        if let Some(font) = &computed.font {
+            attrs.push(StyleAttr::Font(font.clone()));
+        }
+        if let Some(size) = computed.font_size {
+            attrs.push(StyleAttr::FontSize(size));
+        }
+        if let Some(indent) = computed.text_indent {
+            attrs.push(StyleAttr::TextIndent(indent));
+        }
+        if computed.text_shadow.is_some() {
+            attrs.push(StyleAttr::TextShadow(computed.text_shadow));
+        }
+        if computed.debug_outline.is_some() {
+            attrs.push(StyleAttr::DebugOutline(computed.debug_outline));
+        }
+
+        let (transform, base) = (&computed.transform, &default.transform);
+        if transform.rotate != base.rotate {
+            attrs.push(StyleAttr::Rotate(transform.rotate));
+        }
+        if transform.scale != base.scale {
+            attrs.push(StyleAttr::Scale(transform.scale.x, transform.scale.y));
+        }
+        if transform.translate != base.translate {
+            attrs.push(StyleAttr::Translate(
+                transform.translate.0,
+                transform.translate.1,
+            ));
+        }
+
+        let (style, base) = (&computed.style, &default.style);
+        if style.overflow != base.overflow {
+            if style.overflow.x == style.overflow.y {
+                attrs.push(StyleAttr::Overflow(style.overflow.x));
+            } else {
+                attrs.push(StyleAttr::OverflowX(style.overflow.x));
+                attrs.push(StyleAttr::OverflowY(style.overflow.y));
+            }
+        }
+
+        macro_rules! style_attrs {
+            ($($field:ident => $attr:ident),* $(,)?) => {
+                $(
+                    if style.$field != base.$field {
+                        attrs.push(StyleAttr::$attr(style.$field));
+                    }
+                )*
+            };
+        }
+
+        style_attrs!(
+            display => Display,
+            position_type => Position,
+            direction => Direction,
+            left => Left,
+            right => Right,
+            top => Top,
+            bottom => Bottom,
+            width => Width,
+            height => Height,
+            min_width => MinWidth,
+            min_height => MinHeight,
+            max_width => MaxWidth,
+            max_height => MaxHeight,
+            aspect_ratio => AspectRatio,
+            align_items => AlignItems,
+            justify_items => JustifyItems,
+            align_self => AlignSelf,
+            justify_self => JustifySelf,
+            align_content => AlignContent,
+            justify_content => JustifyContent,
+            margin => Margin,
+            padding => Padding,
+            border => Border,
+            flex_direction => FlexDirection,
+            flex_wrap => FlexWrap,
+            flex_grow => FlexGrow,
+            flex_shrink => FlexShrink,
+            flex_basis => FlexBasis,
+            row_gap => RowGap,
+            column_gap => ColumnGap,
+            grid_auto_flow => GridAutoFlow,
+            grid_row => GridRow,
+            grid_column => GridColumn,
+        );
+
+        // After the sizes, which would turn them off again.
+        if computed.max_content.x {
+            attrs.push(StyleAttr::MaxContentWidth);
+        }
+        if computed.max_content.y {
+            attrs.push(StyleAttr::MaxContentHeight);
+        }
+
+        if let Some(radii) = computed.border_radius {
+            attrs.push(StyleAttr::BorderRadius(radii));
+        }
+        if let Some(margin) = computed.overflow_clip_margin {
+            attrs.push(StyleAttr::OverflowClipMargin(margin));
+        }
+        if computed.border_style != default.border_style {
+            attrs.push(StyleAttr::BorderStyle(computed.border_style));
+        }
+        if computed.box_sizing != default.box_sizing {
+            attrs.push(StyleAttr::BoxSizing(computed.box_sizing));
+        }
+
+        Self {
+            attrs,
+            selectors: Vec::new(),
+        }
+    }
+
+    /// Create a new style which has the effect of applying `self` followed by `other`. The
+    /// attribute lists are concatenated rather than deduplicated, so where both styles set the
+    /// same property, the merged style contains both and the value from `other` wins when
+    /// applied. The same goes for selectors.
+    pub fn merged_with(&self, other: &PartialStyle) -> PartialStyle {
+        let mut attrs = Vec::with_capacity(self.attrs.len() + other.attrs.len());
+        attrs.extend_from_slice(&self.attrs);
+        attrs.extend_from_slice(&other.attrs);
+        let mut selectors = Vec::with_capacity(self.selectors.len() + other.selectors.len());
+        selectors.extend_from_slice(&self.selectors);
+        selectors.extend_from_slice(&other.selectors);
+        PartialStyle { attrs, selectors }
+    }
+
+    /// The list of style attributes, not including those behind selectors.
+    pub fn attrs(&self) -> &[StyleAttr] {
+        &self.attrs
+    }
+
+    /// The names of the properties set by this style, in the order they are applied. Properties
+    /// behind selectors are not included.
+    pub fn property_names(&self) -> Vec<&'static str> {
+        self.attrs.iter().map(StyleAttr::name).collect()
+    }
+
+    /// The styles behind selectors, with their selectors, in the order they were added.
+    pub fn selectors(&self) -> &[(Selector, PartialStyle)] {
+        &self.selectors
+    }
+
     pub fn add_selector(&mut self, selector: Selector, style: PartialStyle) {
         self.selectors.push((selector, style));
     }
 
+    /// True if any of the style's selectors refer to the class `name`.
+    pub fn uses_class(&self, name: &str) -> bool {
+        self.selectors
+            .iter()
+            .any(|(selector, style)| selector.uses_class(name) || style.uses_class(name))
+    }
+
+    /// True if any of the style's selectors would match differently at the two window widths.
+    pub fn window_width_changes(&self, from: Option<f32>, to: Option<f32>) -> bool {
+        self.selectors.iter().any(|(selector, style)| {
+            selector.window_width_changes(from, to) || style.window_width_changes(from, to)
+        })
+    }
+
+    /// Check the style for likely mistakes, such as negative sizes or a minimum width which is
+    /// larger than the maximum. Styles behind selectors are checked separately.
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        lint_attrs(&self.attrs, &mut warnings);
+        for (_, style) in self.selectors.iter() {
+            warnings.extend(style.lint());
+        }
+        warnings
+    }
+
     /// True if there are no styles defined.
     pub fn is_empty(&self) -> bool {
         return self.attrs.is_empty();
@@ -120,13 +548,20 @@ impl PartialStyle {
         }
     }
 
-    /// Merge the style properties into a computed `Style` object.
-    pub fn apply_selected_to(&self, computed: &mut ComputedStyle, class_names: &[&str]) {
-        for (selector, ss) in self.selectors.iter() {
-            if selector.test(class_names) {
-                for attr in ss.attrs.iter() {
-                    attr.apply(computed);
-                }
+    /// Merge the properties of the selectors which match `target` into a computed `Style`
+    /// object. Matching selectors are applied in order of increasing specificity, so that the
+    /// most specific wins; selectors of equal specificity are applied in source order.
+    pub fn apply_selected_to(&self, computed: &mut ComputedStyle, target: &SelectorTarget) {
+        let mut matched: Vec<_> = self
+            .selectors
+            .iter()
+            .filter(|(selector, _)| selector.test(target))
+            .collect();
+        // A stable sort, to keep source order among equals.
+        matched.sort_by_key(|(selector, _)| selector.specificity());
+        for (_, ss) in matched {
+            for attr in ss.attrs.iter() {
+                attr.apply(computed);
             }
         }
     }
@@ -163,7 +598,11 @@ impl PartialEq<PartialStyle> for PartialStyle {
 
 #[cfg(test)]
 mod tests {
+    use bevy::prelude::{Color, Val};
+    use bevy::ui::{GridTrackRepetition, RepeatedGridTrack};
+
     use super::*;
+    use crate::guise::style::StyleField;
 
     #[test]
     fn test_serialize_empty() {
@@ -186,4 +625,257 @@ mod tests {
             r#"<style display="flex"/>"#
         );
     }
+
+    #[test]
+    fn test_property_names() {
+        let style = PartialStyle::from_attrs(&[
+            StyleAttr::Display(bevy::ui::Display::Flex),
+            StyleAttr::Width(bevy::ui::Val::Px(10.)),
+            StyleAttr::Gap(bevy::ui::Val::Px(2.), bevy::ui::Val::Px(2.)),
+        ]);
+        assert_eq!(style.property_names(), vec!["display", "width", "gap"]);
+    }
+
+    #[test]
+    fn test_merged_with() {
+        let base = PartialStyle::from_attrs(&[
+            StyleAttr::Width(Val::Px(10.)),
+            StyleAttr::BackgroundColor(Some(Color::RED)),
+        ]);
+        let over = PartialStyle::from_attrs(&[
+            StyleAttr::Width(Val::Px(20.)),
+            StyleAttr::Height(Val::Px(5.)),
+        ]);
+        let merged = base.merged_with(&over);
+        assert_eq!(
+            merged.attrs(),
+            &[
+                StyleAttr::Width(Val::Px(10.)),
+                StyleAttr::BackgroundColor(Some(Color::RED)),
+                StyleAttr::Width(Val::Px(20.)),
+                StyleAttr::Height(Val::Px(5.)),
+            ]
+        );
+
+        let mut computed = ComputedStyle::default();
+        merged.apply_to(&mut computed);
+        assert_eq!(computed.style.width, Val::Px(20.));
+        assert_eq!(computed.style.height, Val::Px(5.));
+        assert_eq!(computed.background_color, Some(Color::RED));
+    }
+
+    #[test]
+    fn test_from_computed() {
+        let style = PartialStyle::from_attrs(&[
+            StyleAttr::BackgroundColor(Some(Color::RED)),
+            StyleAttr::Color(Color::BLUE),
+            StyleAttr::Opacity(0.5),
+            StyleAttr::Display(bevy::ui::Display::Grid),
+            StyleAttr::Width(Val::Px(10.)),
+            StyleAttr::MarginLeft(Val::Px(4.)),
+            StyleAttr::OverflowX(bevy::ui::OverflowAxis::Clip),
+            StyleAttr::Flex(2., 0.5, Val::Percent(50.)),
+            StyleAttr::GridRowSpan(3),
+            StyleAttr::Rotate(1.),
+            StyleAttr::Height(Val::Px(20.)),
+            StyleAttr::MaxContentHeight,
+        ]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+
+        let extracted = PartialStyle::from_computed(&computed);
+        let mut round_trip = ComputedStyle::default();
+        extracted.apply_to(&mut round_trip);
+        assert_eq!(round_trip, computed);
+        assert!(extracted
+            .attrs()
+            .contains(&StyleAttr::Margin(bevy::ui::UiRect::left(Val::Px(4.)))));
+        assert!(extracted.attrs().contains(&StyleAttr::MaxContentHeight));
+
+        // Properties at their default values are left out.
+        assert!(PartialStyle::from_computed(&ComputedStyle::default()).is_empty());
+        let mut computed = ComputedStyle::default();
+        PartialStyle::from_attrs(&[StyleAttr::Width(Val::Auto)]).apply_to(&mut computed);
+        assert!(PartialStyle::from_computed(&computed).is_empty());
+    }
+
+    fn matching_indices_of(selector: &str, sibling_count: usize) -> Vec<usize> {
+        let selector = Selector::parse(selector).unwrap();
+        (0..sibling_count)
+            .filter(|&index| {
+                selector.test(&SelectorTarget {
+                    classes: &[],
+                    index,
+                    sibling_count,
+                })
+            })
+            .collect()
+    }
+
+    fn matching_indices(selector: &str) -> Vec<usize> {
+        matching_indices_of(selector, 6)
+    }
+
+    #[test]
+    fn test_nth_child() {
+        assert_eq!(matching_indices("self:nth-child(even)"), vec![1, 3, 5]);
+        assert_eq!(matching_indices("self:nth-child(odd)"), vec![0, 2, 4]);
+        assert_eq!(matching_indices("self:nth-child(2n+1)"), vec![0, 2, 4]);
+        assert_eq!(matching_indices("self:nth-child(3n)"), vec![2, 5]);
+        assert_eq!(matching_indices("self:nth-child(-n+2)"), vec![0, 1]);
+        assert_eq!(matching_indices("self:nth-child(4)"), vec![3]);
+        assert!(Selector::parse("self:nth-child(2x)").is_err());
+        assert!(Selector::parse("self:first-letter(1)").is_err());
+        assert!(Selector::parse("self:nth-child").is_err());
+    }
+
+    #[test]
+    fn test_structural_selectors() {
+        assert_eq!(matching_indices("self:first-child"), vec![0]);
+        assert_eq!(matching_indices("self:last-child"), vec![5]);
+        assert_eq!(matching_indices("self:nth-last-child(2)"), vec![4]);
+        assert_eq!(matching_indices("self:only-child"), Vec::<usize>::new());
+        assert_eq!(matching_indices_of("self:only-child", 1), vec![0]);
+        assert_eq!(matching_indices_of("self:last-child", 1), vec![0]);
+        assert!(Selector::parse("self:first-child(1)").is_err());
+    }
+
+    #[test]
+    fn test_nth_child_with_class() {
+        let selector = Selector::parse("self.hover:nth-child(odd)").unwrap();
+        let target = |classes: &'static [&'static str], index| SelectorTarget {
+            classes,
+            index,
+            sibling_count: 6,
+        };
+        assert!(selector.test(&target(&["hover"], 0)));
+        assert!(!selector.test(&target(&["hover"], 1)));
+        assert!(!selector.test(&target(&[], 0)));
+    }
+
+    #[test]
+    fn test_raw_style() {
+        let raw = StyleAttr::parse(
+            b"bevy-style",
+            r#"{ "aspect_ratio": 1.5, "width": { "Px": 10.0 } }"#,
+        )
+        .unwrap()
+        .unwrap();
+        let style = PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(20.)), raw]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+        assert_eq!(computed.style.aspect_ratio, Some(1.5));
+        // The typed attribute wins, even though it comes first.
+        assert_eq!(computed.style.width, Val::Px(20.));
+        assert!(computed.style_fields.contains(StyleField::AspectRatio));
+
+        assert!(StyleAttr::parse(b"bevy-style", r#"{ "aspect": 1.5 }"#).is_err());
+        assert!(StyleAttr::parse(b"bevy-style", "aspect_ratio: 1.5").is_err());
+    }
+
+    #[test]
+    fn test_raw_style_grid_tracks() {
+        let raw = StyleAttr::parse(
+            b"bevy-style",
+            r#"{ "grid_template_columns": [{
+                "repetition": { "Count": 2 },
+                "tracks": [{ "min_sizing_function": { "Px": 100.0 }, "max_sizing_function": { "Px": 100.0 } }]
+            }] }"#,
+        )
+        .unwrap()
+        .unwrap();
+        let style = PartialStyle::from_attrs(&[raw]);
+        let mut computed = ComputedStyle::default();
+        style.apply_to(&mut computed);
+        let expected: Vec<RepeatedGridTrack> =
+            vec![RepeatedGridTrack::px(GridTrackRepetition::Count(2), 100.)];
+        assert_eq!(computed.style.grid_template_columns, expected);
+        assert!(computed
+            .style_fields
+            .contains(StyleField::GridTemplateColumns));
+    }
+
+    #[test]
+    fn test_specificity() {
+        let specificity = |input| Selector::parse(input).unwrap().specificity();
+        assert_eq!(specificity("self.hover"), 1);
+        assert_eq!(specificity("self.hover.active"), 2);
+        assert_eq!(specificity("self:nth-child(2n):not(.active)"), 2);
+        assert_eq!(specificity("self:only-child"), 2);
+
+        // The more specific selector wins, even though it comes first.
+        let mut style = PartialStyle::new();
+        style.add_selector(
+            Selector::parse("self.hover.active").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(20.))]),
+        );
+        style.add_selector(
+            Selector::parse("self.hover").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(10.))]),
+        );
+        let mut computed = ComputedStyle::default();
+        let target = SelectorTarget {
+            classes: &["hover", "active"],
+            ..Default::default()
+        };
+        style.apply_selected_to(&mut computed, &target);
+        assert_eq!(computed.style.width, Val::Px(20.));
+
+        // Among equals, the last one wins.
+        style.add_selector(
+            Selector::parse("self.active.hover").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(30.))]),
+        );
+        style.apply_selected_to(&mut computed, &target);
+        assert_eq!(computed.style.width, Val::Px(30.));
+    }
+
+    #[test]
+    fn test_not() {
+        let selector = Selector::parse("self:not(.active)").unwrap();
+        let target = |classes: &'static [&'static str]| SelectorTarget {
+            classes,
+            ..Default::default()
+        };
+        assert!(selector.test(&target(&[])));
+        assert!(selector.test(&target(&["hover"])));
+        assert!(!selector.test(&target(&["active"])));
+        assert!(!selector.test(&target(&["hover", "active"])));
+
+        // Negation combines with other terms, and can contain a pseudo-class.
+        let selector = Selector::parse("self.hover:not(.active)").unwrap();
+        assert!(selector.test(&target(&["hover"])));
+        assert!(!selector.test(&target(&["hover", "active"])));
+        assert_eq!(matching_indices("self:not(:nth-child(2n))"), vec![0, 2, 4]);
+
+        // Dynamic pseudo-classes are the same as classes.
+        let selector = Selector::parse("self:focus:not(:hover)").unwrap();
+        assert!(selector.test(&target(&["focus"])));
+        assert!(!selector.test(&target(&["focus", "hover"])));
+        assert!(Selector::parse("self:focus(1)").is_err());
+
+        // As in CSS, `:not()` takes a single term and can't be nested.
+        assert!(Selector::parse("self:not(:not(.active))").is_err());
+        assert!(Selector::parse("self:not(.a.b)").is_err());
+        assert!(Selector::parse("self:not()").is_err());
+    }
+
+    #[test]
+    fn test_selector_to_string() {
+        for input in [
+            "self.hover",
+            "self.hover.active",
+            "self:not(.active)",
+            "self:nth-child(2n+1)",
+            "self:nth-child(-1n+2)",
+            "self:nth-last-child(3)",
+            "self.focus:not(:nth-child(2n))",
+        ] {
+            assert_eq!(Selector::parse(input).unwrap().to_string(), input);
+        }
+        // Other spellings come out in a canonical form.
+        let canonical = |input| Selector::parse(input).unwrap().to_string();
+        assert_eq!(canonical("self:nth-child(odd)"), "self:nth-child(2n+1)");
+        assert_eq!(canonical("self:first-child"), "self:nth-child(1)");
+    }
 }
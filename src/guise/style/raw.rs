@@ -0,0 +1,109 @@
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::reflect::{serde::TypedReflectDeserializer, Reflect, ReflectRef, TypeRegistry};
+use bevy::ui::*;
+use lazy_static::lazy_static;
+use serde::de::DeserializeSeed;
+
+use crate::guise::GuiseError;
+
+use super::{ComputedStyle, StyleField, StyleFields};
+
+lazy_static! {
+    /// The types needed to deserialize a `Style`. These are the types that `UiPlugin` registers,
+    /// plus the containers of the grid track fields, so the loader doesn't need access to the
+    /// app's registry.
+    static ref STYLE_REGISTRY: TypeRegistry = {
+        let mut registry = TypeRegistry::default();
+        registry.register::<Style>();
+        registry.register::<Display>();
+        registry.register::<PositionType>();
+        registry.register::<Overflow>();
+        registry.register::<OverflowAxis>();
+        registry.register::<Direction>();
+        registry.register::<Val>();
+        registry.register::<UiRect>();
+        registry.register::<Option<f32>>();
+        registry.register::<AlignItems>();
+        registry.register::<JustifyItems>();
+        registry.register::<AlignSelf>();
+        registry.register::<JustifySelf>();
+        registry.register::<AlignContent>();
+        registry.register::<JustifyContent>();
+        registry.register::<FlexDirection>();
+        registry.register::<FlexWrap>();
+        registry.register::<GridAutoFlow>();
+        registry.register::<GridPlacement>();
+        registry.register::<GridTrack>();
+        registry.register::<GridTrackRepetition>();
+        registry.register::<MinTrackSizingFunction>();
+        registry.register::<MaxTrackSizingFunction>();
+        registry.register::<RepeatedGridTrack>();
+        registry.register::<Vec<GridTrack>>();
+        registry.register::<Vec<RepeatedGridTrack>>();
+        registry.register::<Option<Vec<GridTrack>>>();
+        registry.register::<Option<Vec<RepeatedGridTrack>>>();
+        registry
+    };
+}
+
+/// Fields of Bevy's `Style`, written as JSON and applied by reflection, as an escape hatch for
+/// properties which don't have a style attribute yet. For example,
+/// `bevy-style='{ "aspect_ratio": 1.5 }'`. Field names and values are as Bevy serializes them.
+#[derive(Clone)]
+pub struct RawStyle {
+    /// The JSON, as written.
+    pub source: String,
+
+    /// The fields which the JSON sets.
+    pub fields: StyleFields,
+
+    patch: Arc<dyn Reflect>,
+}
+
+impl RawStyle {
+    pub fn parse(source: &str) -> Result<Self, GuiseError> {
+        let invalid = |err: &dyn fmt::Display| {
+            GuiseError::InvalidAttributeValue(format!("{}: {}", source, err))
+        };
+        let registration = STYLE_REGISTRY.get(std::any::TypeId::of::<Style>()).unwrap();
+        let patch = TypedReflectDeserializer::new(registration, &STYLE_REGISTRY)
+            .deserialize(&mut serde_json::Deserializer::from_str(source))
+            .map_err(|err| invalid(&err))?;
+
+        let mut fields = StyleFields::default();
+        if let ReflectRef::Struct(st) = patch.reflect_ref() {
+            for i in 0..st.field_len() {
+                let name = st.name_at(i).unwrap();
+                match StyleField::from_name(name) {
+                    Some(field) => fields.insert(field),
+                    None => return Err(invalid(&format!("unknown field '{}'", name))),
+                }
+            }
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            fields,
+            patch: Arc::from(patch),
+        })
+    }
+
+    pub fn apply(&self, computed: &mut ComputedStyle) {
+        computed.style.apply(self.patch.as_ref());
+        computed.style_fields = computed.style_fields.union(self.fields);
+    }
+}
+
+impl PartialEq for RawStyle {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Debug for RawStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RawStyle({})", self.source)
+    }
+}
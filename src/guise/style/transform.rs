@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+/// Rotation, scale and translation of a UI node, from the `rotate`, `scale` and `translate`
+/// style attributes.
+///
+/// These are applied after layout, and so don't affect the position of other nodes. As with the
+/// individual CSS transform properties, the node is scaled first, then rotated, then translated,
+/// all relative to its center.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct StyleTransform {
+    /// Clockwise rotation, in radians.
+    pub rotate: f32,
+
+    /// Horizontal and vertical scale factors.
+    pub scale: Vec2,
+
+    /// Horizontal and vertical offset. Percentages are relative to the size of the node itself.
+    pub translate: (Val, Val),
+}
+
+impl StyleTransform {
+    pub const IDENTITY: StyleTransform = StyleTransform {
+        rotate: 0.,
+        scale: Vec2::ONE,
+        translate: (Val::Px(0.), Val::Px(0.)),
+    };
+
+    /// True if applying this transform would leave the node unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == StyleTransform::IDENTITY
+    }
+
+    /// Resolve the translation into pixels, given the size of the node.
+    pub fn offset(&self, size: Vec2) -> Vec2 {
+        Vec2::new(
+            resolve_length(self.translate.0, size.x),
+            resolve_length(self.translate.1, size.y),
+        )
+    }
+}
+
+impl Default for StyleTransform {
+    fn default() -> Self {
+        StyleTransform::IDENTITY
+    }
+}
+
+/// Convert a length to pixels. Only pixel and percentage lengths can be resolved here; other
+/// units are treated as zero.
+fn resolve_length(val: Val, size: f32) -> f32 {
+    match val {
+        Val::Px(px) => px,
+        Val::Percent(pct) => size * pct / 100.,
+        _ => 0.,
+    }
+}
+
+/// Applies style transforms to the node `Transform`. Layout overwrites the translation every time
+/// it changes, so this has to run after layout and before transform propagation.
+pub fn apply_style_transforms(mut query: Query<(&StyleTransform, &Node, &mut Transform)>) {
+    for (st, node, mut transform) in query.iter_mut() {
+        let rotation = Quat::from_rotation_z(st.rotate);
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+
+        let scale = st.scale.extend(1.);
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+
+        let offset = st.offset(node.size());
+        if offset != Vec2::ZERO {
+            transform.translation += offset.extend(0.);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset() {
+        let st = StyleTransform {
+            translate: (Val::Px(10.), Val::Percent(50.)),
+            ..default()
+        };
+        assert_eq!(st.offset(Vec2::new(100., 40.)), Vec2::new(10., 20.));
+        assert!(StyleTransform::default().is_identity());
+        assert!(!st.is_identity());
+    }
+}
@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::Vec2;
+use bevy::ui::Val;
+
+/// The sizes which relative lengths are resolved against. Either may be unknown, e.g. when a
+/// style is being checked before layout, in which case lengths which depend on it can only be
+/// compared or interpolated with lengths in the same unit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ValContext {
+    /// Size of the containing node along the axis being resolved, in pixels. Percentages are
+    /// relative to this.
+    pub parent_size: Option<f32>,
+
+    /// Size of the viewport in logical pixels, for `vw`, `vh`, `vmin` and `vmax`.
+    pub viewport: Option<Vec2>,
+}
+
+/// The length of `val` in pixels, or `None` for `auto` and for lengths relative to a size
+/// which the context doesn't know.
+pub fn resolve(val: Val, ctx: &ValContext) -> Option<f32> {
+    match val {
+        Val::Auto => None,
+        Val::Px(px) => Some(px),
+        Val::Percent(pct) => ctx.parent_size.map(|size| size * pct / 100.),
+        Val::Vw(v) => ctx.viewport.map(|vp| vp.x * v / 100.),
+        Val::Vh(v) => ctx.viewport.map(|vp| vp.y * v / 100.),
+        Val::VMin(v) => ctx.viewport.map(|vp| vp.min_element() * v / 100.),
+        Val::VMax(v) => ctx.viewport.map(|vp| vp.max_element() * v / 100.),
+    }
+}
+
+/// Interpolate two lengths. Lengths in the same unit stay in that unit; lengths in different
+/// units are interpolated in pixels if both can be resolved, and otherwise switch halfway
+/// through.
+pub fn lerp(a: Val, b: Val, t: f32, ctx: &ValContext) -> Val {
+    let mix = |a: f32, b: f32| a + (b - a) * t;
+    match (a, b) {
+        (Val::Px(a), Val::Px(b)) => Val::Px(mix(a, b)),
+        (Val::Percent(a), Val::Percent(b)) => Val::Percent(mix(a, b)),
+        (Val::Vw(a), Val::Vw(b)) => Val::Vw(mix(a, b)),
+        (Val::Vh(a), Val::Vh(b)) => Val::Vh(mix(a, b)),
+        (Val::VMin(a), Val::VMin(b)) => Val::VMin(mix(a, b)),
+        (Val::VMax(a), Val::VMax(b)) => Val::VMax(mix(a, b)),
+        _ => match (resolve(a, ctx), resolve(b, ctx)) {
+            (Some(a), Some(b)) => Val::Px(mix(a, b)),
+            _ => {
+                if t < 0.5 {
+                    a
+                } else {
+                    b
+                }
+            }
+        },
+    }
+}
+
+/// Compare two lengths. Lengths in the same unit are compared directly; lengths in different
+/// units are compared in pixels, or not at all if either can't be resolved.
+pub fn cmp(a: Val, b: Val, ctx: &ValContext) -> Option<Ordering> {
+    match (a, b) {
+        (Val::Px(a), Val::Px(b))
+        | (Val::Percent(a), Val::Percent(b))
+        | (Val::Vw(a), Val::Vw(b))
+        | (Val::Vh(a), Val::Vh(b))
+        | (Val::VMin(a), Val::VMin(b))
+        | (Val::VMax(a), Val::VMax(b)) => a.partial_cmp(&b),
+        _ => resolve(a, ctx)?.partial_cmp(&resolve(b, ctx)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ValContext {
+        ValContext {
+            parent_size: Some(200.),
+            viewport: Some(Vec2::new(800., 600.)),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let ctx = context();
+        assert_eq!(resolve(Val::Px(12.), &ctx), Some(12.));
+        assert_eq!(resolve(Val::Percent(25.), &ctx), Some(50.));
+        assert_eq!(resolve(Val::Vw(10.), &ctx), Some(80.));
+        assert_eq!(resolve(Val::Vh(10.), &ctx), Some(60.));
+        assert_eq!(resolve(Val::VMin(50.), &ctx), Some(300.));
+        assert_eq!(resolve(Val::VMax(50.), &ctx), Some(400.));
+        assert_eq!(resolve(Val::Auto, &ctx), None);
+
+        // Without a context, only pixels can be resolved.
+        let unknown = ValContext::default();
+        assert_eq!(resolve(Val::Px(12.), &unknown), Some(12.));
+        assert_eq!(resolve(Val::Percent(25.), &unknown), None);
+        assert_eq!(resolve(Val::Vw(10.), &unknown), None);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let ctx = context();
+        assert_eq!(lerp(Val::Px(0.), Val::Px(10.), 0.25, &ctx), Val::Px(2.5));
+        assert_eq!(
+            lerp(Val::Percent(10.), Val::Percent(20.), 0.5, &ctx),
+            Val::Percent(15.)
+        );
+
+        // Mixed units are interpolated in pixels: 50% of 200px is 100px.
+        assert_eq!(
+            lerp(Val::Px(0.), Val::Percent(50.), 0.5, &ctx),
+            Val::Px(50.)
+        );
+
+        // Unless they can't be resolved, or one of them is `auto`.
+        let unknown = ValContext::default();
+        assert_eq!(
+            lerp(Val::Px(0.), Val::Percent(50.), 0.4, &unknown),
+            Val::Px(0.)
+        );
+        assert_eq!(
+            lerp(Val::Px(0.), Val::Percent(50.), 0.6, &unknown),
+            Val::Percent(50.)
+        );
+        assert_eq!(lerp(Val::Auto, Val::Px(10.), 0.5, &ctx), Val::Px(10.));
+    }
+
+    #[test]
+    fn test_cmp() {
+        let ctx = context();
+        assert_eq!(cmp(Val::Px(10.), Val::Px(20.), &ctx), Some(Ordering::Less));
+        assert_eq!(
+            cmp(Val::Percent(50.), Val::Px(100.), &ctx),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            cmp(Val::Vw(50.), Val::Percent(100.), &ctx),
+            Some(Ordering::Greater)
+        );
+
+        // Same units can be compared without a context; mixed units can't.
+        let unknown = ValContext::default();
+        assert_eq!(
+            cmp(Val::Percent(60.), Val::Percent(50.), &unknown),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(cmp(Val::Percent(50.), Val::Px(100.), &unknown), None);
+        assert_eq!(cmp(Val::Auto, Val::Auto, &ctx), None);
+    }
+}
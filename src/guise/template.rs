@@ -1,9 +1,15 @@
+use std::path::Path;
 use std::sync::Arc;
 
+use bevy::asset::{AssetPath, AssetServer, Handle, HandleUntyped};
 use bevy::reflect::{TypePath, TypeUuid};
 use bevy::utils::HashMap;
+use lazy_static::lazy_static;
+use regex::Regex;
 
-use super::style::PartialStyle;
+use super::style::{PartialStyle, StyleAttr};
+use super::view::relative_asset_path;
+use super::GuiseError;
 
 pub type TemplateNodeList = Vec<Box<TemplateNode>>;
 
@@ -22,18 +28,241 @@ impl Template {
             children: Vec::new(),
         }
     }
+
+    /// Check the values passed to a template invocation against the declared param types.
+    /// Params which aren't declared are not checked.
+    pub fn validate_params(&self, values: &HashMap<String, String>) -> Result<(), GuiseError> {
+        for (name, value) in values.iter() {
+            if let Some(param) = self.params.get(name) {
+                param.validate(name, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterate over all of the template's nodes, depth-first, with each node before its
+    /// children. Included templates are not entered.
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &TemplateNode> {
+        self.iter_nodes_with_depth().map(|(_, node)| node)
+    }
+
+    /// Like `iter_nodes`, but also yields the depth of each node, where the template's
+    /// top-level nodes have a depth of zero.
+    pub fn iter_nodes_with_depth(&self) -> TemplateNodeIter<'_> {
+        TemplateNodeIter {
+            stack: vec![self.children.iter()],
+        }
+    }
+
+    /// The names of the params that the template refers to as `{{name}}`, in attribute values
+    /// and text, sorted and without duplicates. Included templates are not searched.
+    pub fn param_references(&self) -> Vec<String> {
+        lazy_static! {
+            static ref RE_PARAM_REF: Regex = Regex::new(r"\{\{\s*([\w-]+)\s*\}\}").unwrap();
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        let mut scan = |text: &str| {
+            names.extend(
+                RE_PARAM_REF
+                    .captures_iter(text)
+                    .map(|cap| cap[1].to_string()),
+            );
+        };
+        for node in self.iter_nodes() {
+            match node {
+                TemplateNode::Element(elt) | TemplateNode::Fragment(elt) => {
+                    elt.attrs.values().for_each(|value| scan(value));
+                }
+                TemplateNode::Text(text) => scan(&text.content),
+                TemplateNode::Include(_) => {}
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Check that every param the template refers to is declared, so that a missing param is
+    /// reported when the template is loaded rather than when it is spawned.
+    pub fn validate_references(&self) -> Result<(), GuiseError> {
+        let undeclared: Vec<String> = self
+            .param_references()
+            .into_iter()
+            .filter(|name| !self.params.contains_key(name))
+            .collect();
+        if undeclared.is_empty() {
+            Ok(())
+        } else {
+            Err(GuiseError::UndeclaredParams(undeclared))
+        }
+    }
+
+    /// Handles to every asset this template refers to, so that they can be loaded before the
+    /// template is spawned. `asset_path` and `base_dir` are the path of the template and the
+    /// settings base path, used to resolve relative references the same way spawning does.
+    ///
+    /// This returns one handle for each distinct `style` attribute and each included template.
+    /// The styles of included templates are not included.
+    pub fn dependency_handles(
+        &self,
+        server: &AssetServer,
+        asset_path: &AssetPath,
+        base_dir: Option<&Path>,
+    ) -> Vec<HandleUntyped> {
+        let mut handles: Vec<HandleUntyped> = Vec::new();
+        let mut to_visit: Vec<&TemplateNodeList> = vec![&self.children];
+        while let Some(nodes) = to_visit.pop() {
+            for node in nodes.iter() {
+                match node.as_ref() {
+                    TemplateNode::Element(elt) | TemplateNode::Fragment(elt) => {
+                        if let Some(style) = elt.attrs.get("style") {
+                            let handle = server
+                                .load::<PartialStyle, _>(relative_asset_path(
+                                    asset_path, base_dir, style,
+                                ))
+                                .clone_untyped();
+                            if !handles.contains(&handle) {
+                                handles.push(handle);
+                            }
+                        }
+                        to_visit.push(&elt.children);
+                    }
+                    TemplateNode::Include(inc) => {
+                        let handle = server.get_handle_untyped(inc.template.id());
+                        if !handles.contains(&handle) {
+                            handles.push(handle);
+                        }
+                    }
+                    TemplateNode::Text(_) => {}
+                }
+            }
+        }
+        handles
+    }
+
+    /// The templates directly included by this template, at any depth within its nodes.
+    pub fn includes(&self) -> Vec<&Handle<Template>> {
+        self.iter_nodes()
+            .filter_map(|node| match node {
+                TemplateNode::Include(inc) => Some(&inc.template),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Depth-first iterator over the nodes of a template, returned by
+/// `Template::iter_nodes_with_depth`.
+pub struct TemplateNodeIter<'a> {
+    /// Remaining siblings at each level of the nodes being visited.
+    stack: Vec<std::slice::Iter<'a, Box<TemplateNode>>>,
+}
+
+impl<'a> Iterator for TemplateNodeIter<'a> {
+    type Item = (usize, &'a TemplateNode);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len().checked_sub(1)?;
+            match self.stack[depth].next() {
+                Some(node) => {
+                    if let TemplateNode::Element(elt) | TemplateNode::Fragment(elt) = node.as_ref()
+                    {
+                        self.stack.push(elt.children.iter());
+                    }
+                    return Some((depth, node));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// The type of a template parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamType {
+    Number,
+
+    /// A whole number, written as `i32` in templates.
+    Integer,
+    String,
+    Color,
+    Bool,
+    Length,
+    Enum,
+    Nodes,
+
+    /// A Rust type path, such as `panoply::editor::ui::EditorMode`. Values are not checked.
+    Named(String),
+}
+
+impl ParamType {
+    pub fn parse(str: &str) -> Result<Self, GuiseError> {
+        match str {
+            "number" | "f32" => Ok(ParamType::Number),
+            "i32" => Ok(ParamType::Integer),
+            "string" => Ok(ParamType::String),
+            "color" => Ok(ParamType::Color),
+            "bool" => Ok(ParamType::Bool),
+            "length" => Ok(ParamType::Length),
+            "enum" => Ok(ParamType::Enum),
+            "nodes" => Ok(ParamType::Nodes),
+            _ if str.contains("::") => Ok(ParamType::Named(str.to_string())),
+            _ => Err(GuiseError::UnknownParamType(str.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ParamType::Number => "number",
+            ParamType::Integer => "i32",
+            ParamType::String => "string",
+            ParamType::Color => "color",
+            ParamType::Bool => "bool",
+            ParamType::Length => "length",
+            ParamType::Enum => "enum",
+            ParamType::Nodes => "nodes",
+            ParamType::Named(name) => name,
+        }
+    }
+
+    /// Whether `value` can be interpreted as this type.
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            ParamType::Number => value.parse::<f32>().is_ok(),
+            ParamType::Integer => value.parse::<i32>().is_ok(),
+            ParamType::Bool => value == "true" || value == "false",
+            ParamType::Color => StyleAttr::parse_color(value).is_ok(),
+            ParamType::Length => StyleAttr::parse_val(value).is_ok(),
+            ParamType::String | ParamType::Enum | ParamType::Nodes | ParamType::Named(_) => true,
+        }
+    }
 }
 
 /// Defines the types of parameters that can be passed to a template.
 #[derive(Debug)]
 pub struct TemplateParam {
-    pub r#type: String,
+    pub r#type: ParamType,
 }
 
 impl TemplateParam {
-    pub fn new(ty: &str) -> Self {
-        Self {
-            r#type: ty.to_string(),
+    pub fn new(ty: ParamType) -> Self {
+        Self { r#type: ty }
+    }
+
+    /// Check that `value` is valid for this param, returning an error naming the param if not.
+    pub fn validate(&self, name: &str, value: &str) -> Result<(), GuiseError> {
+        if self.r#type.accepts(value) {
+            Ok(())
+        } else {
+            Err(GuiseError::InvalidParamValue {
+                name: name.to_string(),
+                expected: self.r#type.as_str().to_string(),
+                value: value.to_string(),
+            })
         }
     }
 }
@@ -43,6 +272,7 @@ pub enum TemplateNode {
     Element(ElementNode),
     Fragment(ElementNode),
     Text(TextNode),
+    Include(IncludeNode),
 }
 
 /// Node that represents a 'node' node.
@@ -58,6 +288,9 @@ pub struct ElementNode {
     // Attached controller
     pub controller: Option<String>,
 
+    /// Position in keyboard focus order, from the `tab-index` attribute.
+    pub tab_index: Option<i32>,
+
     /// Controller parameters
     pub attrs: HashMap<String, String>,
 
@@ -67,6 +300,22 @@ pub struct ElementNode {
     // each / if / match
 }
 
+/// Node that is replaced by the nodes of another template when the view is built.
+#[derive(Debug)]
+pub struct IncludeNode {
+    /// Reference to the included template, as written, e.g. `shared.guise.xml#header`.
+    pub src: String,
+
+    /// The included template, resolved by the loader relative to the including file. This is a
+    /// weak handle: the loader makes the included file a dependency of the including template,
+    /// which is what causes it to be loaded.
+    pub template: Handle<Template>,
+
+    /// Values of the included template's params, from the other attributes of the `<include>`
+    /// element. These are checked against the param types when the include is expanded.
+    pub params: HashMap<String, String>,
+}
+
 /// Node that represents a span of text.
 #[derive(Debug, Default)]
 pub struct TextNode {
@@ -90,3 +339,145 @@ pub enum TemplateNodeType {
     // Match
     // Call
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    fn counter() -> Template {
+        let mut template = Template::new();
+        template.params.insert(
+            "count".to_string(),
+            TemplateParam::new(ParamType::parse("i32").unwrap()),
+        );
+        template.params.insert(
+            "color".to_string(),
+            TemplateParam::new(ParamType::parse("color").unwrap()),
+        );
+        template
+    }
+
+    fn element(id: &str, children: TemplateNodeList) -> Box<TemplateNode> {
+        Box::new(TemplateNode::Element(ElementNode {
+            id: Some(id.to_string()),
+            children,
+            ..default()
+        }))
+    }
+
+    #[test]
+    fn test_iter_nodes() {
+        let text = Box::new(TemplateNode::Text(TextNode {
+            content: "Hello".to_string(),
+            ..default()
+        }));
+        let mut template = Template::new();
+        template.children = vec![
+            element("a", vec![element("b", vec![text]), element("c", vec![])]),
+            element("d", vec![]),
+        ];
+
+        let visited: Vec<(usize, String)> = template
+            .iter_nodes_with_depth()
+            .map(|(depth, node)| match node {
+                TemplateNode::Element(elt) => (depth, elt.id.clone().unwrap()),
+                TemplateNode::Text(text) => (depth, text.content.clone()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            visited,
+            vec![
+                (0, "a".to_string()),
+                (1, "b".to_string()),
+                (2, "Hello".to_string()),
+                (1, "c".to_string()),
+                (0, "d".to_string()),
+            ]
+        );
+        assert_eq!(template.iter_nodes().count(), 5);
+        assert_eq!(Template::new().iter_nodes().count(), 0);
+    }
+
+    #[test]
+    fn test_parse_param_type() {
+        assert_eq!(ParamType::parse("length").unwrap(), ParamType::Length);
+        assert_eq!(
+            ParamType::parse("panoply::editor::ui::EditorMode").unwrap(),
+            ParamType::Named("panoply::editor::ui::EditorMode".to_string())
+        );
+        assert!(ParamType::parse("integer").is_err());
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let template = counter();
+        let mut values = HashMap::new();
+        values.insert("count".to_string(), "3".to_string());
+        values.insert("color".to_string(), "#ff0000".to_string());
+        assert!(template.validate_params(&values).is_ok());
+    }
+
+    #[test]
+    fn test_validate_params_mismatch() {
+        let template = counter();
+        let mut values = HashMap::new();
+        values.insert("count".to_string(), "three".to_string());
+        match template.validate_params(&values) {
+            Err(GuiseError::InvalidParamValue {
+                name,
+                expected,
+                value,
+            }) => {
+                assert_eq!(name, "count");
+                assert_eq!(expected, "i32");
+                assert_eq!(value, "three");
+            }
+            result => panic!("expected a param type error, got {:?}", result),
+        }
+
+        // Integer params don't accept fractions.
+        values.insert("count".to_string(), "1.5".to_string());
+        assert!(template.validate_params(&values).is_err());
+        assert!(ParamType::Number.accepts("1.5"));
+    }
+
+    #[test]
+    fn test_dependency_handles() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<PartialStyle>();
+
+        let styled = |style: &str, children: TemplateNodeList| {
+            let mut elt = ElementNode::default();
+            elt.attrs.insert("style".to_string(), style.to_string());
+            elt.children = children;
+            Box::new(TemplateNode::Element(elt))
+        };
+        let mut template = Template::new();
+        template.children.push(styled(
+            "#panel",
+            vec![
+                styled("./common.guise.xml#button", Vec::new()),
+                styled("#panel", Vec::new()),
+                Box::new(TemplateNode::Text(TextNode::default())),
+            ],
+        ));
+        template
+            .children
+            .push(Box::new(TemplateNode::Element(ElementNode::default())));
+
+        let server = app.world.resource::<AssetServer>();
+        let handles =
+            template.dependency_handles(server, &AssetPath::from("ui/dialog.guise.xml#main"), None);
+        let paths: Vec<AssetPath> = handles
+            .iter()
+            .map(|handle| server.get_handle_path(handle).unwrap())
+            .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&AssetPath::from("ui/dialog.guise.xml#panel")));
+        assert!(paths.contains(&AssetPath::from("ui/common.guise.xml#button")));
+    }
+}
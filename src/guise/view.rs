@@ -1,19 +1,33 @@
 use bevy::{
+    a11y::{
+        accesskit::{NodeBuilder, Role},
+        AccessibilityNode,
+    },
     asset::{AssetPath, LoadState},
     ecs::system::Command,
     prelude::*,
+    reflect::{serde::TypedReflectDeserializer, ReflectMut, TypeRegistry},
     ui::FocusPolicy,
+    utils::HashMap,
+    window::{PrimaryWindow, WindowResized},
 };
 use bevy_trait_query::One;
-use std::{path::PathBuf, sync::Arc};
+use serde::de::DeserializeSeed;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::guise::style::ComputedStyle;
+use crate::guise::style::{
+    ComputedStyle, GeneratedContent, LastComputedStyle, RootFontSize, StyleChanged, WhiteSpace,
+};
 
 use super::{
-    controller::Controller,
+    controller::{Controller, StyleContext},
     controllers::DefaultController,
-    style::PartialStyle,
+    style::{PartialStyle, SelectorTarget},
     template::{Template, TemplateNode, TemplateNodeList},
+    GuiseError, GuiseSettings, GuiseTheme,
 };
 
 /// Component that defines the root of a view hierarchy and a template invocation.
@@ -37,8 +51,17 @@ pub struct ViewElement {
 
     /// ID of controller component associated with this element.
     pub controller: Option<String>,
-    // pub controller_instance: Option<Arc<dyn Controller>>,
     pub classes: Vec<String>,
+
+    /// Zero-based index of this element among its parent's element children, for matching
+    /// structural selectors such as `:nth-child`. Text nodes are not counted.
+    pub sibling_index: usize,
+
+    /// Number of element children of this element's parent, including this element.
+    pub sibling_count: usize,
+
+    /// Position in keyboard focus order. See `update_focus`.
+    pub tab_index: Option<i32>,
 }
 
 impl ViewElement {
@@ -49,24 +72,67 @@ impl ViewElement {
         }
     }
 
-    /// Calculate the "computed" style struct for this `ViewElement`.
-    pub fn compute_style(&self, computed: &mut ComputedStyle, assets: &Assets<PartialStyle>) {
-        // TODO: Style state selectors
-        if let Some(ref style_handle) = self.style {
-            if let Some(ps) = assets.get(&style_handle) {
-                ps.apply_to(computed);
-            }
-        }
+    /// Type name of the controller attached to this element, as written in the template's
+    /// `controller` attribute. `None` for elements which use the default controller.
+    pub fn controller_name(&self) -> Option<&str> {
+        self.controller.as_deref()
+    }
 
-        if let Some(ref inline) = self.inline_styles {
-            inline.apply_to(computed);
+    /// The asset path of the element's named style, as resolved from the template's `style`
+    /// attribute. `None` if the element has no named style.
+    pub fn style_path<'a>(&self, server: &'a AssetServer) -> Option<AssetPath<'a>> {
+        self.style
+            .as_ref()
+            .and_then(|handle| server.get_handle_path(handle))
+    }
+
+    /// The element's current class names, for matching against style selectors.
+    pub fn class_names(&self) -> Vec<&str> {
+        self.classes.iter().map(String::as_str).collect()
+    }
+
+    /// True if the element currently has the class `name`.
+    pub fn has_class(&self, name: &str) -> bool {
+        self.classes.iter().any(|cls| cls == name)
+    }
+
+    /// Add or remove the class `name`. Returns true if the classes changed, in which case the
+    /// caller should insert `StyleHandlesChanged` so that the style is recomputed.
+    pub fn set_class(&mut self, name: &str, enabled: bool) -> bool {
+        match (self.has_class(name), enabled) {
+            (false, true) => self.classes.push(name.to_string()),
+            (true, false) => self.classes.retain(|cls| cls != name),
+            _ => return false,
         }
+        true
     }
 
     /// Calculate the "computed" style struct for this `ViewElement`.
-    pub fn apply_base_styles(&self, computed: &mut ComputedStyle, assets: &Assets<PartialStyle>) {
+    pub fn compute_style(&self, computed: &mut ComputedStyle, ctx: &StyleContext) {
+        self.apply_base_styles(computed, ctx);
+        let classes = self.class_names();
+        let target = SelectorTarget {
+            classes: &classes,
+            index: self.sibling_index,
+            sibling_count: self.sibling_count,
+            window_width: ctx.window_width,
+        };
+        self.apply_selected_styles(computed, ctx, &target);
+        self.apply_inline_styles(computed);
+        // TODO: Once Bevy has `Display::Block`, log a warning naming the element when a gap is
+        // set on an element which is neither a flex nor a grid container. Bevy 0.11 only has
+        // flex, grid and `none`, and the gap of a hidden element isn't a mistake, so there is
+        // nothing to check yet.
+    }
+
+    /// Apply the default style, followed by the element's named style.
+    pub fn apply_base_styles(&self, computed: &mut ComputedStyle, ctx: &StyleContext) {
+        if let Some(default_style) = ctx.default_style {
+            default_style.apply_to(computed);
+        }
+
         if let Some(ref style_handle) = self.style {
-            if let Some(ps) = assets.get(&style_handle) {
+            if let Some(ps) = ctx.style(style_handle) {
                 ps.apply_to(computed);
             } else {
                 // warn!("Failed to get stylesheet for node '{}'", self.element_id());
@@ -77,16 +143,39 @@ impl ViewElement {
     pub fn apply_selected_styles(
         &self,
         computed: &mut ComputedStyle,
-        assets: &Assets<PartialStyle>,
-        class_names: &[&str],
+        ctx: &StyleContext,
+        target: &SelectorTarget,
     ) {
         if let Some(ref style_handle) = self.style {
-            if let Some(ps) = assets.get(&style_handle) {
-                ps.apply_selected_to(computed, class_names);
+            if let Some(ps) = ctx.style(style_handle) {
+                ps.apply_selected_to(computed, target);
             }
         }
     }
 
+    /// True if any of the element's styles have selectors on the `hover` or `active` classes,
+    /// which need an `Interaction` component to be kept up to date.
+    pub fn uses_interaction_classes(&self, ctx: &StyleContext) -> bool {
+        let uses = |style: &PartialStyle| {
+            INTERACTION_CLASSES
+                .iter()
+                .any(|name| style.uses_class(name))
+        };
+        ctx.default_style.map_or(false, uses)
+            || self
+                .style
+                .as_ref()
+                .and_then(|handle| ctx.style(handle))
+                .map_or(false, uses)
+            || self.inline_styles.as_deref().map_or(false, uses)
+    }
+
+    /// True if there are any styles to apply to the element: the default style, a named style or
+    /// inline styles.
+    pub fn has_styles(&self, ctx: &StyleContext) -> bool {
+        ctx.default_style.is_some() || self.style.is_some() || self.inline_styles.is_some()
+    }
+
     pub fn apply_inline_styles(&self, computed: &mut ComputedStyle) {
         if let Some(ref inline) = self.inline_styles {
             inline.apply_to(computed);
@@ -94,13 +183,120 @@ impl ViewElement {
     }
 }
 
+/// Component on text nodes holding the text from the template, before whitespace processing.
+#[derive(Component, Default, Debug, Clone)]
+pub struct ViewText {
+    pub source: String,
+}
+
+/// Component recording which template a view element was spawned from, for debugging.
+#[derive(Component, Reflect, Default, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct ViewSource {
+    /// Path of the guise file.
+    pub path: PathBuf,
+
+    /// Label of the template within the file.
+    pub label: Option<String>,
+}
+
+impl ViewSource {
+    pub fn new(asset_path: &AssetPath) -> Self {
+        Self {
+            path: asset_path.path().to_path_buf(),
+            label: asset_path.label().map(str::to_string),
+        }
+    }
+
+    /// The source as an asset path, e.g. `ui/buttons.guise.xml#primary`.
+    pub fn asset_path(&self) -> AssetPath {
+        AssetPath::new_ref(&self.path, self.label.as_deref())
+    }
+}
+
 /// Marker that signals when a component's stylesheet handles have changed.
 #[derive(Component, Default)]
 pub struct StyleHandlesChanged;
 
+/// Command which adds or removes a class on a view element and recomputes its style. This is
+/// the way for controller event handlers to drive selector styles.
+///
+/// The `hover` and `active` classes are kept in sync with the `Interaction` component on elements
+/// which have one, so setting them here only lasts until the interaction state next changes.
+pub struct SetClass {
+    pub entity: Entity,
+    pub class: String,
+    pub enabled: bool,
+}
+
+impl Command for SetClass {
+    fn apply(self, world: &mut World) {
+        let Some(mut view) = world.get_mut::<ViewElement>(self.entity) else {
+            return;
+        };
+        if view.set_class(&self.class, self.enabled) {
+            world.entity_mut(self.entity).insert(StyleHandlesChanged);
+        }
+    }
+}
+
+/// Command which adds a class to a view element if it is absent, or removes it if present, and
+/// recomputes the element's style.
+pub struct ToggleClass {
+    pub entity: Entity,
+    pub class: String,
+}
+
+impl Command for ToggleClass {
+    fn apply(self, world: &mut World) {
+        let Some(view) = world.get::<ViewElement>(self.entity) else {
+            return;
+        };
+        let enabled = !view.has_class(&self.class);
+        SetClass {
+            entity: self.entity,
+            class: self.class,
+            enabled,
+        }
+        .apply(world);
+    }
+}
+
+/// Command which makes a view element pickable by adding an `Interaction` component, unless it
+/// already has one.
+struct InsertInteraction {
+    entity: Entity,
+}
+
+impl Command for InsertInteraction {
+    fn apply(self, world: &mut World) {
+        if let Some(mut e) = world.get_entity_mut(self.entity) {
+            if !e.contains::<Interaction>() {
+                e.insert(Interaction::default());
+            }
+        }
+    }
+}
+
+/// Element attributes which mean something to guise itself, and so are never copied into
+/// controller fields.
+const RESERVED_ATTRS: &[&str] = &[
+    "id",
+    "style",
+    "controller",
+    "tab-index",
+    "when",
+    "apply",
+    "role",
+    "aria-label",
+];
+
 pub struct InsertController {
     entity: Entity,
     controller: String,
+
+    /// Element attributes, copied into the controller's fields of the same name.
+    attrs: HashMap<String, String>,
 }
 
 /// Custom command to insert a Component by its type name. This is used for Controllers.
@@ -120,7 +316,11 @@ impl Command for InsertController {
         };
 
         if let Some(rcmp) = rcmp {
-            let controller = rcmp.from_world(world);
+            let mut controller = rcmp.from_world(world);
+            {
+                let types = world.resource::<AppTypeRegistry>().read();
+                apply_controller_attrs(controller.as_mut(), &self.attrs, &types);
+            }
             rcmp.insert(&mut world.entity_mut(self.entity), controller.as_ref());
         } else {
             println!("Controller type not found: [{}]", self.controller);
@@ -128,13 +328,69 @@ impl Command for InsertController {
     }
 }
 
+/// Copy element attributes into the fields of a newly-created controller. An attribute named
+/// `max-value` sets the field `max_value`. Values are parsed as JSON into the type of the field,
+/// except that a value which isn't valid JSON is treated as a string, so that `label="Ok"` and
+/// `mode="Compact"` (a unit enum variant) work without quotes. Attributes which don't match a
+/// field, and the attributes in `RESERVED_ATTRS`, are left alone.
+fn apply_controller_attrs(
+    controller: &mut dyn Reflect,
+    attrs: &HashMap<String, String>,
+    registry: &TypeRegistry,
+) {
+    let st = match controller.reflect_mut() {
+        ReflectMut::Struct(st) => st,
+        _ => return,
+    };
+
+    for (name, value) in attrs.iter() {
+        if RESERVED_ATTRS.contains(&name.as_str()) {
+            continue;
+        }
+        let field = match st.field_mut(&name.replace('-', "_")) {
+            Some(field) => field,
+            None => continue,
+        };
+
+        let registration = field
+            .get_represented_type_info()
+            .and_then(|info| registry.get(info.type_id()));
+        let registration = match registration {
+            Some(registration) => registration,
+            None => {
+                warn!("Controller attribute '{}' has an unregistered type", name);
+                continue;
+            }
+        };
+
+        let parse = |json: &str| {
+            TypedReflectDeserializer::new(registration, registry)
+                .deserialize(&mut serde_json::Deserializer::from_str(json))
+        };
+        let result = parse(value).or_else(|err| {
+            serde_json::to_string(value)
+                .map_err(|_| err)
+                .and_then(|quoted| parse(&quoted))
+        });
+        match result {
+            Ok(typed) => field.apply(typed.as_ref()),
+            Err(err) => warn!(
+                "Invalid value '{}' for controller attribute '{}': {}",
+                value, name, err
+            ),
+        }
+    }
+}
+
 pub fn create_views(
     mut commands: Commands,
     mut root_query: Query<(Entity, Ref<ViewRoot>, Option<&Children>)>,
     mut view_query: Query<(&mut ViewElement, Option<&Children>)>,
+    generated_query: Query<(), With<GeneratedContent>>,
     // mut text_query: Query<&Text>,
     server: Res<AssetServer>,
     assets: Res<Assets<Template>>,
+    settings: Res<GuiseSettings>,
     mut ev_template: EventReader<AssetEvent<Template>>,
 ) {
     for ev in ev_template.iter() {
@@ -144,15 +400,31 @@ pub fn create_views(
                     match assets.get(handle) {
                         Some(template) => {
                             for (entity, view_root, children) in root_query.iter_mut() {
-                                if view_root.template.eq(handle) {
+                                // Views which include the template are rebuilt from their own
+                                // template.
+                                let root = if view_root.template.eq(handle) {
+                                    Some((asset_path.to_owned(), template))
+                                } else if includes_template(&assets, &view_root.template, handle) {
+                                    server
+                                        .get_handle_path(&view_root.template)
+                                        .map(|path| path.to_owned())
+                                        .zip(assets.get(&view_root.template))
+                                } else {
+                                    None
+                                };
+
+                                if let Some((root_path, root_template)) = root {
                                     reconcile_template(
                                         &mut commands,
                                         &server,
-                                        &asset_path,
+                                        &root_path,
+                                        settings.base_path.as_deref(),
                                         entity,
                                         children,
-                                        &template.children,
+                                        &root_template.children,
+                                        &assets,
                                         &mut view_query,
+                                        &generated_query,
                                     );
                                 }
                             }
@@ -182,25 +454,28 @@ pub fn create_views(
 /// This tries to preserve the existing view hierarchy (a bit like React's VDOM), but will destroy
 /// and re-create entire sub-trees of entities if it feels that differential updates are too
 /// complicated.
-fn reconcile_template(
+fn reconcile_template<'t>(
     commands: &mut Commands,
     server: &AssetServer,
     asset_path: &AssetPath,
+    base_dir: Option<&Path>,
     root: Entity,
     root_children: Option<&Children>,
-    root_template_nodes: &TemplateNodeList,
+    root_template_nodes: &'t TemplateNodeList,
+    templates: &'t Assets<Template>,
     view_query: &mut Query<(&mut ViewElement, Option<&Children>)>,
+    generated_query: &Query<(), With<GeneratedContent>>,
 ) {
     // Use a queue to visit the tree; easier than trying to pass a borrowed query into a recursive
-    // function.
-    let mut to_visit = Vec::<(Entity, &TemplateNodeList)>::with_capacity(64);
-    to_visit.push((root, &root_template_nodes));
+    // function. Each list of nodes is paired with the path of the file it came from.
+    let mut to_visit = Vec::<(Entity, &TemplateNodeList, AssetPath<'static>)>::with_capacity(64);
+    to_visit.push((root, root_template_nodes, asset_path.to_owned()));
 
     // Loop which compares the list of child template nodes with the existing child entities.
-    while let Some((parent, parent_template_nodes)) = to_visit.pop() {
+    while let Some((parent, parent_template_nodes, parent_path)) = to_visit.pop() {
         // Logic is a bit complex here because the root has a different Component type than the
         // rest of the tree. Turn it into a list.
-        let children: &[Entity] = if parent == root {
+        let all_children: &[Entity] = if parent == root {
             match root_children {
                 Some(ch) => ch,
                 None => &[],
@@ -212,11 +487,33 @@ fn reconcile_template(
             }
         };
 
+        // Nodes generated by a style, such as text from a `content` attribute, aren't part of
+        // the template, so keep them in front and leave them out of the comparison.
+        let (generated, children): (Vec<Entity>, Vec<Entity>) = all_children
+            .iter()
+            .copied()
+            .partition(|child| generated_query.contains(*child));
+
+        let mut template_nodes = Vec::with_capacity(parent_template_nodes.len());
+        expand_includes(
+            parent_template_nodes,
+            &parent_path,
+            server,
+            templates,
+            &mut template_nodes,
+        );
+
         let old_count = children.len();
-        let new_count = parent_template_nodes.len();
+        let new_count = template_nodes.len();
         let max_index = old_count.max(new_count);
-        let mut new_children: Vec<Entity> = Vec::with_capacity(new_count);
+        let mut new_children: Vec<Entity> = Vec::with_capacity(generated.len() + new_count);
+        new_children.extend(generated);
         let mut children_changed = false;
+        let mut element_index: usize = 0;
+        let sibling_count = template_nodes
+            .iter()
+            .filter(|(node, _)| matches!(node, TemplateNode::Element(_)))
+            .count();
 
         for i in 0..max_index {
             if i >= new_count {
@@ -224,10 +521,13 @@ fn reconcile_template(
                 commands.entity(children[i]).despawn_recursive();
                 children_changed = true;
             } else {
-                let template_node = &parent_template_nodes[i];
-                match template_node.as_ref() {
+                let (template_node, node_path) = &template_nodes[i];
+                match *template_node {
                     TemplateNode::Element(elt) => {
-                        let style = get_named_styles(elt.attrs.get("style"), asset_path, server);
+                        let sibling_index = element_index;
+                        element_index += 1;
+                        let style =
+                            get_named_styles(elt.attrs.get("style"), node_path, base_dir, server);
                         if i < old_count {
                             let old_child = children[i];
                             match view_query.get(old_child) {
@@ -240,6 +540,9 @@ fn reconcile_template(
                                         let mut changed = false;
                                         if !view.style.eq(&style)
                                             || view.inline_styles != elt.inline_styles
+                                            || view.sibling_index != sibling_index
+                                            || view.sibling_count != sibling_count
+                                            || view.tab_index != elt.tab_index
                                         {
                                             changed = true;
                                         }
@@ -253,15 +556,37 @@ fn reconcile_template(
                                                     id: elt.id.clone(),
                                                     style: style.clone(),
                                                     inline_styles: elt.inline_styles.clone(),
+                                                    controller: elt.controller.clone(),
+                                                    classes: view.classes.clone(),
+                                                    sibling_index,
+                                                    sibling_count,
+                                                    tab_index: elt.tab_index,
                                                     ..default()
                                                 },
                                                 StyleHandlesChanged,
                                             ));
                                         }
 
+                                        // Accessibility nodes can't be compared, so they are
+                                        // replaced on every patch.
+                                        match accessibility_node(&elt.attrs) {
+                                            Some(node) => {
+                                                commands.entity(old_child).insert(node);
+                                            }
+                                            None => {
+                                                commands
+                                                    .entity(old_child)
+                                                    .remove::<AccessibilityNode>();
+                                            }
+                                        }
+
                                         new_children.push(old_child);
                                         if !elt.children.is_empty() || grand_children.is_some() {
-                                            to_visit.push((old_child, &elt.children));
+                                            to_visit.push((
+                                                old_child,
+                                                &elt.children,
+                                                node_path.clone(),
+                                            ));
                                         }
 
                                         continue;
@@ -283,9 +608,14 @@ fn reconcile_template(
                                     id: elt.id.clone(),
                                     style: style.clone(),
                                     inline_styles: elt.inline_styles.clone(),
+                                    controller: elt.controller.clone(),
+                                    sibling_index,
+                                    sibling_count,
+                                    tab_index: elt.tab_index,
                                     ..default()
                                 },
                                 StyleHandlesChanged,
+                                ViewSource::new(node_path),
                                 NodeBundle {
                                     background_color: Color::rgb(0.65, 0.75, 0.65).into(),
                                     border_color: Color::BLUE.into(),
@@ -295,12 +625,19 @@ fn reconcile_template(
                             ))
                             .id();
 
-                        // See if there's a controller for this ui node.
+                        if let Some(node) = accessibility_node(&elt.attrs) {
+                            commands.entity(new_entity).insert(node);
+                        }
+
+                        // See if there's a controller for this ui node. Controllers handle
+                        // events, so their elements need to be pickable.
                         if let Some(ref controller_id) = elt.controller {
                             // println!("Controller {}", controller_id);
+                            commands.entity(new_entity).insert(Interaction::default());
                             commands.add(InsertController {
                                 entity: new_entity,
                                 controller: controller_id.clone(),
+                                attrs: elt.attrs.clone(),
                             });
                         } else {
                             commands.entity(new_entity).insert(DefaultController);
@@ -309,7 +646,7 @@ fn reconcile_template(
                         children_changed = true;
                         new_children.push(new_entity);
                         if elt.children.len() > 0 {
-                            to_visit.push((new_entity, &elt.children));
+                            to_visit.push((new_entity, &elt.children, node_path.clone()));
                         }
                     }
 
@@ -361,23 +698,29 @@ fn reconcile_template(
                             commands.entity(old_child).despawn_recursive();
                         }
 
-                        // Create the new entity
+                        // Create the new entity. Whitespace is collapsed until the parent's
+                        // style says otherwise.
                         let new_entity = commands
-                            .spawn((TextBundle {
-                                text: Text::from_section(
-                                    text.content.clone(),
-                                    TextStyle { ..default() },
-                                ),
-                                // TextStyle {
-                                //     font_size: 40.0,
-                                //     color: Color::rgb(0.9, 0.9, 0.9),
-                                //     ..Default::default()
-                                // },
-                                // background_color: Color::rgb(0.65, 0.75, 0.65).into(),
-                                // border_color: Color::BLUE.into(),
-                                // focus_policy: FocusPolicy::Pass,
-                                ..default()
-                            },))
+                            .spawn((
+                                TextBundle {
+                                    text: Text::from_section(
+                                        WhiteSpace::Normal.process(&text.content),
+                                        TextStyle { ..default() },
+                                    ),
+                                    // TextStyle {
+                                    //     font_size: 40.0,
+                                    //     color: Color::rgb(0.9, 0.9, 0.9),
+                                    //     ..Default::default()
+                                    // },
+                                    // background_color: Color::rgb(0.65, 0.75, 0.65).into(),
+                                    // border_color: Color::BLUE.into(),
+                                    // focus_policy: FocusPolicy::Pass,
+                                    ..default()
+                                },
+                                ViewText {
+                                    source: text.content.clone(),
+                                },
+                            ))
                             .id();
 
                         children_changed = true;
@@ -387,29 +730,141 @@ fn reconcile_template(
                     TemplateNode::Fragment(_frag) => {
                         panic!("Implement fragment")
                     }
+
+                    TemplateNode::Include(_) => {
+                        unreachable!("Includes are expanded before reconciling")
+                    }
                 }
             }
         }
 
         if children_changed {
             commands.entity(parent).replace_children(&new_children);
+            if parent != root {
+                // Restyle the parent so that its styles reach the new text nodes.
+                commands.entity(parent).insert(StyleHandlesChanged);
+            }
+        }
+    }
+}
+
+/// Append `nodes` to `out`, replacing each include with the nodes of the included template, and
+/// pair each node with the path of the file it came from. Includes of templates which aren't
+/// loaded yet, which include themselves, or whose param values don't match the param types, are
+/// left out.
+fn expand_includes<'t>(
+    nodes: &'t TemplateNodeList,
+    path: &AssetPath<'static>,
+    server: &AssetServer,
+    templates: &'t Assets<Template>,
+    out: &mut Vec<(&'t TemplateNode, AssetPath<'static>)>,
+) {
+    for node in nodes.iter() {
+        match node.as_ref() {
+            TemplateNode::Include(inc) => {
+                if includes_template(templates, &inc.template, &inc.template) {
+                    warn!("{}", GuiseError::CircularReference(inc.src.clone()));
+                    continue;
+                }
+                if let Some(template) = templates.get(&inc.template) {
+                    if let Err(err) = template.validate_params(&inc.params) {
+                        warn!("Include of '{}': {}", inc.src, err);
+                        continue;
+                    }
+                    let included_path = server
+                        .get_handle_path(&inc.template)
+                        .map_or_else(|| path.clone(), |path| path.to_owned());
+                    expand_includes(&template.children, &included_path, server, templates, out);
+                }
+            }
+            node => out.push((node, path.clone())),
+        }
+    }
+}
+
+/// True if the template `from` includes `target`, directly or through other includes.
+fn includes_template<'t>(
+    templates: &'t Assets<Template>,
+    from: &'t Handle<Template>,
+    target: &Handle<Template>,
+) -> bool {
+    let mut visited: Vec<&Handle<Template>> = Vec::new();
+    let mut to_visit: Vec<&Handle<Template>> = vec![from];
+    while let Some(handle) = to_visit.pop() {
+        if let Some(template) = templates.get(handle) {
+            for include in template.includes() {
+                if include == target {
+                    return true;
+                }
+                if !visited.contains(&include) {
+                    visited.push(include);
+                    to_visit.push(include);
+                }
+            }
         }
     }
+    false
 }
 
 fn get_named_styles(
     name: Option<&String>,
     base_path: &AssetPath,
+    base_dir: Option<&Path>,
     server: &AssetServer,
 ) -> Option<Handle<PartialStyle>> {
     // Check if template has a 'style' attribute
     name.map(|str| {
-        let style_path = relative_asset_path(&base_path, str);
+        let style_path = relative_asset_path(&base_path, base_dir, str);
         // println!("Relative asset: {:?}", style_path);
         server.load(style_path)
     })
 }
 
+/// The accessibility role named by a `role` attribute, or `None` if it isn't one guise knows.
+pub(crate) fn accessibility_role(name: &str) -> Option<Role> {
+    Some(match name.trim() {
+        "button" => Role::Button,
+        "checkbox" => Role::CheckBox,
+        "dialog" => Role::Dialog,
+        "group" => Role::Group,
+        "heading" => Role::Heading,
+        "img" => Role::Image,
+        "link" => Role::Link,
+        "list" => Role::List,
+        "listitem" => Role::ListItem,
+        "menu" => Role::Menu,
+        "menuitem" => Role::MenuItem,
+        "progressbar" => Role::ProgressIndicator,
+        "radio" => Role::RadioButton,
+        "slider" => Role::Slider,
+        "tab" => Role::Tab,
+        "tablist" => Role::TabList,
+        "tabpanel" => Role::TabPanel,
+        _ => return None,
+    })
+}
+
+/// The accessibility node described by an element's `role` and `aria-label` attributes, or
+/// `None` if it has neither. Elements with a label but no role are generic containers. Unknown
+/// roles are reported when the document is loaded.
+fn accessibility_node(attrs: &HashMap<String, String>) -> Option<AccessibilityNode> {
+    let role = attrs.get("role");
+    let label = attrs.get("aria-label");
+    if role.is_none() && label.is_none() {
+        return None;
+    }
+
+    let role = match role {
+        None => Role::GenericContainer,
+        Some(role) => accessibility_role(role).unwrap_or(Role::Unknown),
+    };
+    let mut node = NodeBuilder::new(role);
+    if let Some(label) = label {
+        node.set_name(label.as_str());
+    }
+    Some(AccessibilityNode(node))
+}
+
 pub fn attach_view_controllers(
     mut commands: Commands,
     query: Query<(Entity, &ViewElement, One<&dyn Controller>), Added<ViewElement>>,
@@ -426,8 +881,29 @@ pub fn update_view_styles(
     query: Query<(Entity, &ViewElement, One<&dyn Controller>)>,
     server: Res<AssetServer>,
     assets: Res<Assets<PartialStyle>>,
+    settings: Res<GuiseSettings>,
+    theme: Res<GuiseTheme>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    root_font_size: Res<RootFontSize>,
     mut ev_style: EventReader<AssetEvent<PartialStyle>>,
 ) {
+    // Changing the default style, the theme or the root font size affects every element.
+    if settings.is_changed() || theme.is_changed() || root_font_size.is_changed() {
+        for (entity, _, _) in query.iter() {
+            commands.entity(entity).insert(StyleHandlesChanged);
+        }
+    }
+
+    let ctx = StyleContext {
+        assets: &assets,
+        default_style: settings
+            .default_style
+            .as_ref()
+            .and_then(|handle| assets.get(theme.resolve(handle))),
+        theme: Some(&theme),
+        window_width: primary_window_width(&windows),
+    };
+
     for ev in ev_style.iter() {
         match ev {
             AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
@@ -435,14 +911,21 @@ pub fn update_view_styles(
                     debug!("Asset Created/Modified: Style {:?}", asset_path);
                 }
 
+                let is_default = settings
+                    .default_style
+                    .as_ref()
+                    .map(|style| theme.resolve(style))
+                    == Some(handle);
                 for (entity, view, controller) in query.iter() {
-                    if let Some(ref style_handle) = view.style {
-                        if style_handle.eq(handle) {
-                            // println!("Updating styles for node: [{}]", view.element_id());
-                            controller.update_styles(&mut commands, entity, &view, &assets);
-                            commands.entity(entity).remove::<StyleHandlesChanged>();
-                            // view.set_changed();
+                    let style = view.style.as_ref().map(|style| theme.resolve(style));
+                    if is_default || style == Some(handle) {
+                        // println!("Updating styles for node: [{}]", view.element_id());
+                        controller.update_styles(&mut commands, entity, &view, &ctx);
+                        if view.uses_interaction_classes(&ctx) {
+                            commands.add(InsertInteraction { entity });
                         }
+                        commands.entity(entity).remove::<StyleHandlesChanged>();
+                        // view.set_changed();
                     }
                 }
             }
@@ -456,18 +939,137 @@ pub fn update_view_styles(
     }
 }
 
+/// Restyles the elements whose `<media>` styles match differently after the primary window is
+/// resized.
+pub fn update_media_styles(
+    mut commands: Commands,
+    query: Query<(Entity, &ViewElement)>,
+    assets: Res<Assets<PartialStyle>>,
+    settings: Res<GuiseSettings>,
+    theme: Res<GuiseTheme>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut ev_resized: EventReader<WindowResized>,
+    mut last_width: Local<Option<f32>>,
+) {
+    if ev_resized.iter().count() == 0 {
+        return;
+    }
+    let width = primary_window_width(&windows);
+    let from = std::mem::replace(&mut *last_width, width);
+    if from == width {
+        return;
+    }
+
+    let changes = |handle: Option<&Handle<PartialStyle>>| {
+        handle
+            .and_then(|handle| assets.get(theme.resolve(handle)))
+            .map_or(false, |style| style.window_width_changes(from, width))
+    };
+    let default_changes = changes(settings.default_style.as_ref());
+    for (entity, view) in query.iter() {
+        if default_changes || changes(view.style.as_ref()) {
+            commands.entity(entity).insert(StyleHandlesChanged);
+        }
+    }
+}
+
+/// Width of the primary window in logical pixels, if there is one.
+fn primary_window_width(windows: &Query<&Window, With<PrimaryWindow>>) -> Option<f32> {
+    windows.get_single().ok().map(Window::width)
+}
+
+/// Class names which mirror the element's `Interaction` state, so that styles can use
+/// `self.hover` and `self.active` selectors. Keyboard focus is tracked separately, by
+/// `update_focus`.
+const INTERACTION_CLASSES: [&str; 2] = ["hover", "active"];
+
+/// Keeps the interaction classes of view elements in sync with their `Interaction` component.
+/// Elements without an `Interaction` component never get these classes.
+pub fn update_interaction_classes(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ViewElement, &Interaction), Changed<Interaction>>,
+) {
+    for (entity, mut view, interaction) in query.iter_mut() {
+        let classes: &[&str] = match interaction {
+            Interaction::Pressed => &["hover", "active"],
+            Interaction::Hovered => &["hover"],
+            Interaction::None => &[],
+        };
+        let current: Vec<&str> = view
+            .classes
+            .iter()
+            .map(String::as_str)
+            .filter(|cls| INTERACTION_CLASSES.contains(cls))
+            .collect();
+        if current != classes {
+            view.classes
+                .retain(|cls| !INTERACTION_CLASSES.contains(&cls.as_str()));
+            view.classes
+                .extend(classes.iter().map(|cls| cls.to_string()));
+            commands.entity(entity).insert(StyleHandlesChanged);
+        }
+    }
+}
+
+/// Tells the controllers of view elements which fields of their style changed.
+pub fn notify_style_changes(
+    mut commands: Commands,
+    query: Query<(&ViewElement, One<&dyn Controller>)>,
+    mut ev_changed: EventReader<StyleChanged>,
+) {
+    for ev in ev_changed.iter() {
+        if let Ok((view, controller)) = query.get(ev.entity) {
+            controller.style_changed(&mut commands, ev.entity, view, ev.fields);
+        }
+    }
+}
+
 /// One of two updaters for computing the ui node styles, this one looks for a marker component
 /// on the entity.
 pub fn update_view_styles_poll(
     mut commands: Commands,
-    query: Query<(Entity, &ViewElement, One<&dyn Controller>), With<StyleHandlesChanged>>,
+    query: Query<
+        (
+            Entity,
+            &ViewElement,
+            One<&dyn Controller>,
+            Option<&Children>,
+            Has<LastComputedStyle>,
+        ),
+        With<StyleHandlesChanged>,
+    >,
+    text_query: Query<(), With<ViewText>>,
     server: Res<AssetServer>,
     assets: Res<Assets<PartialStyle>>,
+    settings: Res<GuiseSettings>,
+    theme: Res<GuiseTheme>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
-    for (entity, view, controller) in query.iter() {
+    let ctx = StyleContext {
+        assets: &assets,
+        default_style: settings
+            .default_style
+            .as_ref()
+            .and_then(|handle| assets.get(theme.resolve(handle))),
+        theme: Some(&theme),
+        window_width: primary_window_width(&windows),
+    };
+
+    for (entity, view, controller, children, styled) in query.iter() {
+        // An element with nothing to apply keeps the default style it was spawned with, unless
+        // it needs resetting, its text children need the text style it passes down, or its
+        // controller styles it.
+        let has_text = children.map_or(false, |children| {
+            children.iter().any(|child| text_query.contains(*child))
+        });
+        if !view.has_styles(&ctx) && !styled && !has_text && view.controller.is_none() {
+            commands.entity(entity).remove::<StyleHandlesChanged>();
+            continue;
+        }
+
         // Don't update style if stylesheet isn't loaded.
         if let Some(ref style_handle) = view.style {
-            let load_state = server.get_load_state(style_handle);
+            let load_state = server.get_load_state(theme.resolve(style_handle));
             println!(
                 "Updating node '{}' style handle status: '{:?}'",
                 view.element_id(),
@@ -478,7 +1080,10 @@ pub fn update_view_styles_poll(
             }
         }
 
-        controller.update_styles(&mut commands, entity, &view, &assets);
+        controller.update_styles(&mut commands, entity, &view, &ctx);
+        if view.uses_interaction_classes(&ctx) {
+            commands.add(InsertInteraction { entity });
+        }
         commands.entity(entity).remove::<StyleHandlesChanged>();
     }
 }
@@ -486,17 +1091,27 @@ pub fn update_view_styles_poll(
 /// Resolves a relative asset path. The relative path can be one of:
 /// * An absolute path e.g. `foo/bar#fragment`
 /// * A path starting with './' or '../', e.g. `./bar#fragment`, in which case it is resolved
-///   relative to the current directory.
+///   relative to `base_dir` if set, or to the current directory otherwise.
 /// * Just a label, `#fragment`.
-fn relative_asset_path<'a>(base: &'a AssetPath<'a>, relative_path: &'a str) -> AssetPath<'a> {
+pub(crate) fn relative_asset_path<'a>(
+    base: &'a AssetPath<'a>,
+    base_dir: Option<&Path>,
+    relative_path: &'a str,
+) -> AssetPath<'a> {
     if relative_path.starts_with('#') {
         AssetPath::new_ref(base.path(), Some(&relative_path[1..]))
     } else if relative_path.starts_with("./") || relative_path.starts_with("../") {
         let mut rpath = relative_path;
-        let mut fpath = PathBuf::from(base.path());
-        if !fpath.pop() {
-            panic!("Can't compute relative path");
-        }
+        let mut fpath = match base_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => {
+                let mut fpath = PathBuf::from(base.path());
+                if !fpath.pop() {
+                    panic!("Can't compute relative path");
+                }
+                fpath
+            }
+        };
         loop {
             if rpath.starts_with("./") {
                 rpath = &rpath[2..];
@@ -524,26 +1139,937 @@ fn relative_asset_path<'a>(base: &'a AssetPath<'a>, relative_path: &'a str) -> A
 
 #[cfg(test)]
 mod tests {
+    use bevy::asset::AssetPlugin;
+
     use super::*;
+    use crate::guise::style::{Selector, StyleAttr, StyleField, StyleFields, UpdateComputedStyle};
+    use crate::guise::template::{ElementNode, IncludeNode, ParamType, TemplateParam, TextNode};
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<PartialStyle>()
+            .add_asset::<Template>();
+        app
+    }
+
+    /// The background color an element's styles compute to, without a theme or default style.
+    fn computed_background(world: &World, entity: Entity) -> Option<Color> {
+        let ctx = StyleContext {
+            assets: world.resource::<Assets<PartialStyle>>(),
+            default_style: None,
+            theme: None,
+            window_width: None,
+        };
+        let mut computed = ComputedStyle::default();
+        world
+            .get::<ViewElement>(entity)
+            .unwrap()
+            .compute_style(&mut computed, &ctx);
+        computed.background_color
+    }
+
+    #[test]
+    fn test_default_style() {
+        let app = test_app();
+        let default_style =
+            PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::RED))]);
+        let ctx = StyleContext {
+            assets: app.world.resource::<Assets<PartialStyle>>(),
+            default_style: Some(&default_style),
+            theme: None,
+            window_width: None,
+        };
+
+        let view = ViewElement::default();
+        let mut computed = ComputedStyle::default();
+        view.compute_style(&mut computed, &ctx);
+        assert_eq!(computed.background_color, Some(Color::RED));
+    }
+
+    #[test]
+    fn test_theme() {
+        let mut app = test_app();
+        let background =
+            |color| PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(color))]);
+        let mut assets = app.world.resource_mut::<Assets<PartialStyle>>();
+        let light = assets.add(background(Color::WHITE));
+        let dark = assets.add(background(Color::BLACK));
+        let views: Vec<ViewElement> = (0..2)
+            .map(|_| ViewElement {
+                style: Some(light.clone()),
+                ..default()
+            })
+            .collect();
+
+        let mut theme = GuiseTheme::default();
+        let backgrounds = |theme: &GuiseTheme| {
+            let ctx = StyleContext {
+                assets: app.world.resource::<Assets<PartialStyle>>(),
+                default_style: None,
+                theme: Some(theme),
+                window_width: None,
+            };
+            views
+                .iter()
+                .map(|view| {
+                    let mut computed = ComputedStyle::default();
+                    view.compute_style(&mut computed, &ctx);
+                    computed.background_color
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(backgrounds(&theme), vec![Some(Color::WHITE); 2]);
+
+        theme.styles.insert(light.clone(), dark);
+        assert_eq!(backgrounds(&theme), vec![Some(Color::BLACK); 2]);
+    }
+
+    #[test]
+    fn test_theme_restyles() {
+        use bevy_trait_query::RegisterExt;
+
+        let mut app = test_app();
+        app.init_resource::<GuiseSettings>()
+            .init_resource::<GuiseTheme>()
+            .init_resource::<RootFontSize>()
+            .register_component_as::<dyn Controller, DefaultController>()
+            .add_systems(
+                Update,
+                (update_view_styles, update_view_styles_poll).chain(),
+            );
+        let background =
+            |color| PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(color))]);
+        let mut assets = app.world.resource_mut::<Assets<PartialStyle>>();
+        let light = assets.add(background(Color::WHITE));
+        let dark = assets.add(background(Color::BLACK));
+        let plain = assets.add(background(Color::RED));
+        let mut spawn = |style: &Handle<PartialStyle>| {
+            app.world
+                .spawn((
+                    ViewElement {
+                        style: Some(style.clone()),
+                        ..default()
+                    },
+                    DefaultController,
+                    NodeBundle::default(),
+                ))
+                .id()
+        };
+        let elements = [spawn(&light), spawn(&light), spawn(&plain)];
+        let colors = |app: &App| {
+            elements
+                .iter()
+                .map(|entity| app.world.get::<BackgroundColor>(*entity).unwrap().0)
+                .collect::<Vec<_>>()
+        };
+        app.update();
+        assert_eq!(colors(&app), vec![Color::WHITE, Color::WHITE, Color::RED]);
+
+        // Switching themes restyles the existing elements, without touching their styles.
+        app.world
+            .resource_mut::<GuiseTheme>()
+            .styles
+            .insert(light.clone(), dark);
+        app.update();
+        assert_eq!(colors(&app), vec![Color::BLACK, Color::BLACK, Color::RED]);
+    }
+
+    #[test]
+    fn test_skip_unstyled() {
+        use bevy_trait_query::RegisterExt;
+
+        let mut app = test_app();
+        app.init_resource::<GuiseSettings>()
+            .init_resource::<GuiseTheme>()
+            .register_component_as::<dyn Controller, DefaultController>()
+            .add_systems(Update, update_view_styles_poll);
+        let mut spawn = |view: ViewElement| {
+            app.world
+                .spawn((
+                    view,
+                    DefaultController,
+                    StyleHandlesChanged,
+                    NodeBundle::default(),
+                ))
+                .id()
+        };
+        let unstyled = spawn(ViewElement::default());
+        let width = PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(10.))]);
+        let styled = spawn(ViewElement {
+            inline_styles: Some(Arc::new(width)),
+            ..default()
+        });
+        let with_text = spawn(ViewElement::default());
+        let text = app
+            .world
+            .spawn((TextBundle::default(), ViewText::default()))
+            .id();
+        app.world.entity_mut(with_text).push_children(&[text]);
+        app.update();
+
+        // No update is issued for an element with nothing to apply.
+        assert!(app.world.get::<LastComputedStyle>(unstyled).is_none());
+        assert!(app.world.get::<StyleHandlesChanged>(unstyled).is_none());
+        assert!(app.world.get::<LastComputedStyle>(styled).is_some());
+        assert!(app.world.get::<LastComputedStyle>(with_text).is_some());
+
+        assert!(ComputedStyle::default().is_default());
+        let mut computed = ComputedStyle::default();
+        StyleAttr::Width(Val::Px(10.)).apply(&mut computed);
+        assert!(!computed.is_default());
+    }
+
+    #[derive(Component)]
+    struct CanvasController;
+
+    #[derive(Component)]
+    struct Relayout(StyleFields);
+
+    impl Controller for CanvasController {
+        fn style_changed(
+            &self,
+            commands: &mut Commands,
+            entity: Entity,
+            _view: &ViewElement,
+            changed: StyleFields,
+        ) {
+            commands.entity(entity).insert(Relayout(changed));
+        }
+    }
+
+    #[test]
+    fn test_style_changed() {
+        use bevy_trait_query::RegisterExt;
+
+        let mut app = test_app();
+        app.add_event::<StyleChanged>()
+            .register_component_as::<dyn Controller, CanvasController>()
+            .add_systems(Update, notify_style_changes);
+        let entity = app
+            .world
+            .spawn((
+                ViewElement::default(),
+                CanvasController,
+                NodeBundle::default(),
+            ))
+            .id();
+        let restyle = |app: &mut App, width, height| {
+            let mut computed = ComputedStyle::default();
+            StyleAttr::Width(Val::Px(width)).apply(&mut computed);
+            StyleAttr::Height(Val::Px(height)).apply(&mut computed);
+            UpdateComputedStyle { entity, computed }.apply(&mut app.world);
+            app.update();
+            app.world.entity_mut(entity).take::<Relayout>().map(|r| r.0)
+        };
+
+        let mut both = StyleFields::default();
+        both.insert(StyleField::Width);
+        both.insert(StyleField::Height);
+        assert_eq!(restyle(&mut app, 10., 10.), Some(both));
+
+        // Only the width changed.
+        let mut width = StyleFields::default();
+        width.insert(StyleField::Width);
+        assert_eq!(restyle(&mut app, 20., 10.), Some(width));
+
+        // Nothing changed, so the controller isn't called.
+        assert_eq!(restyle(&mut app, 20., 10.), None);
+    }
+
+    #[test]
+    fn test_media_styles() {
+        let mut app = test_app();
+        app.add_event::<WindowResized>()
+            .init_resource::<GuiseSettings>()
+            .init_resource::<GuiseTheme>()
+            .add_systems(Update, update_media_styles);
+        let mut style = PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(100.))]);
+        style.add_selector(
+            Selector::WindowWidth(600., f32::INFINITY),
+            PartialStyle::from_attrs(&[StyleAttr::Width(Val::Px(300.))]),
+        );
+        let handle = app.world.resource_mut::<Assets<PartialStyle>>().add(style);
+        let entity = app
+            .world
+            .spawn(ViewElement {
+                style: Some(handle),
+                ..default()
+            })
+            .id();
+        let window = app.world.spawn((Window::default(), PrimaryWindow)).id();
+
+        let resize = |app: &mut App, width: f32| {
+            app.world
+                .get_mut::<Window>(window)
+                .unwrap()
+                .resolution
+                .set(width, 400.);
+            app.world.send_event(WindowResized {
+                window,
+                width,
+                height: 400.,
+            });
+            app.update();
+            app.world
+                .entity_mut(entity)
+                .take::<StyleHandlesChanged>()
+                .is_some()
+        };
+        let width = |world: &World, window_width| {
+            let ctx = StyleContext {
+                assets: world.resource::<Assets<PartialStyle>>(),
+                default_style: None,
+                theme: None,
+                window_width,
+            };
+            let mut computed = ComputedStyle::default();
+            world
+                .get::<ViewElement>(entity)
+                .unwrap()
+                .compute_style(&mut computed, &ctx);
+            computed.style.width
+        };
+
+        assert!(!resize(&mut app, 400.));
+        assert_eq!(width(&app.world, Some(400.)), Val::Px(100.));
+
+        // Crossing the breakpoint restyles the element, and the media style applies.
+        assert!(resize(&mut app, 800.));
+        assert_eq!(width(&app.world, Some(800.)), Val::Px(300.));
+
+        // Resizing within the breakpoint doesn't.
+        assert!(!resize(&mut app, 900.));
+        assert!(resize(&mut app, 500.));
+    }
+
+    #[test]
+    fn test_interaction_classes() {
+        let mut app = test_app();
+        let mut style = PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::RED))]);
+        style.add_selector(
+            Selector::parse("self.hover").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::BLUE))]),
+        );
+        let handle = app.world.resource_mut::<Assets<PartialStyle>>().add(style);
+        let entity = app
+            .world
+            .spawn((
+                ViewElement {
+                    style: Some(handle),
+                    ..default()
+                },
+                Interaction::None,
+            ))
+            .id();
+        app.add_systems(Update, update_interaction_classes);
+
+        let background = |world: &World| computed_background(world, entity);
+
+        app.update();
+        assert_eq!(background(&app.world), Some(Color::RED));
+
+        *app.world.get_mut::<Interaction>(entity).unwrap() = Interaction::Hovered;
+        app.update();
+        assert_eq!(background(&app.world), Some(Color::BLUE));
+        assert!(app.world.get::<StyleHandlesChanged>(entity).is_some());
+
+        *app.world.get_mut::<Interaction>(entity).unwrap() = Interaction::None;
+        app.update();
+        assert_eq!(background(&app.world), Some(Color::RED));
+    }
+
+    #[test]
+    fn test_insert_interaction() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+        let button = ElementNode {
+            controller: Some("ButtonController".to_string()),
+            ..default()
+        };
+        let mut template = Template::new();
+        template.children = vec![
+            Box::new(TemplateNode::Element(button)),
+            element("decoration"),
+        ];
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        // Elements with controllers are pickable; purely decorative ones aren't.
+        let children = app.world.get::<Children>(root).unwrap().to_vec();
+        assert!(app.world.get::<Interaction>(children[0]).is_some());
+        assert!(app.world.get::<Interaction>(children[1]).is_none());
+        let controller_name = |entity| {
+            let view = app.world.get::<ViewElement>(entity).unwrap();
+            view.controller_name().map(str::to_string)
+        };
+        assert_eq!(
+            controller_name(children[0]).as_deref(),
+            Some("ButtonController")
+        );
+        assert_eq!(controller_name(children[1]), None);
+
+        // So are elements with hover or active styles, once their styles are known.
+        let mut style = PartialStyle::new();
+        style.add_selector(
+            Selector::parse("self.hover").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::BLUE))]),
+        );
+        let hoverable = ViewElement {
+            inline_styles: Some(Arc::new(style)),
+            ..default()
+        };
+        let ctx = StyleContext {
+            assets: app.world.resource::<Assets<PartialStyle>>(),
+            default_style: None,
+            theme: None,
+            window_width: None,
+        };
+        assert!(hoverable.uses_interaction_classes(&ctx));
+        assert!(!ViewElement::default().uses_interaction_classes(&ctx));
+
+        // An existing interaction state is left alone.
+        let entity = app.world.spawn(Interaction::Hovered).id();
+        InsertInteraction { entity }.apply(&mut app.world);
+        assert_eq!(
+            app.world.get::<Interaction>(entity),
+            Some(&Interaction::Hovered)
+        );
+        let entity = app.world.spawn_empty().id();
+        InsertInteraction { entity }.apply(&mut app.world);
+        assert_eq!(
+            app.world.get::<Interaction>(entity),
+            Some(&Interaction::None)
+        );
+    }
+
+    /// Marker standing in for a pointer click, which picking would deliver as an event.
+    #[derive(Component)]
+    struct Clicked;
+
+    fn toggle_on_click(mut commands: Commands, query: Query<Entity, With<Clicked>>) {
+        for entity in query.iter() {
+            commands.entity(entity).remove::<Clicked>();
+            commands.add(ToggleClass {
+                entity,
+                class: "active".to_string(),
+            });
+        }
+    }
+
+    #[test]
+    fn test_toggle_class() {
+        let mut app = test_app();
+        let mut style = PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::RED))]);
+        style.add_selector(
+            Selector::parse("self.active").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::BackgroundColor(Some(Color::BLUE))]),
+        );
+        let handle = app.world.resource_mut::<Assets<PartialStyle>>().add(style);
+        let entity = app
+            .world
+            .spawn(ViewElement {
+                style: Some(handle),
+                ..default()
+            })
+            .id();
+        app.add_systems(Update, toggle_on_click);
+
+        let background = |world: &World| computed_background(world, entity);
+        let active = |world: &World| {
+            world
+                .get::<ViewElement>(entity)
+                .unwrap()
+                .has_class("active")
+        };
+
+        app.update();
+        assert_eq!(background(&app.world), Some(Color::RED));
+        assert!(app.world.get::<StyleHandlesChanged>(entity).is_none());
+
+        app.world.entity_mut(entity).insert(Clicked);
+        app.update();
+        assert!(active(&app.world));
+        assert_eq!(background(&app.world), Some(Color::BLUE));
+        assert!(app.world.get::<StyleHandlesChanged>(entity).is_some());
+
+        app.world.entity_mut(entity).remove::<StyleHandlesChanged>();
+        app.world.entity_mut(entity).insert(Clicked);
+        app.update();
+        assert!(!active(&app.world));
+        assert_eq!(background(&app.world), Some(Color::RED));
+        assert!(app.world.get::<StyleHandlesChanged>(entity).is_some());
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    struct Range {
+        min: f32,
+        max: f32,
+    }
+
+    #[derive(Reflect, Component, Default)]
+    #[reflect(Component)]
+    struct SliderController {
+        label: String,
+        range: Range,
+        show_value: bool,
+        role: String,
+    }
+
+    #[test]
+    fn test_controller_attrs() {
+        let mut app = test_app();
+        app.register_type::<SliderController>()
+            .register_type::<Range>();
+        let entity = app.world.spawn_empty().id();
+
+        let mut attrs = HashMap::new();
+        attrs.insert("label".to_string(), "Volume".to_string());
+        attrs.insert(
+            "range".to_string(),
+            r#"{ "min": 0.0, "max": 11.0 }"#.to_string(),
+        );
+        attrs.insert("show-value".to_string(), "true".to_string());
+        attrs.insert("unused".to_string(), "1".to_string());
+        attrs.insert("role".to_string(), "slider".to_string());
+        InsertController {
+            entity,
+            controller: "SliderController".to_string(),
+            attrs,
+        }
+        .apply(&mut app.world);
+
+        let slider = app.world.get::<SliderController>(entity).unwrap();
+        assert_eq!(slider.label, "Volume");
+        assert_eq!(slider.range, Range { min: 0., max: 11. });
+        assert!(slider.show_value);
+
+        // Attributes which guise uses itself don't reach the controller.
+        assert_eq!(slider.role, "");
+    }
+
+    /// Add a system which reconciles `template` into the children of `root`, as if it had been
+    /// loaded from `path`.
+    fn add_reconcile(app: &mut App, root: Entity, path: &'static str, template: Template) {
+        app.add_systems(
+            Update,
+            move |mut commands: Commands,
+                  server: Res<AssetServer>,
+                  templates: Res<Assets<Template>>,
+                  mut view_query: Query<(&mut ViewElement, Option<&Children>)>,
+                  generated_query: Query<(), With<GeneratedContent>>| {
+                reconcile_template(
+                    &mut commands,
+                    &server,
+                    &AssetPath::from(path),
+                    None,
+                    root,
+                    None,
+                    &template.children,
+                    &templates,
+                    &mut view_query,
+                    &generated_query,
+                );
+            },
+        );
+    }
+
+    /// Reconcile `template` into the existing children of `root` once, as a reload of `path`
+    /// would.
+    fn reconcile_again(app: &mut App, root: Entity, path: &'static str, template: Template) {
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            move |mut commands: Commands,
+                  server: Res<AssetServer>,
+                  templates: Res<Assets<Template>>,
+                  children_query: Query<&Children>,
+                  mut view_query: Query<(&mut ViewElement, Option<&Children>)>,
+                  generated_query: Query<(), With<GeneratedContent>>| {
+                reconcile_template(
+                    &mut commands,
+                    &server,
+                    &AssetPath::from(path),
+                    None,
+                    root,
+                    children_query.get(root).ok(),
+                    &template.children,
+                    &templates,
+                    &mut view_query,
+                    &generated_query,
+                );
+            },
+        );
+        schedule.run(&mut app.world);
+    }
+
+    #[test]
+    fn test_accessibility() {
+        use bevy::a11y::accesskit::NodeClassSet;
+
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+        let mut template = Template::new();
+        let mut close = ElementNode::default();
+        close.attrs.insert("role".to_string(), "button".to_string());
+        close
+            .attrs
+            .insert("aria-label".to_string(), "Close".to_string());
+        let mut title = ElementNode::default();
+        title
+            .attrs
+            .insert("aria-label".to_string(), "Title".to_string());
+        template.children = vec![
+            Box::new(TemplateNode::Element(close)),
+            Box::new(TemplateNode::Element(title)),
+            element("plain"),
+        ];
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        let children = app.world.get::<Children>(root).unwrap().to_vec();
+        let node = |entity| {
+            app.world
+                .get::<AccessibilityNode>(entity)
+                .map(|node| node.0.clone().build(&mut NodeClassSet::new()))
+        };
+        let close = node(children[0]).unwrap();
+        assert_eq!(close.role(), Role::Button);
+        assert_eq!(close.name(), Some("Close"));
+        let title = node(children[1]).unwrap();
+        assert_eq!(title.role(), Role::GenericContainer);
+        assert_eq!(title.name(), Some("Title"));
+        assert!(node(children[2]).is_none());
+
+        // Removing the attributes removes the node.
+        let mut template = Template::new();
+        template.children = vec![element("close"), element("title"), element("plain")];
+        reconcile_again(&mut app, root, "ui/dialog.guise.xml#main", template);
+        assert_eq!(app.world.get::<Children>(root).unwrap().to_vec(), children);
+        assert!(children
+            .iter()
+            .all(|child| app.world.get::<AccessibilityNode>(*child).is_none()));
+    }
+
+    #[test]
+    fn test_view_source() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+        let mut template = Template::new();
+        template
+            .children
+            .push(Box::new(TemplateNode::Element(ElementNode::default())));
+
+        add_reconcile(&mut app, root, "ui/buttons.guise.xml#primary", template);
+        app.update();
+
+        let children = app.world.get::<Children>(root).unwrap();
+        let source = app.world.get::<ViewSource>(children[0]).unwrap();
+        assert_eq!(source.path, PathBuf::from("ui/buttons.guise.xml"));
+        assert_eq!(source.label.as_deref(), Some("primary"));
+        assert_eq!(
+            source.asset_path(),
+            AssetPath::from("ui/buttons.guise.xml#primary")
+        );
+    }
+
+    #[test]
+    fn test_style_path() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+        let mut template = Template::new();
+        for style in ["#panel", "./common.guise.xml#button"] {
+            let mut elt = ElementNode::default();
+            elt.attrs.insert("style".to_string(), style.to_string());
+            template.children.push(Box::new(TemplateNode::Element(elt)));
+        }
+        template
+            .children
+            .push(Box::new(TemplateNode::Element(ElementNode::default())));
+
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        let server = app.world.resource::<AssetServer>();
+        let children = app.world.get::<Children>(root).unwrap();
+        let paths: Vec<Option<AssetPath>> = children
+            .iter()
+            .map(|child| {
+                app.world
+                    .get::<ViewElement>(*child)
+                    .unwrap()
+                    .style_path(server)
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                Some(AssetPath::from("ui/dialog.guise.xml#panel")),
+                Some(AssetPath::from("ui/common.guise.xml#button")),
+                None,
+            ]
+        );
+    }
+
+    fn element(id: &str) -> Box<TemplateNode> {
+        Box::new(TemplateNode::Element(ElementNode {
+            id: Some(id.to_string()),
+            ..default()
+        }))
+    }
+
+    fn include(template: &Handle<Template>) -> Box<TemplateNode> {
+        include_with(template, &[])
+    }
+
+    fn include_with(template: &Handle<Template>, params: &[(&str, &str)]) -> Box<TemplateNode> {
+        Box::new(TemplateNode::Include(IncludeNode {
+            src: "#included".to_string(),
+            template: template.clone_weak(),
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }))
+    }
+
+    #[test]
+    fn test_last_child() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+
+        let mut style = PartialStyle::from_attrs(&[StyleAttr::MarginBottom(Val::Px(8.))]);
+        style.add_selector(
+            Selector::parse("self:last-child").unwrap(),
+            PartialStyle::from_attrs(&[StyleAttr::MarginBottom(Val::Px(0.))]),
+        );
+        app.world
+            .resource_mut::<Assets<PartialStyle>>()
+            .set_untracked(AssetPath::from("ui/list.guise.xml#row"), style);
+
+        let mut template = Template::new();
+        for _ in 0..3 {
+            let mut elt = ElementNode::default();
+            elt.attrs.insert("style".to_string(), "#row".to_string());
+            template.children.push(Box::new(TemplateNode::Element(elt)));
+        }
+        add_reconcile(&mut app, root, "ui/list.guise.xml#main", template);
+        app.update();
+
+        let ctx = StyleContext {
+            assets: app.world.resource::<Assets<PartialStyle>>(),
+            default_style: None,
+            theme: None,
+            window_width: None,
+        };
+        let margins: Vec<Val> = app
+            .world
+            .get::<Children>(root)
+            .unwrap()
+            .iter()
+            .map(|child| {
+                let mut computed = ComputedStyle::default();
+                app.world
+                    .get::<ViewElement>(*child)
+                    .unwrap()
+                    .compute_style(&mut computed, &ctx);
+                computed.style.margin.bottom
+            })
+            .collect();
+        assert_eq!(margins, vec![Val::Px(8.), Val::Px(8.), Val::Px(0.)]);
+    }
+
+    #[test]
+    fn test_include() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+
+        let mut header = Template::new();
+        header.children.push(element("title"));
+        header.children.push(Box::new(TemplateNode::Text(TextNode {
+            content: "Hello".to_string(),
+            ..default()
+        })));
+        header.children.push(element("close"));
+        let mut templates = app.world.resource_mut::<Assets<Template>>();
+        let header = templates.add(header);
+
+        // A template which includes itself is skipped rather than expanded forever.
+        let cyclic = templates.add(Template::new());
+        let mut cyclic_template = Template::new();
+        cyclic_template.children.push(include(&cyclic));
+        templates.set_untracked(&cyclic, cyclic_template);
+
+        let mut template = Template::new();
+        template.children.push(include(&header));
+        template.children.push(element("body"));
+        template.children.push(include(&cyclic));
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        let children = app.world.get::<Children>(root).unwrap();
+        assert_eq!(children.len(), 4);
+        let view = |i: usize| app.world.get::<ViewElement>(children[i]);
+        assert_eq!(view(0).unwrap().element_id(), "title");
+        assert_eq!(
+            app.world.get::<ViewText>(children[1]).unwrap().source,
+            "Hello"
+        );
+        assert_eq!(view(2).unwrap().element_id(), "close");
+        assert_eq!(view(3).unwrap().element_id(), "body");
+        assert_eq!(view(3).unwrap().sibling_index, 2);
+    }
+
+    #[test]
+    fn test_include_params() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+
+        let mut counter = Template::new();
+        counter
+            .params
+            .insert("count".to_string(), TemplateParam::new(ParamType::Number));
+        counter.children.push(element("counter"));
+        let counter = app.world.resource_mut::<Assets<Template>>().add(counter);
+
+        // An include whose param has the wrong type is left out.
+        let mut template = Template::new();
+        template
+            .children
+            .push(include_with(&counter, &[("count", "three")]));
+        template
+            .children
+            .push(include_with(&counter, &[("count", "3")]));
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        let children = app.world.get::<Children>(root).unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(
+            app.world
+                .get::<ViewElement>(children[0])
+                .unwrap()
+                .element_id(),
+            "counter"
+        );
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let mut app = test_app();
+        let root = app.world.spawn(NodeBundle::default()).id();
+
+        // Two templates, standing in for two files, which include each other.
+        let mut templates = app.world.resource_mut::<Assets<Template>>();
+        let first = templates.add(Template::new());
+        let second = templates.add(Template::new());
+        let mut first_template = Template::new();
+        first_template.children.push(element("first"));
+        first_template.children.push(include(&second));
+        templates.set_untracked(&first, first_template);
+        let mut second_template = Template::new();
+        second_template.children.push(include(&first));
+        templates.set_untracked(&second, second_template);
+
+        let mut template = Template::new();
+        template.children.push(include(&first));
+        template.children.push(element("body"));
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        let children = app.world.get::<Children>(root).unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(
+            app.world
+                .get::<ViewElement>(children[0])
+                .unwrap()
+                .element_id(),
+            "body"
+        );
+    }
+
+    #[test]
+    fn test_include_cycle_between_files() {
+        use crate::guise::asset::GuiseLoader;
+        use crate::guise::GuiseDocument;
+
+        let mut app = test_app();
+        app.add_asset::<GuiseDocument>()
+            .add_asset_loader(GuiseLoader::default());
+        let root = app.world.spawn(NodeBundle::default()).id();
+
+        // Each file's template includes the other's.
+        let server = app.world.resource::<AssetServer>();
+        let first: Handle<Template> = server.load("editor/ui/tests/cycle-first.guise.xml#first");
+        let second: Handle<Template> = server.load("editor/ui/tests/cycle-second.guise.xml#second");
+        for _ in 0..200 {
+            app.update();
+            let templates = app.world.resource::<Assets<Template>>();
+            if templates.contains(&first) && templates.contains(&second) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let mut template = Template::new();
+        template.children.push(include(&first));
+        template.children.push(element("body"));
+        add_reconcile(&mut app, root, "ui/dialog.guise.xml#main", template);
+        app.update();
+
+        // The cycle is skipped, rather than expanded until the stack overflows.
+        let children = app.world.get::<Children>(root).unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(
+            app.world
+                .get::<ViewElement>(children[0])
+                .unwrap()
+                .element_id(),
+            "body"
+        );
+    }
 
     #[test]
     fn test_relative_path() {
         let base = AssetPath::from("alice/bob#carol");
         assert_eq!(
-            relative_asset_path(&base, "joe/next"),
+            relative_asset_path(&base, None, "joe/next"),
             AssetPath::from("joe/next")
         );
         assert_eq!(
-            relative_asset_path(&base, "#dave"),
+            relative_asset_path(&base, None, "#dave"),
             AssetPath::from("alice/bob#dave")
         );
         assert_eq!(
-            relative_asset_path(&base, "./martin#dave"),
+            relative_asset_path(&base, None, "./martin#dave"),
             AssetPath::from("alice/martin#dave")
         );
         assert_eq!(
-            relative_asset_path(&base, "../martin#dave"),
+            relative_asset_path(&base, None, "../martin#dave"),
             AssetPath::from("martin#dave")
         );
     }
+
+    #[test]
+    fn test_relative_path_base_dir() {
+        let base = AssetPath::from("alice/bob#carol");
+        let base_dir = Some(Path::new("ui/widgets"));
+        assert_eq!(
+            relative_asset_path(&base, base_dir, "./martin#dave"),
+            AssetPath::from("ui/widgets/martin#dave")
+        );
+        assert_eq!(
+            relative_asset_path(&base, base_dir, "../martin#dave"),
+            AssetPath::from("ui/martin#dave")
+        );
+
+        // Labels and absolute paths are unaffected.
+        assert_eq!(
+            relative_asset_path(&base, base_dir, "#dave"),
+            AssetPath::from("alice/bob#dave")
+        );
+        assert_eq!(
+            relative_asset_path(&base, base_dir, "joe/next"),
+            AssetPath::from("joe/next")
+        );
+    }
 }
@@ -124,7 +124,7 @@ fn main() {
             WorldPlugin,
             TerrainPlugin,
             InstancedModelsPlugin,
-            GuisePlugin,
+            GuisePlugin::default(),
             // WorldInspectorPlugin::new(),
         ))
         .run();